@@ -0,0 +1,298 @@
+use turbo::*;
+
+use crate::draw::DrawList;
+use crate::{GameEvent, GameMode, PowerUpType, GameState, COLOR_AURORA_GREEN, COLOR_STAR, COLOR_TEXT, COLOR_UI_BG};
+
+// Rows that fit on the gallery at once
+const VISIBLE_ACHIEVEMENTS: usize = 5;
+
+// How long an unlock popup stays up
+const TOAST_FRAMES: u32 = 150;
+
+pub(crate) struct Achievement {
+    // Stored in the save, so never rename one
+    pub(crate) id: &'static str,
+    pub(crate) name: &'static str,
+    pub(crate) description: &'static str,
+    goal: Goal,
+}
+
+enum Goal {
+    // Combo reached in a run
+    Combo(u32),
+    // Score reached in a run
+    Score(u32),
+    // Obstacles crushed without the shield running out
+    ShieldCrushes(u32),
+    // All four kinds of gift picked up in one run
+    AllGifts,
+    // A run finished with every star collected, and at least this many of them
+    EveryStar(u32),
+    // Lifetime totals
+    TotalStars(u32),
+    RunsPlayed(u32),
+    // Three stars on every campaign level
+    CampaignMastered,
+}
+
+pub(crate) const ACHIEVEMENTS: &[Achievement] = &[
+    Achievement {
+        id: "combo_10",
+        name: "Warming Up",
+        description: "Reach a 10x combo",
+        goal: Goal::Combo(10),
+    },
+    Achievement {
+        id: "combo_50",
+        name: "Unstoppable",
+        description: "Reach a 50x combo",
+        goal: Goal::Combo(50),
+    },
+    Achievement {
+        id: "score_5000",
+        name: "High Flyer",
+        description: "Score 5000 in one run",
+        goal: Goal::Score(5000),
+    },
+    Achievement {
+        id: "score_20000",
+        name: "Aurora Legend",
+        description: "Score 20000 in one run",
+        goal: Goal::Score(20000),
+    },
+    Achievement {
+        id: "shield_crush_10",
+        name: "Snowplough",
+        description: "Crush 10 obstacles with one shield",
+        goal: Goal::ShieldCrushes(10),
+    },
+    Achievement {
+        id: "all_gifts",
+        name: "Gift Wrapped",
+        description: "Open all four gifts in one run",
+        goal: Goal::AllGifts,
+    },
+    Achievement {
+        id: "every_star",
+        name: "Star Catcher",
+        description: "Finish a run without missing a star (10+)",
+        goal: Goal::EveryStar(10),
+    },
+    Achievement {
+        id: "stars_500",
+        name: "Stargazer",
+        description: "Collect 500 stars in total",
+        goal: Goal::TotalStars(500),
+    },
+    Achievement {
+        id: "runs_50",
+        name: "Regular",
+        description: "Play 50 runs",
+        goal: Goal::RunsPlayed(50),
+    },
+    Achievement {
+        id: "campaign_mastered",
+        name: "Summit",
+        description: "Three stars on every campaign level",
+        goal: Goal::CampaignMastered,
+    },
+];
+
+// What player one has done this run towards the in-run goals
+#[turbo::serialize]
+#[derive(Default)]
+pub(crate) struct RunProgress {
+    // Obstacles crushed by the shield player one has on now
+    shield_crushes: u32,
+    stars_missed: u32,
+    // One bit per PowerUpType picked up
+    gifts: u8,
+}
+
+// Popup for an unlock or a completed mission
+#[turbo::serialize]
+pub(crate) struct Toast {
+    title: String,
+    text: String,
+    frames: u32,
+}
+
+impl Toast {
+    pub(crate) fn new(title: &str, text: String) -> Self {
+        Self {
+            title: title.to_string(),
+            text,
+            frames: TOAST_FRAMES,
+        }
+    }
+}
+
+impl GameState {
+    // Fold this frame's events into the run's progress and unlock anything earned mid-run
+    pub(crate) fn track_achievements(&mut self, events: &[GameEvent]) {
+        let progress = &mut self.run_progress;
+        for event in events.iter().filter(|event| event.is_local()) {
+            match event {
+                GameEvent::ObstacleCrushed(_) => progress.shield_crushes += 1,
+                GameEvent::StarMissed => progress.stars_missed += 1,
+                GameEvent::PowerUpCollected(_, kind) => {
+                    progress.gifts |= kind.bit();
+                    // A fresh shield starts a fresh count, even on top of one that's still up
+                    if *kind == PowerUpType::Shield {
+                        progress.shield_crushes = 0;
+                    }
+                }
+                _ => {}
+            }
+        }
+        if !self.players[0].has_shield {
+            progress.shield_crushes = 0;
+        }
+        self.check_achievements(false);
+    }
+
+    // Unlock every goal that's been met; end-of-run goals only count once the run is over
+    pub(crate) fn check_achievements(&mut self, run_over: bool) {
+        let earned: Vec<&Achievement> = ACHIEVEMENTS
+            .iter()
+            .filter(|achievement| !self.save.has_achievement(achievement.id) && self.met(&achievement.goal, run_over))
+            .collect();
+        for achievement in &earned {
+            self.save.unlock_achievement(achievement.id);
+            self.toasts.push(Toast::new("ACHIEVEMENT UNLOCKED", achievement.name.to_uppercase()));
+        }
+        if !earned.is_empty() {
+            audio::play("combo-milestone");
+            self.write_save();
+        }
+    }
+
+    fn met(&self, goal: &Goal, run_over: bool) -> bool {
+        let progress = &self.run_progress;
+        match *goal {
+            Goal::Combo(combo) => self.player().max_combo >= combo,
+            Goal::Score(score) => self.player().score >= score,
+            Goal::ShieldCrushes(crushes) => progress.shield_crushes >= crushes,
+            Goal::AllGifts => progress.gifts & 0b1111 == 0b1111,
+            Goal::EveryStar(stars) => {
+                run_over && progress.stars_missed == 0 && self.player().stars_collected >= stars
+            }
+            Goal::TotalStars(stars) => self.save.total_stars >= stars,
+            Goal::RunsPlayed(runs) => self.save.runs_played >= runs,
+            Goal::CampaignMastered => {
+                !self.campaign.is_empty()
+                    && self
+                        .campaign
+                        .iter()
+                        .all(|level| self.save.level_best(&level.name).is_some_and(|best| best.stars >= 3))
+            }
+        }
+    }
+
+    pub(crate) fn update_toasts(&mut self) {
+        if let Some(toast) = self.toasts.first_mut() {
+            toast.frames -= 1;
+            if toast.frames == 0 {
+                self.toasts.remove(0);
+            }
+        }
+    }
+
+    // Oldest popup slides down from the top, then back up
+    pub(crate) fn render_toast(&self, draw: &mut DrawList) {
+        let Some(toast) = self.toasts.first() else {
+            return;
+        };
+        let shown = TOAST_FRAMES - toast.frames;
+        let slide = shown.min(toast.frames).min(12) as i32;
+        let y = slide * 2 - 22;
+        draw.rect(58, y, 140, 20, 0x000000dd);
+        draw.rect(58, y + 19, 140, 1, COLOR_STAR);
+        draw.text(toast.title.clone(), 70, y + 3, "small", COLOR_STAR);
+        draw.text(toast.text.clone(), 70, y + 11, "small", COLOR_TEXT);
+    }
+
+    pub(crate) fn open_achievements(&mut self) {
+        self.achievement_cursor = 0;
+        self.mode = GameMode::Achievements;
+    }
+
+    pub(crate) fn update_achievements(&mut self, gp: &gamepad::Gamepad) {
+        self.update_backdrop();
+
+        if gp.up.just_pressed() {
+            self.achievement_cursor = self.achievement_cursor.saturating_sub(1);
+        }
+        if gp.down.just_pressed() && self.achievement_cursor + 1 < ACHIEVEMENTS.len() {
+            self.achievement_cursor += 1;
+        }
+        if gp.left.just_pressed() {
+            self.open_rules();
+        }
+        if gp.right.just_pressed() {
+            self.open_shop();
+        }
+        if gp.b.just_pressed() || gp.start.just_pressed() {
+            self.mode = GameMode::Title;
+        }
+    }
+
+    pub(crate) fn render_achievements(&self, draw: &mut DrawList) {
+        let unlocked = ACHIEVEMENTS.iter().filter(|achievement| self.save.has_achievement(achievement.id)).count();
+        draw.text("ACHIEVEMENTS", 62, 10, "large", COLOR_AURORA_GREEN);
+        draw.text(format!("{unlocked}/{} UNLOCKED", ACHIEVEMENTS.len()), 92, 26, "small", COLOR_TEXT);
+
+        let first = self.achievement_cursor.saturating_sub(VISIBLE_ACHIEVEMENTS - 1);
+        for (row, (i, achievement)) in ACHIEVEMENTS.iter().enumerate().skip(first).take(VISIBLE_ACHIEVEMENTS).enumerate() {
+            let y = 38 + row as i32 * 17;
+            let has = self.save.has_achievement(achievement.id);
+            if i == self.achievement_cursor {
+                draw.rect(16, y - 2, 224, 16, COLOR_UI_BG);
+                draw.text(">", 20, y + 2, "small", COLOR_STAR);
+            }
+            draw.sprite("star", 28, y + 1, if has { 1.0 } else { 0.25 });
+            let name_color = if has { COLOR_STAR } else { 0x888888ff };
+            draw.text(achievement.name.to_uppercase(), 42, y - 1, "small", name_color);
+            draw.text(achievement.description, 42, y + 6, "small", if has { COLOR_TEXT } else { 0x888888ff });
+        }
+
+        draw.text("[UP/DN] Scroll [LEFT] Rules [RIGHT] Shop [B] Back", 6, 130, "small", 0xaaaaaaff);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::start;
+    use crate::RunKind;
+
+    fn crushes(player: usize, count: usize) -> Vec<GameEvent> {
+        vec![GameEvent::ObstacleCrushed(player); count]
+    }
+
+    #[test]
+    fn player_twos_play_unlocks_nothing() {
+        let mut state = start(RunKind::Versus, 4);
+        let two = &mut state.players[1];
+        two.has_shield = true;
+        two.max_combo = 50;
+        two.score = 20000;
+        state.track_achievements(&crushes(1, 10));
+        let gifts = [PowerUpType::Shield, PowerUpType::SlowMo, PowerUpType::Magnet, PowerUpType::DoublePoints];
+        let events: Vec<GameEvent> = gifts.into_iter().map(|kind| GameEvent::PowerUpCollected(1, kind)).collect();
+        state.track_achievements(&events);
+        assert!(state.save.achievements.is_empty());
+    }
+
+    #[test]
+    fn a_new_shield_starts_a_new_crush_count() {
+        let mut state = start(RunKind::Endless, 4);
+        state.players[0].has_shield = true;
+        state.track_achievements(&crushes(0, 9));
+        state.track_achievements(&[GameEvent::PowerUpCollected(0, PowerUpType::Shield)]);
+        state.track_achievements(&crushes(0, 9));
+        assert!(!state.save.has_achievement("shield_crush_10"));
+        state.track_achievements(&crushes(0, 1));
+        assert!(state.save.has_achievement("shield_crush_10"));
+    }
+}
@@ -0,0 +1,80 @@
+use turbo::*;
+
+use crate::replay::fnv1a;
+
+// JSON assets kept next to turbo.toml: levels.json, tuning.json and ghosts.json
+//
+// Turbo games can't read files at runtime, so each one is compiled in with `include_str!` and an edit
+// takes a rebuild. Under `turbo run -w` the rebuilt game is hot-reloaded without restarting the session:
+// every frame the game compares each asset's `fingerprint` with the one it loaded and reloads on a change,
+// keeping what it had if the new file doesn't validate. (levels.json can also be replaced without a
+// rebuild by an editor export kept in the save.)
+
+// Problem in a JSON asset, pinned to where the parser was when it noticed
+#[derive(Debug, PartialEq)]
+pub(crate) struct AssetError {
+    pub(crate) path: &'static str,
+    pub(crate) line: usize,
+    pub(crate) column: usize,
+    pub(crate) message: String,
+}
+
+impl AssetError {
+    pub(crate) fn from_json(path: &'static str, err: serde_json::Error) -> Self {
+        let (line, column) = (err.line(), err.column());
+        let message = err.to_string();
+        let message = message
+            .strip_suffix(&format!(" at line {line} column {column}"))
+            .unwrap_or(&message)
+            .to_string();
+        Self { path, line, column, message }
+    }
+}
+
+impl std::fmt::Display for AssetError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // serde_json reports line 0 for problems it can't place (checks across a whole object)
+        if self.line == 0 {
+            write!(f, "{}: {}", self.path, self.message)
+        } else {
+            write!(f, "{}:{}:{}: {}", self.path, self.line, self.column, self.message)
+        }
+    }
+}
+
+// Parse and validate the asset at `path`
+pub(crate) fn parse<T: serde::de::DeserializeOwned>(path: &'static str, source: &str) -> Result<T, AssetError> {
+    serde_json::from_str(source).map_err(|err| AssetError::from_json(path, err))
+}
+
+// Fingerprint of an asset's compiled-in text, to notice when a hot reload brought in a new one
+pub(crate) fn fingerprint(source: &str) -> u64 {
+    fnv1a(source.as_bytes())
+}
+
+pub(crate) fn check_range<T>(field: &str, value: T, min: T, max: T) -> Result<T, String>
+where
+    T: Copy + PartialOrd + std::fmt::Display,
+{
+    if (min..=max).contains(&value) {
+        Ok(value)
+    } else {
+        Err(format!("`{field}` must be between {min} and {max}, got {value}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn problems_without_a_line_leave_it_out() {
+        let err = AssetError {
+            path: "levels.json",
+            line: 0,
+            column: 0,
+            message: "no chunks".to_string(),
+        };
+        assert_eq!(err.to_string(), "levels.json: no chunks");
+    }
+}
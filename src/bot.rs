@@ -0,0 +1,272 @@
+use std::ops::Range;
+
+use crate::reach::JumpEnvelope;
+use crate::replay::{Replay, RunResult, RunSetup};
+use crate::difficulty::Preset;
+use crate::save::SaveData;
+use crate::shop::Modifiers;
+use crate::{GameMode, GameState, InputFrame, RunKind};
+
+// How far ahead the bot simulates each option
+const LOOKAHEAD_FRAMES: u32 = 90;
+
+// Autoplayer that decides jumps by simulating its options ahead of time
+#[derive(Debug, Default)]
+pub(crate) struct Bot {
+    hold_left: u32,
+    // First frame of the current stretch where no option could get past what's on screen
+    cornered_at: Option<u32>,
+}
+
+impl Bot {
+    pub(crate) fn next_input(&mut self, state: &GameState) -> InputFrame {
+        // Keep holding a jump that's already under way
+        if self.hold_left > 0 {
+            self.hold_left -= 1;
+            return hold_input(false);
+        }
+        if state.mode != GameMode::Playing || !state.player().is_on_ground {
+            return InputFrame::default();
+        }
+
+        let state = &lookahead_base(state);
+        let envelope = JumpEnvelope::new(&state.tuning);
+        let max_hold = state.tuning.max_jump_hold;
+        let idle = Choice::evaluate(state, None, &envelope);
+        // Every hold length from a tap (1) to a full jump, same as the envelope considers
+        let jumps: Vec<Choice> = (1..=max_hold)
+            .map(|hold| Choice::evaluate(state, Some(hold), &envelope))
+            .collect();
+
+        // Only options that can still get past everything on screen,
+        // then whichever gains the most (stars and altitude make a jump worth it)
+        let safe_jump = jumps
+            .iter()
+            .filter(|choice| choice.safe)
+            .max_by_key(|choice| (choice.outcome.score_gained, std::cmp::Reverse(choice.hold)));
+        self.cornered_at = match (safe_jump, idle.safe) {
+            (None, false) => Some(self.cornered_at.unwrap_or(state.run_frames)),
+            _ => None,
+        };
+        let hold = match safe_jump {
+            Some(jump) if !idle.safe || jump.outcome.score_gained > idle.outcome.score_gained => jump.hold,
+            Some(_) => return InputFrame::default(),
+            None if idle.safe => return InputFrame::default(),
+            // Cornered - wait unless waiting is fatal right now, then take the longest-lived jump
+            None if simulate(state, None, LOOKAHEAD_FRAMES).survived > 1 => return InputFrame::default(),
+            None => (1..=max_hold)
+                .max_by_key(|&hold| simulate(state, Some(hold), LOOKAHEAD_FRAMES).survived)
+                .unwrap_or(max_hold),
+        };
+
+        self.hold_left = hold - 1;
+        hold_input(true)
+    }
+}
+
+// Copy of `state` without what `step` never reads (the save, replays, ghosts, effects),
+// so the dozens of copies made while weighing up options stay cheap
+fn lookahead_base(state: &GameState) -> GameState {
+    let mut base = state.clone();
+    base.save = SaveData::default();
+    base.replay = Replay::new(state.replay.setup(), state.sim_hash);
+    base.ghosts = Vec::new();
+    base.ghost = None;
+    base.particles = Vec::new();
+    base.floating_texts = Vec::new();
+    base.toasts = Vec::new();
+    // Only campaign runs read the level scripts
+    if !matches!(state.run_kind, RunKind::Campaign(_)) {
+        base.campaign = Vec::new();
+    }
+    base
+}
+
+// One option the bot is weighing up
+struct Choice {
+    hold: u32,
+    // The jump envelope can still get past every obstacle on screen after committing to this
+    safe: bool,
+    // Only simulated for safe options
+    outcome: Outcome,
+}
+
+impl Choice {
+    fn evaluate(state: &GameState, hold: Option<u32>, envelope: &JumpEnvelope) -> Self {
+        // Play through the release frame so the remaining arc is pinned down
+        let commit_frames = hold.map_or(1, |hold| hold + 1);
+        let mut committed = state.clone();
+        for frame in 0..commit_frames {
+            committed.step(&[jump_input(hold, frame)]);
+        }
+        let speed = if committed.slowed() {
+            committed.scroll_speed * 0.5
+        } else {
+            committed.scroll_speed
+        };
+        let safe = committed.mode == GameMode::Playing
+            && envelope.survivable(committed.player(), &committed.obstacles, speed);
+
+        // Carry on from the committed state rather than replaying the jump from scratch
+        let outcome = if safe {
+            play_out(committed, hold, commit_frames..LOOKAHEAD_FRAMES, state.player().score)
+        } else {
+            Outcome::default()
+        };
+
+        Self {
+            hold: hold.unwrap_or(0),
+            safe,
+            outcome,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+struct Outcome {
+    survived: u32,
+    score_gained: u32,
+}
+
+fn hold_input(first_frame: bool) -> InputFrame {
+    InputFrame {
+        up_pressed: true,
+        up_just_pressed: first_frame,
+    }
+}
+
+// Input on `frame` of an option: jump immediately holding for `hold` frames, otherwise idle
+fn jump_input(hold: Option<u32>, frame: u32) -> InputFrame {
+    match hold {
+        Some(hold) if frame < hold => hold_input(frame == 0),
+        _ => InputFrame::default(),
+    }
+}
+
+// Play `frames` frames of an option from `state`, idling afterwards
+fn simulate(state: &GameState, hold: Option<u32>, frames: u32) -> Outcome {
+    play_out(state.clone(), hold, 0..frames, state.player().score)
+}
+
+// Carry on an option already played up to `frames.start`, scoring against `start_score`
+fn play_out(mut sim: GameState, hold: Option<u32>, frames: Range<u32>, start_score: u32) -> Outcome {
+    let survived = frames.end;
+    for frame in frames {
+        sim.step(&[jump_input(hold, frame)]);
+        if sim.mode != GameMode::Playing {
+            return Outcome {
+                survived: frame + 1,
+                score_gained: 0,
+            };
+        }
+    }
+    Outcome {
+        survived,
+        score_gained: sim.player().score.saturating_sub(start_score),
+    }
+}
+
+// Headless bot run summary
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct BotRun {
+    pub(crate) seed: u32,
+    pub(crate) frames: u32,
+    pub(crate) distance: f32,
+    pub(crate) result: RunResult,
+    // Set when the bot died after the spawner left it nothing the jump envelope could get past
+    pub(crate) unclearable_at: Option<u32>,
+}
+
+// Let the bot play one seeded run for at most `max_frames`
+pub(crate) fn play(seed: u32, max_frames: u32) -> BotRun {
+    let mut state = GameState::new();
+    state.start_run(RunSetup {
+        seed,
+        tuning: state.tuning_config,
+        kind: RunKind::Endless,
+        modifiers: Modifiers::default(),
+        hearts: 1,
+        preset: Preset::Normal,
+    });
+    let mut bot = Bot::default();
+
+    while state.mode == GameMode::Playing && state.run_frames < max_frames {
+        let input = bot.next_input(&state);
+        state.step(&[input]);
+    }
+
+    let died = state.mode != GameMode::Playing;
+    BotRun {
+        seed,
+        frames: state.run_frames,
+        distance: state.distance,
+        result: state.run_result(),
+        unclearable_at: bot.cornered_at.filter(|_| died),
+    }
+}
+
+// Results of many bot runs
+#[derive(Debug, Default)]
+pub(crate) struct SoakReport {
+    pub(crate) runs: Vec<BotRun>,
+}
+
+impl SoakReport {
+    // Run counts per `bucket`-sized slice of distance travelled
+    pub(crate) fn distance_histogram(&self, bucket: f32) -> Vec<u32> {
+        let mut histogram = Vec::new();
+        for run in &self.runs {
+            let index = (run.distance / bucket.max(1.0)) as usize;
+            if histogram.len() <= index {
+                histogram.resize(index + 1, 0);
+            }
+            histogram[index] += 1;
+        }
+        histogram
+    }
+
+    // Runs that ended on a spawn sequence the bot couldn't have cleared
+    pub(crate) fn unclearable(&self) -> impl Iterator<Item = &BotRun> {
+        self.runs.iter().filter(|run| run.unclearable_at.is_some())
+    }
+}
+
+pub(crate) fn soak(seeds: Range<u32>, max_frames: u32) -> SoakReport {
+    SoakReport {
+        runs: seeds.map(|seed| play(seed, max_frames)).collect(),
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::start;
+
+    #[test]
+    fn bot_outlasts_an_idle_player() {
+        let mut idle = start(RunKind::Endless, 7);
+        while idle.mode == GameMode::Playing {
+            idle.step(&[InputFrame::default()]);
+        }
+        let run = play(7, idle.run_frames * 3);
+        assert_eq!(run.frames, idle.run_frames * 3);
+        assert!(run.result.score > idle.player().score);
+        assert_eq!(run.unclearable_at, None);
+    }
+
+    // Slow: cargo test --release soak -- --ignored
+    #[test]
+    #[ignore]
+    fn soak_finds_no_unclearable_spawns() {
+        let max_frames = 3000;
+        let report = soak(0..20, max_frames);
+        let unclearable: Vec<u32> = report.unclearable().map(|run| run.seed).collect();
+        assert!(unclearable.is_empty(), "seeds with unclearable spawns: {unclearable:?}");
+        // Every seed should see the speed ramp, not die in the opening chunks
+        let short: Vec<u32> = report.runs.iter().filter(|run| run.frames < max_frames).map(|run| run.seed).collect();
+        assert!(short.is_empty(), "bot died early on seeds {short:?}");
+        assert_eq!(report.distance_histogram(1000.0).iter().sum::<u32>(), 20);
+    }
+}
@@ -0,0 +1,315 @@
+use turbo::*;
+
+use crate::draw::DrawList;
+use crate::levels;
+use crate::replay::RunResult;
+use crate::{
+    apply_opacity, GameMode, GameState, RunKind, COLOR_AURORA_GREEN, COLOR_AURORA_RED, COLOR_STAR, COLOR_TEXT,
+    COLOR_UI_BG, GROUND_Y,
+};
+
+// Rows that fit on the level select screen at once
+const VISIBLE_LEVELS: usize = 6;
+
+// What it takes to earn one star of a level's rating
+#[turbo::serialize]
+#[derive(Copy, PartialEq, Default)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct Rating {
+    #[serde(skip_serializing_if = "levels::is_zero")]
+    pub(crate) score: u32,
+    #[serde(skip_serializing_if = "levels::is_zero")]
+    pub(crate) stars_collected: u32,
+}
+
+// Hand-built level: a fixed run of chunks (`script`) ending at a finish line `distance` px in
+#[turbo::serialize]
+#[serde(try_from = "levels::RawCampaignLevel")]
+pub(crate) struct CampaignLevel {
+    pub(crate) name: String,
+    pub(crate) distance: f32,
+    pub(crate) script: Vec<String>,
+    pub(crate) ratings: [Rating; 3],
+}
+
+impl CampaignLevel {
+    // Stars earned by finishing with `result`: one per rating met, in order
+    pub(crate) fn stars_for(&self, result: &RunResult) -> u32 {
+        self.ratings
+            .iter()
+            .take_while(|rating| result.score >= rating.score && result.stars_collected >= rating.stars_collected)
+            .count() as u32
+    }
+}
+
+impl GameState {
+    // Distance of the current run's finish line, if it has one
+    pub(crate) fn finish_distance(&self) -> Option<f32> {
+        match self.run_kind {
+            RunKind::Campaign(level) => self.campaign.get(level).map(|level| level.distance),
+            _ => None,
+        }
+    }
+
+    // Next chunk of the level's script, once it can be placed without boxing the player in
+    // Returns None when the script is used up, so the run coasts to the finish line
+    pub(crate) fn next_scripted_chunk(&mut self, level: usize) -> Option<usize> {
+        let name = self.campaign.get(level)?.script.get(self.script_index)?;
+        let index = self.chunks.iter().position(|chunk| &chunk.name == name)?;
+        if !self.chunk_fits(index) {
+            return None;
+        }
+        self.script_index += 1;
+        Some(index)
+    }
+
+    // The first level is always open; each one after unlocks once the previous has a star
+    pub(crate) fn level_unlocked(&self, level: usize) -> bool {
+        level == 0
+            || self
+                .campaign
+                .get(level - 1)
+                .and_then(|previous| self.save.level_best(&previous.name))
+                .is_some_and(|best| best.stars > 0)
+    }
+
+    pub(crate) fn open_level_select(&mut self) {
+        audio::stop("music-background");
+        if let RunKind::Campaign(level) = self.run_kind {
+            self.level_cursor = level;
+        }
+        self.level_cursor = self.level_cursor.min(self.campaign.len().saturating_sub(1));
+        self.mode = GameMode::LevelSelect;
+    }
+
+    pub(crate) fn update_level_select(&mut self, gp: &gamepad::Gamepad) {
+        self.update_backdrop();
+
+        if gp.up.just_pressed() {
+            self.level_cursor = self.level_cursor.saturating_sub(1);
+        }
+        if gp.down.just_pressed() && self.level_cursor + 1 < self.campaign.len() {
+            self.level_cursor += 1;
+        }
+        if gp.b.just_pressed() {
+            self.mode = GameMode::Title;
+        } else if (gp.start.just_pressed() || gp.a.just_pressed()) && self.level_unlocked(self.level_cursor) {
+            self.reset_game(RunKind::Campaign(self.level_cursor));
+        }
+    }
+
+    // Save the clear and its rating if it beats the level's record
+    pub(crate) fn record_level_clear(&mut self, level: usize) {
+        let Some(level) = self.campaign.get(level) else {
+            return;
+        };
+        let result = self.run_result();
+        self.save.record_level(&level.name, level.stars_for(&result), result.score);
+    }
+
+    pub(crate) fn update_level_clear(&mut self, gp: &gamepad::Gamepad) {
+        self.update_backdrop();
+
+        let RunKind::Campaign(level) = self.run_kind else {
+            self.mode = GameMode::Title;
+            return;
+        };
+        if gp.start.just_pressed() || gp.a.just_pressed() {
+            let next = level + 1;
+            if next < self.campaign.len() && self.level_unlocked(next) {
+                self.reset_game(RunKind::Campaign(next));
+            } else {
+                self.open_level_select();
+            }
+        } else if gp.b.just_pressed() {
+            self.open_level_select();
+        } else if gp.down.just_pressed() && self.replay.frames() > 0 {
+            self.start_replay();
+        }
+    }
+
+    pub(crate) fn render_level_select(&self, draw: &mut DrawList) {
+        draw.text("CAMPAIGN", 96, 12, "large", COLOR_AURORA_GREEN);
+
+        if self.campaign.is_empty() {
+            draw.text("No levels in levels.json", 60, 60, "small", COLOR_TEXT);
+        }
+        let first = self.level_cursor.saturating_sub(VISIBLE_LEVELS - 1);
+        for (row, (i, level)) in self.campaign.iter().enumerate().skip(first).take(VISIBLE_LEVELS).enumerate() {
+            let y = 34 + row as i32 * 14;
+            let selected = i == self.level_cursor;
+            if selected {
+                draw.rect(16, y - 2, 224, 12, COLOR_UI_BG);
+                draw.text(">", 20, y, "small", COLOR_STAR);
+            }
+
+            if !self.level_unlocked(i) {
+                draw.text(format!("{}. LOCKED", i + 1), 28, y, "small", 0x888888ff);
+                continue;
+            }
+            let color = if selected { COLOR_TEXT } else { 0xccccccff };
+            draw.text(format!("{}. {}", i + 1, level.name.to_uppercase()), 28, y, "small", color);
+            let best = self.save.level_best(&level.name);
+            let stars = best.map_or(0, |best| best.stars);
+            self.render_rating(draw, stars, 160, y - 1);
+            if let Some(best) = best {
+                draw.text(format!("{}", best.score), 194, y, "small", COLOR_STAR);
+            }
+        }
+
+        draw.text("[UP/DOWN] Choose  [A] Play  [B] Back", 36, 130, "small", 0xaaaaaaff);
+    }
+
+    pub(crate) fn render_level_clear(&self, draw: &mut DrawList) {
+        self.render_frozen_run(draw);
+
+        let RunKind::Campaign(index) = self.run_kind else {
+            return;
+        };
+        let Some(level) = self.campaign.get(index) else {
+            return;
+        };
+        let result = self.run_result();
+
+        draw.rect(0, 0, 256, 144, 0x00000099);
+        draw.rect(28, 22, 200, 104, COLOR_UI_BG);
+        draw.text("LEVEL CLEAR!", 66, 28, "large", COLOR_AURORA_GREEN);
+        draw.text(level.name.to_uppercase(), 40, 44, "small", COLOR_TEXT);
+        self.render_rating(draw, level.stars_for(&result), 104, 56);
+        draw.text(format!("SCORE: {}", result.score), 85, 70, "medium", COLOR_STAR);
+        let best = self.save.level_best(&level.name).map_or(0, |best| best.score);
+        draw.text(format!("BEST: {best}"), 85, 84, "small", COLOR_TEXT);
+        draw.text(format!("STARS: {}", result.stars_collected), 89, 94, "small", COLOR_STAR);
+
+        let next = index + 1 < self.campaign.len() && self.level_unlocked(index + 1);
+        if (self.frame / 30).is_multiple_of(2) {
+            let prompt = if next { "[SPACE] Next Level" } else { "[SPACE] Level Select" };
+            draw.text(prompt, 70, 106, "small", COLOR_TEXT);
+        }
+        draw.text("[DOWN] Replay  [B] Levels", 58, 115, "small", 0xaaaaaaff);
+    }
+
+    // Three stars, lit up to `earned`
+    fn render_rating(&self, draw: &mut DrawList, earned: u32, x: i32, y: i32) {
+        for i in 0..3 {
+            let opacity = if i < earned { 1.0 } else { 0.25 };
+            draw.sprite("star", x + i as i32 * 10, y, opacity);
+        }
+    }
+
+    // Chequered finish line across the track, and a progress bar up top
+    pub(crate) fn render_finish_line(&self, draw: &mut DrawList) {
+        let Some(finish) = self.finish_distance() else {
+            return;
+        };
+        let x = (self.player().x + finish - self.distance) as i32;
+        if (-8..264).contains(&x) {
+            for row in 0..12 {
+                let y = GROUND_Y as i32 - 80 + row * 8;
+                for column in 0..2 {
+                    let color = if (row + column) % 2 == 0 { COLOR_TEXT } else { 0x000000ff };
+                    draw.rect(x + column * 4, y, 4, 8, apply_opacity(color, 0.8));
+                }
+            }
+        }
+
+        let progress = (self.distance / finish.max(1.0)).clamp(0.0, 1.0);
+        draw.rect(130, 6, 100, 4, COLOR_UI_BG);
+        draw.rect(130, 6, (progress * 100.0) as u32, 4, COLOR_AURORA_GREEN);
+        draw.rect(229, 4, 2, 8, COLOR_AURORA_RED);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::start;
+    use crate::{GameEvent, InputFrame};
+
+    fn rated(ratings: [Rating; 3]) -> CampaignLevel {
+        CampaignLevel {
+            name: "test".to_string(),
+            distance: 1000.0,
+            script: Vec::new(),
+            ratings,
+        }
+    }
+
+    fn result(score: u32, stars_collected: u32) -> RunResult {
+        RunResult {
+            score,
+            max_combo: 0,
+            stars_collected,
+        }
+    }
+
+    #[test]
+    fn stars_come_in_order_of_the_ratings() {
+        let level = rated([
+            Rating::default(),
+            Rating {
+                score: 500,
+                stars_collected: 0,
+            },
+            Rating {
+                score: 900,
+                stars_collected: 4,
+            },
+        ]);
+        assert_eq!(level.stars_for(&result(0, 0)), 1);
+        assert_eq!(level.stars_for(&result(499, 10)), 1);
+        assert_eq!(level.stars_for(&result(500, 0)), 2);
+        assert_eq!(level.stars_for(&result(900, 3)), 2);
+        assert_eq!(level.stars_for(&result(900, 4)), 3);
+
+        // Later ratings don't count while an earlier one is missed
+        let first = Rating {
+            score: 100,
+            stars_collected: 0,
+        };
+        let level = rated([first, Rating::default(), Rating::default()]);
+        assert_eq!(level.stars_for(&result(50, 10)), 0);
+        assert_eq!(level.stars_for(&result(100, 0)), 3);
+    }
+
+    // Step until the run is over, with player one parked just short of the finish line
+    fn run_to_the_finish(state: &mut GameState) -> Vec<GameEvent> {
+        let finish = state.finish_distance().unwrap();
+        state.distance = finish - 1.0;
+        state.obstacles.clear();
+        state.spawn_cursor = f32::MAX;
+        let mut events = Vec::new();
+        while state.mode == GameMode::Playing {
+            events.extend(state.step(&[InputFrame::default()]));
+        }
+        events
+    }
+
+    #[test]
+    fn runs_end_at_the_finish_line() {
+        let mut state = start(RunKind::Campaign(0), 1);
+        let events = run_to_the_finish(&mut state);
+        assert!(events.contains(&GameEvent::LevelCleared));
+        assert!(!events.contains(&GameEvent::GameOver));
+        assert_eq!(state.mode, GameMode::LevelClear);
+        assert!(state.distance >= state.campaign[0].distance);
+
+        assert_eq!(start(RunKind::Endless, 1).finish_distance(), None);
+    }
+
+    #[test]
+    fn clearing_a_level_unlocks_the_next() {
+        let mut state = GameState::new();
+        assert!(state.level_unlocked(0));
+        assert!(!state.level_unlocked(1));
+
+        state.play_run(state.own_setup(RunKind::Campaign(0), 1));
+        run_to_the_finish(&mut state);
+        state.finish_run();
+
+        let best = state.save.level_best(&state.campaign[0].name).unwrap();
+        assert!(best.stars > 0);
+        assert!(state.level_unlocked(1));
+        assert!(!state.level_unlocked(2));
+    }
+}
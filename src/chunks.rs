@@ -0,0 +1,281 @@
+use crate::health::HEART_CHANCE;
+use crate::levels;
+use crate::reach::JumpEnvelope;
+use crate::rng::Rng;
+use crate::{GameState, Obstacle, ObstacleType, PowerUp, PowerUpType, RunKind, Star, SPAWN_ATTEMPTS};
+
+// Where new chunks appear (right screen edge)
+pub(crate) const SPAWN_X: f32 = 256.0;
+
+// Distance travelled before harder chunks join the rotation
+const MEDIUM_DISTANCE: f32 = 1500.0;
+const HARD_DISTANCE: f32 = 5000.0;
+
+#[turbo::serialize]
+#[derive(Copy, PartialEq, PartialOrd)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum Difficulty {
+    Easy,
+    Medium,
+    Hard,
+}
+
+// Obstacle `dx` px after the start of its chunk
+// `lift` is how far a floating rock's bottom edge sits above the ground (0 for ground obstacles)
+#[turbo::serialize]
+#[derive(PartialEq)]
+#[serde(try_from = "levels::RawObstacle")]
+pub(crate) struct ChunkObstacle {
+    pub(crate) dx: f32,
+    #[serde(rename = "type")]
+    pub(crate) obstacle_type: ObstacleType,
+    #[serde(skip_serializing_if = "levels::is_zero")]
+    pub(crate) lift: f32,
+}
+
+// Star `dx` px after the start of its chunk
+#[turbo::serialize]
+#[derive(PartialEq)]
+#[serde(try_from = "levels::RawItem")]
+pub(crate) struct ChunkItem {
+    pub(crate) dx: f32,
+    pub(crate) y: f32,
+}
+
+// Power-up slot `dx` px after the start of its chunk
+// Without a `powerup_type` it's filled with a random gift now and then
+#[turbo::serialize]
+#[derive(PartialEq)]
+#[serde(try_from = "levels::RawPowerUpSlot")]
+pub(crate) struct PowerUpSlot {
+    pub(crate) dx: f32,
+    pub(crate) y: f32,
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub(crate) powerup_type: Option<PowerUpType>,
+}
+
+// `count` stars spread over `width` px along an arch `height` px tall, starting at (dx, y)
+#[turbo::serialize]
+#[derive(PartialEq)]
+#[serde(try_from = "levels::RawStarArc")]
+pub(crate) struct StarArc {
+    pub(crate) dx: f32,
+    pub(crate) y: f32,
+    pub(crate) width: f32,
+    pub(crate) height: f32,
+    pub(crate) count: u32,
+}
+
+impl StarArc {
+    pub(crate) fn stars(&self) -> impl Iterator<Item = (f32, f32)> + '_ {
+        let steps = self.count.saturating_sub(1).max(1) as f32;
+        (0..self.count).map(move |i| {
+            let t = i as f32 / steps;
+            (self.dx + self.width * t, self.y - self.height * 4.0 * t * (1.0 - t))
+        })
+    }
+}
+
+// Hand-authored stretch of obstacles and pickups, loaded from levels.json
+#[turbo::serialize]
+#[derive(PartialEq)]
+#[serde(try_from = "levels::RawChunk")]
+pub(crate) struct Chunk {
+    pub(crate) name: String,
+    pub(crate) difficulty: Difficulty,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub(crate) obstacles: Vec<ChunkObstacle>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub(crate) stars: Vec<ChunkItem>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub(crate) star_arcs: Vec<StarArc>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) powerup: Option<PowerUpSlot>,
+}
+
+impl Chunk {
+    // Distance from the chunk's start to the far edge of its last entity
+    pub(crate) fn length(&self) -> f32 {
+        let obstacles = self.obstacles_at(0.0).map(|o| o.x + o.width);
+        let stars = self.star_positions().map(|(dx, _)| dx);
+        let powerup = self.powerup.iter().map(|item| item.dx);
+        obstacles.chain(stars).chain(powerup).fold(0.0, f32::max)
+    }
+
+    pub(crate) fn obstacles_at(&self, x: f32) -> impl Iterator<Item = Obstacle> + '_ {
+        self.obstacles
+            .iter()
+            .map(move |o| Obstacle::new(x + o.dx, o.obstacle_type.clone(), o.lift))
+    }
+
+    // (dx, y) of every star, single ones and arcs alike
+    pub(crate) fn star_positions(&self) -> impl Iterator<Item = (f32, f32)> + '_ {
+        let single = self.stars.iter().map(|star| (star.dx, star.y));
+        single.chain(self.star_arcs.iter().flat_map(StarArc::stars))
+    }
+}
+
+// Hardest chunks allowed this far into a run
+fn difficulty_at(distance: f32) -> Difficulty {
+    if distance >= HARD_DISTANCE {
+        Difficulty::Hard
+    } else if distance >= MEDIUM_DISTANCE {
+        Difficulty::Medium
+    } else {
+        Difficulty::Easy
+    }
+}
+
+impl GameState {
+    // Lay down the next chunk once the previous one (plus its breathing room) has scrolled on screen
+    pub(crate) fn spawn_chunks(&mut self) {
+        if self.spawn_cursor > SPAWN_X {
+            return;
+        }
+        // Campaign levels follow their script; endless runs roll, re-rolling unclearable picks
+        // Either way, if nothing fits, try again next frame
+        let next = match self.run_kind {
+            RunKind::Campaign(level) => self.next_scripted_chunk(level),
+            RunKind::Endless | RunKind::Daily(_) | RunKind::Versus | RunKind::Coop => self.roll_chunk(),
+        };
+        let Some(index) = next else {
+            return;
+        };
+        let chunk = self.chunks[index].clone();
+        self.place_chunk(&chunk, SPAWN_X);
+
+        self.spawn_cursor = SPAWN_X + chunk.length() + self.tuning.obstacle_spawn_interval as f32 * self.scroll_speed;
+    }
+
+    // Put a chunk's obstacles, stars and power-up on the track starting at `x`
+    pub(crate) fn place_chunk(&mut self, chunk: &Chunk, x: f32) {
+        self.obstacles.extend(chunk.obstacles_at(x));
+        for (dx, y) in chunk.star_positions() {
+            self.stars.push(Star {
+                x: x + dx,
+                y,
+                collected: false,
+                missed: false,
+            });
+        }
+        if let Some(slot) = &chunk.powerup {
+            // Open slots are filled now and then (gifts/presents)
+            let powerup_type = match &slot.powerup_type {
+                Some(powerup_type) => Some(powerup_type.clone()),
+                None if self.wants_heart() && self.rng.one_in(HEART_CHANCE) => Some(PowerUpType::Heart),
+                None if self.rng.one_in(self.preset.rules().powerup_chance) => Some(random_powerup(&mut self.rng)),
+                None => None,
+            };
+            if let Some(powerup_type) = powerup_type {
+                self.powerups.push(PowerUp {
+                    x: x + slot.dx,
+                    y: slot.y,
+                    powerup_type,
+                    collected: false,
+                    missed: false,
+                });
+            }
+        }
+    }
+
+    // Random chunk for the current difficulty that the player can still get past
+    // at the current speed and at slow-mo speed, given what's already on screen
+    // The run's preset weights the pick towards easier or harder chunks
+    fn roll_chunk(&mut self) -> Option<usize> {
+        let difficulty = difficulty_at(self.distance);
+        let rules = self.preset.rules();
+        let eligible: Vec<(usize, u32)> = (0..self.chunks.len())
+            .filter(|&i| self.chunks[i].difficulty <= difficulty)
+            .map(|i| (i, rules.chunk_weight(self.chunks[i].difficulty)))
+            .filter(|&(_, weight)| weight > 0)
+            .collect();
+        let total: u32 = eligible.iter().map(|&(_, weight)| weight).sum();
+        if total == 0 {
+            return None;
+        }
+
+        for _ in 0..SPAWN_ATTEMPTS {
+            let index = pick_weighted(&eligible, self.rng.below(total));
+            if self.chunk_fits(index) {
+                return Some(index);
+            }
+        }
+        None
+    }
+
+    // Could every player still in the run get past everything if chunk `index` spawned now,
+    // at the current speed and at slow-mo speed?
+    pub(crate) fn chunk_fits(&self, index: usize) -> bool {
+        let envelope = JumpEnvelope::new(&self.tuning);
+        let mut lineup = self.obstacles.clone();
+        lineup.extend(self.chunks[index].obstacles_at(SPAWN_X));
+        [self.scroll_speed, self.scroll_speed * 0.5].iter().all(|&speed| {
+            self.players
+                .iter()
+                .filter(|player| player.alive)
+                .all(|player| envelope.survivable(player, &lineup, speed))
+        })
+    }
+}
+
+// The (index, weight) pair `roll` lands on, for a roll below their total weight
+// With equal weights this is the same as indexing by the roll
+fn pick_weighted(weighted: &[(usize, u32)], mut roll: u32) -> usize {
+    for &(index, weight) in weighted {
+        if roll < weight {
+            return index;
+        }
+        roll -= weight;
+    }
+    weighted[weighted.len() - 1].0
+}
+
+pub(crate) fn random_powerup(rng: &mut Rng) -> PowerUpType {
+    match rng.below(6) {
+        0 | 1 => PowerUpType::Shield,  // 33% chance for shield (2/6)
+        2 => PowerUpType::SlowMo,      // 17% chance
+        3 | 4 => PowerUpType::Magnet,  // 33% chance for magnet (2/6)
+        _ => PowerUpType::DoublePoints, // 17% chance
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::difficulty::Preset;
+    use crate::tests::start;
+
+    #[test]
+    fn chunks_must_fit_every_live_player() {
+        let mut state = start(RunKind::Versus, 1);
+        state.obstacles.clear();
+        assert!(state.chunk_fits(0));
+
+        // Already level with player two, who can't get out of the way; player one has time to jump it
+        state.obstacles.push(Obstacle::new(state.players[1].x - 4.0, ObstacleType::Crystal, 0.0));
+        assert!(!state.chunk_fits(0));
+
+        state.players[1].alive = false;
+        assert!(state.chunk_fits(0));
+    }
+
+    #[test]
+    fn rolls_land_on_chunks_in_proportion_to_the_presets_weights() {
+        let state = start(RunKind::Endless, 1);
+        for preset in Preset::ALL {
+            let rules = preset.rules();
+            let weighted: Vec<(usize, u32)> =
+                state.chunks.iter().enumerate().map(|(i, chunk)| (i, rules.chunk_weight(chunk.difficulty))).collect();
+            let total: u32 = weighted.iter().map(|&(_, weight)| weight).sum();
+
+            // Every possible roll, once each
+            let mut landed = vec![0; state.chunks.len()];
+            for roll in 0..total {
+                landed[pick_weighted(&weighted, roll)] += 1;
+            }
+            for (i, chunk) in state.chunks.iter().enumerate() {
+                assert_eq!(landed[i], rules.chunk_weight(chunk.difficulty), "{} on {}", chunk.name, preset.name());
+            }
+        }
+    }
+}
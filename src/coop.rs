@@ -0,0 +1,248 @@
+use crate::draw::DrawList;
+use crate::player::Player;
+use crate::versus::PLAYER_COLORS;
+use crate::{GameEvent, GameState, InputFrame, RunKind, COLOR_AURORA_GREEN, COLOR_AURORA_RED, COLOR_SHIELD, COLOR_STAR, COLOR_TEXT, COLOR_UI_BG, GROUND_Y};
+
+// Lives the pair share in co-op
+pub(crate) const COOP_LIVES: u32 = 2;
+
+// Frames a downed reindeer sits out before rejoining its partner
+const REVIVE_FRAMES: u32 = 120;
+
+// Shield a revived reindeer comes back with, so it isn't knocked straight out again
+const REVIVE_SHIELD_FRAMES: u32 = 90;
+
+// Frames after a hand-off before the shield can change hands again
+const SHIELD_PASS_COOLDOWN: u32 = 60;
+
+// How far apart (px) the two 16x16 hitboxes can be for the shield to still reach the partner
+const SHIELD_PASS_REACH: f32 = 6.0;
+
+// Distance between two reindeers' 16x16 hitboxes (0 once they overlap)
+fn hitbox_gap(a: &Player, b: &Player) -> f32 {
+    let dx = ((a.x - b.x).abs() - 16.0).max(0.0);
+    let dy = ((a.y - b.y).abs() - 16.0).max(0.0);
+    (dx * dx + dy * dy).sqrt()
+}
+
+impl GameState {
+    // A co-op reindeer just went down: spend a shared life to bring it back, if its partner is still running
+    pub(crate) fn spend_life(&mut self, index: usize) {
+        let partner_running = self.players.iter().enumerate().any(|(i, player)| i != index && player.alive);
+        if self.lives > 0 && partner_running {
+            self.lives -= 1;
+            self.players[index].revive_timer = REVIVE_FRAMES;
+        }
+    }
+
+    // Co-op reindeer tapping UP while already in the air, which hands their shield to the partner
+    pub(crate) fn shield_pass_taps(&self, inputs: &[InputFrame]) -> Vec<bool> {
+        self.players
+            .iter()
+            .enumerate()
+            .map(|(index, player)| {
+                self.run_kind == RunKind::Coop
+                    && player.alive
+                    && !player.is_on_ground
+                    && inputs.get(index).is_some_and(|input| input.up_just_pressed)
+            })
+            .collect()
+    }
+
+    // Revivals and shield hand-offs, once everyone has moved this frame
+    pub(crate) fn update_coop(&mut self, pass_taps: &[bool], events: &mut Vec<GameEvent>) {
+        for index in 0..self.players.len() {
+            let player = &mut self.players[index];
+            if player.alive || player.revive_timer == 0 {
+                continue;
+            }
+            player.revive_timer -= 1;
+            if player.revive_timer == 0 {
+                self.revive(index);
+                events.push(GameEvent::Revived);
+            }
+        }
+
+        // A shielded reindeer tosses its shield to an unshielded partner by tapping UP again mid-air,
+        // as long as the two are touching or nearly so
+        self.shield_pass_cooldown = self.shield_pass_cooldown.saturating_sub(1);
+        if self.shield_pass_cooldown > 0 || self.players.len() != 2 {
+            return;
+        }
+        let Some(giver) = (0..2).find(|&index| pass_taps.get(index) == Some(&true) && self.players[index].has_shield) else {
+            return;
+        };
+        let receiver = 1 - giver;
+        let (from, to) = (&self.players[giver], &self.players[receiver]);
+        if !to.alive || to.has_shield || hitbox_gap(from, to) > SHIELD_PASS_REACH {
+            return;
+        }
+        let shield_timer = from.shield_timer;
+        let (x, y) = (to.x, to.y);
+        self.players[receiver].has_shield = true;
+        self.players[receiver].shield_timer = shield_timer;
+        self.players[giver].has_shield = false;
+        self.players[giver].shield_timer = 0;
+        self.shield_pass_cooldown = SHIELD_PASS_COOLDOWN;
+        self.spawn_floating_text(x, y - 20.0, "SHIELD PASS!", COLOR_SHIELD);
+        events.push(GameEvent::ShieldPassed);
+    }
+
+    // Back on the ground under a short shield
+    fn revive(&mut self, index: usize) {
+        let player = &mut self.players[index];
+        player.alive = true;
+        player.hearts = player.max_hearts;
+        player.y = GROUND_Y;
+        player.velocity_y = 0.0;
+        player.is_jumping = false;
+        player.is_on_ground = true;
+        player.rotation = 0.0;
+        player.has_shield = true;
+        player.shield_timer = player.shield_timer.max(REVIVE_SHIELD_FRAMES);
+        let x = player.x;
+
+        // Obstacles that went by while it was down don't count towards its combo
+        for obstacle in &mut self.obstacles {
+            if obstacle.x + obstacle.width < x {
+                obstacle.passed |= 1 << index;
+            }
+        }
+        for _ in 0..15 {
+            self.spawn_sparkle(x, GROUND_Y);
+        }
+    }
+
+    // Shared lives, and a countdown over each reindeer waiting to come back
+    pub(crate) fn render_lives(&self, draw: &mut DrawList) {
+        draw.rect(100, 128, 56, 12, COLOR_UI_BG);
+        draw.text(format!("LIVES x{}", self.lives), 106, 131, "small", COLOR_AURORA_RED);
+        for player in &self.players {
+            if !player.alive && player.revive_timer > 0 {
+                let seconds = player.revive_timer / 60 + 1;
+                draw.text(format!("{seconds}"), player.x as i32 - 2, player.y as i32 - 36, "medium", COLOR_TEXT);
+            }
+        }
+    }
+
+    pub(crate) fn render_team_over(&self, draw: &mut DrawList) {
+        self.render_frozen_run(draw);
+
+        let team_score: u32 = self.players.iter().map(|player| player.score).sum();
+        draw.rect(0, 0, 256, 144, 0x00000099);
+        draw.rect(28, 26, 200, 96, COLOR_UI_BG);
+        draw.text("GAME OVER", 75, 32, "large", COLOR_TEXT);
+        draw.text(format!("TEAM SCORE: {team_score}"), 64, 48, "medium", COLOR_STAR);
+
+        for (i, player) in self.players.iter().enumerate() {
+            let x = 40 + i as i32 * 96;
+            draw.text(format!("P{}: {}", i + 1, player.score), x, 66, "small", PLAYER_COLORS[i]);
+            draw.text(format!("COMBO: {}x", player.max_combo), x, 76, "small", COLOR_AURORA_GREEN);
+            draw.text(format!("STARS: {}", player.stars_collected), x, 86, "small", COLOR_STAR);
+        }
+
+        if (self.frame / 30).is_multiple_of(2) {
+            draw.text("[SPACE] Play Again", 70, 103, "small", COLOR_TEXT);
+        }
+        draw.text("[DOWN] Watch Replay", 68, 112, "small", 0xaaaaaaff);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::start;
+
+    const TAP: InputFrame = InputFrame {
+        up_pressed: true,
+        up_just_pressed: true,
+    };
+    const HOLD: InputFrame = InputFrame {
+        up_pressed: true,
+        up_just_pressed: false,
+    };
+    const IDLE: InputFrame = InputFrame {
+        up_pressed: false,
+        up_just_pressed: false,
+    };
+
+    fn shielded_pair() -> GameState {
+        let mut state = start(RunKind::Coop, 9);
+        state.obstacles.clear();
+        state.spawn_cursor = f32::MAX;
+        state.players[0].has_shield = true;
+        state.players[0].shield_timer = 600;
+        state
+    }
+
+    fn shields(state: &GameState) -> [bool; 2] {
+        [state.players[0].has_shield, state.players[1].has_shield]
+    }
+
+    #[test]
+    fn running_side_by_side_keeps_the_shield_put() {
+        let mut state = shielded_pair();
+        for _ in 0..120 {
+            state.step(&[IDLE, IDLE]);
+        }
+        // Both jumping together doesn't hand it over either
+        state.step(&[TAP, TAP]);
+        for _ in 0..60 {
+            state.step(&[IDLE, IDLE]);
+        }
+        assert_eq!(shields(&state), [true, false]);
+    }
+
+    #[test]
+    fn mid_air_tap_passes_the_shield_once_per_cooldown() {
+        let mut state = shielded_pair();
+        state.step(&[TAP, IDLE]);
+        assert_eq!(shields(&state), [true, false]);
+        state.step(&[TAP, IDLE]);
+        assert_eq!(shields(&state), [false, true]);
+
+        // The partner can't hand it straight back
+        state.step(&[IDLE, TAP]);
+        state.step(&[IDLE, TAP]);
+        assert_eq!(shields(&state), [false, true]);
+
+        while state.shield_pass_cooldown > 0 || !state.players[0].is_on_ground || !state.players[1].is_on_ground {
+            state.step(&[IDLE, IDLE]);
+        }
+        state.step(&[IDLE, TAP]);
+        state.step(&[IDLE, TAP]);
+        assert_eq!(shields(&state), [true, false]);
+    }
+
+    // Move player two so its hitbox is `gap` px ahead of player one's
+    fn spread(state: &mut GameState, gap: f32) {
+        let x = state.players[0].x + 16.0 + gap;
+        state.players[1].x = x;
+        state.players[1].home_x = x;
+    }
+
+    #[test]
+    fn the_shield_only_reaches_a_partner_close_by() {
+        let mut state = shielded_pair();
+        spread(&mut state, 40.0);
+        state.step(&[TAP, IDLE]);
+        state.step(&[TAP, IDLE]);
+        assert_eq!(shields(&state), [true, false]);
+        assert_eq!(state.shield_pass_cooldown, 0);
+
+        let mut state = shielded_pair();
+        spread(&mut state, 0.0);
+        state.step(&[TAP, IDLE]);
+        state.step(&[TAP, IDLE]);
+        assert_eq!(shields(&state), [false, true]);
+
+        // A toss from high above the partner falls short too
+        let mut state = shielded_pair();
+        state.step(&[TAP, IDLE]);
+        while state.players[0].y > state.players[1].y - 40.0 {
+            state.step(&[HOLD, IDLE]);
+        }
+        state.step(&[TAP, IDLE]);
+        assert_eq!(shields(&state), [true, false]);
+    }
+}
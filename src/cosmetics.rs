@@ -0,0 +1,382 @@
+use turbo::*;
+
+use crate::achievements::ACHIEVEMENTS;
+use crate::draw::DrawList;
+use crate::{apply_opacity, GameMode, GameState, COLOR_AURORA_GREEN, COLOR_PLAYER_GLOW, COLOR_STAR, COLOR_TEXT, COLOR_UI_BG};
+
+// Wardrobe rows, in the order the screen lists them
+const SLOTS: [&str; 3] = ["CHARACTER", "HAT", "TRAIL"];
+
+// One thing to wear, `look` being what it changes
+pub(crate) struct Cosmetic<L: 'static> {
+    // Stored in the save, so never rename one
+    id: &'static str,
+    name: &'static str,
+    // Achievement that unlocks it (None = always available)
+    unlock: Option<&'static str>,
+    look: L,
+}
+
+#[turbo::serialize]
+#[derive(Copy, PartialEq)]
+pub(crate) enum TrailShape {
+    Dot,
+    Square,
+    // Small plus sign
+    Spark,
+}
+
+#[derive(Clone, Copy)]
+pub(crate) struct TrailStyle {
+    pub(crate) color: u32,
+    pub(crate) shape: TrailShape,
+    // Frames each particle lasts
+    pub(crate) lifetime: u32,
+}
+
+// Reindeer sprites
+const CHARACTERS: &[Cosmetic<&str>] = &[
+    Cosmetic {
+        id: "classic",
+        name: "Classic",
+        unlock: None,
+        look: "reindeer",
+    },
+    Cosmetic {
+        id: "arctic",
+        name: "Arctic",
+        unlock: Some("runs_50"),
+        look: "reindeer-arctic",
+    },
+    Cosmetic {
+        id: "midnight",
+        name: "Midnight",
+        unlock: Some("score_5000"),
+        look: "reindeer-midnight",
+    },
+    Cosmetic {
+        id: "golden",
+        name: "Golden",
+        unlock: Some("campaign_mastered"),
+        look: "reindeer-golden",
+    },
+];
+
+// Sprites worn on top, if any
+const HATS: &[Cosmetic<Option<&str>>] = &[
+    Cosmetic {
+        id: "santa",
+        name: "Santa Hat",
+        unlock: None,
+        look: Some("santa-hat"),
+    },
+    Cosmetic {
+        id: "none",
+        name: "No Hat",
+        unlock: None,
+        look: None,
+    },
+    Cosmetic {
+        id: "elf",
+        name: "Elf Hat",
+        unlock: Some("all_gifts"),
+        look: Some("elf-hat"),
+    },
+    Cosmetic {
+        id: "wizard",
+        name: "Wizard Hat",
+        unlock: Some("score_20000"),
+        look: Some("wizard-hat"),
+    },
+];
+
+const TRAILS: &[Cosmetic<TrailStyle>] = &[
+    Cosmetic {
+        id: "aurora",
+        name: "Aurora",
+        unlock: None,
+        look: TrailStyle { color: COLOR_PLAYER_GLOW, shape: TrailShape::Dot, lifetime: 20 },
+    },
+    Cosmetic {
+        id: "candy",
+        name: "Candy Stripe",
+        unlock: Some("combo_10"),
+        look: TrailStyle { color: 0xff3355ff, shape: TrailShape::Square, lifetime: 24 },
+    },
+    Cosmetic {
+        id: "frost",
+        name: "Frost",
+        unlock: Some("shield_crush_10"),
+        look: TrailStyle { color: 0xddeeffff, shape: TrailShape::Dot, lifetime: 40 },
+    },
+    Cosmetic {
+        id: "stardust",
+        name: "Stardust",
+        unlock: Some("every_star"),
+        look: TrailStyle { color: COLOR_STAR, shape: TrailShape::Spark, lifetime: 30 },
+    },
+    Cosmetic {
+        id: "comet",
+        name: "Comet",
+        unlock: Some("combo_50"),
+        look: TrailStyle { color: 0xff8800ff, shape: TrailShape::Square, lifetime: 12 },
+    },
+];
+
+// What's picked in each slot, by cosmetic id
+#[turbo::serialize]
+#[derive(PartialEq)]
+pub(crate) struct Wardrobe {
+    pub(crate) character: String,
+    pub(crate) hat: String,
+    pub(crate) trail: String,
+}
+
+impl Default for Wardrobe {
+    fn default() -> Self {
+        Self {
+            character: CHARACTERS[0].id.to_string(),
+            hat: HATS[0].id.to_string(),
+            trail: TRAILS[0].id.to_string(),
+        }
+    }
+}
+
+impl Wardrobe {
+    pub(crate) fn outfit(&self) -> Outfit {
+        Outfit {
+            character: find(CHARACTERS, &self.character),
+            hat: find(HATS, &self.hat),
+            trail: find(TRAILS, &self.trail),
+        }
+    }
+
+    fn slot(&self, slot: usize) -> &str {
+        match slot {
+            0 => &self.character,
+            1 => &self.hat,
+            _ => &self.trail,
+        }
+    }
+
+    fn slot_mut(&mut self, slot: usize) -> &mut String {
+        match slot {
+            0 => &mut self.character,
+            1 => &mut self.hat,
+            _ => &mut self.trail,
+        }
+    }
+}
+
+// How one reindeer looks
+pub(crate) struct Outfit {
+    pub(crate) character: &'static str,
+    pub(crate) hat: Option<&'static str>,
+    pub(crate) trail: TrailStyle,
+}
+
+impl Default for Outfit {
+    fn default() -> Self {
+        Self {
+            character: CHARACTERS[0].look,
+            hat: HATS[0].look,
+            trail: TRAILS[0].look,
+        }
+    }
+}
+
+// The picked cosmetic, or the slot's default if the save names one that's gone
+fn find<L: Copy>(options: &'static [Cosmetic<L>], id: &str) -> L {
+    options.iter().find(|option| option.id == id).unwrap_or(&options[0]).look
+}
+
+// Hat over reindeer, centred on (x, y)
+pub(crate) fn render_outfit(draw: &mut DrawList, outfit: &Outfit, x: i32, y: i32, opacity: f32) {
+    if let Some(hat) = outfit.hat {
+        draw.sprite(hat, x - 4, y - 18, opacity);
+    }
+    draw.sprite(outfit.character, x - 8, y - 8, opacity);
+}
+
+// (id, name, unlock) of every option in a slot
+fn entries(slot: usize) -> Vec<(&'static str, &'static str, Option<&'static str>)> {
+    fn list<L>(options: &'static [Cosmetic<L>]) -> Vec<(&'static str, &'static str, Option<&'static str>)> {
+        options.iter().map(|option| (option.id, option.name, option.unlock)).collect()
+    }
+    match slot {
+        0 => list(CHARACTERS),
+        1 => list(HATS),
+        _ => list(TRAILS),
+    }
+}
+
+impl GameState {
+    // Player one wears the wardrobe; anyone else keeps the classic look so they're easy to tell apart
+    pub(crate) fn outfit(&self, player: usize) -> Outfit {
+        if player > 0 {
+            return Outfit::default();
+        }
+        self.save.wardrobe.outfit()
+    }
+
+    fn cosmetic_unlocked(&self, unlock: Option<&str>) -> bool {
+        unlock.is_none_or(|id| self.save.has_achievement(id))
+    }
+
+    // Hat over reindeer, centred on (x, y)
+    pub(crate) fn render_reindeer(&self, draw: &mut DrawList, player: usize, x: i32, y: i32, opacity: f32) {
+        render_outfit(draw, &self.outfit(player), x, y, opacity);
+    }
+
+    pub(crate) fn open_wardrobe(&mut self) {
+        self.wardrobe_cursor = 0;
+        self.mode = GameMode::Wardrobe;
+    }
+
+    pub(crate) fn update_wardrobe(&mut self, gp: &gamepad::Gamepad) {
+        self.update_backdrop();
+
+        if gp.up.just_pressed() {
+            self.wardrobe_cursor = self.wardrobe_cursor.saturating_sub(1);
+        }
+        if gp.down.just_pressed() && self.wardrobe_cursor + 1 < SLOTS.len() {
+            self.wardrobe_cursor += 1;
+        }
+        let step = if gp.right.just_pressed() {
+            1
+        } else if gp.left.just_pressed() {
+            -1
+        } else {
+            0
+        };
+        if step != 0 {
+            self.cycle_cosmetic(step);
+        }
+        if gp.b.just_pressed() || gp.start.just_pressed() {
+            self.mode = GameMode::Title;
+        }
+    }
+
+    // Move the highlighted slot to the next unlocked option (`step` = 1) or the previous one (-1)
+    fn cycle_cosmetic(&mut self, step: i32) {
+        let slot = self.wardrobe_cursor;
+        let options = entries(slot);
+        let worn = self.save.wardrobe.slot(slot);
+        let current = options.iter().position(|(id, _, _)| *id == worn).unwrap_or(0) as i32;
+        let count = options.len() as i32;
+        for offset in 1..count {
+            let (id, _, unlock) = options[(current + step * offset).rem_euclid(count) as usize];
+            if self.cosmetic_unlocked(unlock) {
+                *self.save.wardrobe.slot_mut(slot) = id.to_string();
+                audio::play("collect-star");
+                self.write_save();
+                return;
+            }
+        }
+    }
+
+    pub(crate) fn render_wardrobe(&self, draw: &mut DrawList) {
+        draw.text("WARDROBE", 82, 10, "large", COLOR_AURORA_GREEN);
+
+        for (slot, label) in SLOTS.iter().enumerate() {
+            let y = 34 + slot as i32 * 18;
+            if slot == self.wardrobe_cursor {
+                draw.rect(16, y - 2, 140, 16, COLOR_UI_BG);
+                draw.text(">", 20, y + 2, "small", COLOR_STAR);
+            }
+            let options = entries(slot);
+            let worn = self.save.wardrobe.slot(slot);
+            let name = options.iter().find(|(id, _, _)| *id == worn).map_or(options[0].1, |(_, name, _)| name);
+            let unlocked = options.iter().filter(|(_, _, unlock)| self.cosmetic_unlocked(*unlock)).count();
+            draw.text(format!("{label} {unlocked}/{}", options.len()), 28, y - 1, "small", 0xaaaaaaff);
+            draw.text(format!("< {} >", name.to_uppercase()), 28, y + 6, "small", COLOR_TEXT);
+        }
+
+        // How to get whatever's still locked in the highlighted slot
+        let locked: Vec<String> = entries(self.wardrobe_cursor)
+            .into_iter()
+            .filter(|(_, _, unlock)| !self.cosmetic_unlocked(*unlock))
+            .filter_map(|(_, name, unlock)| {
+                let achievement = ACHIEVEMENTS.iter().find(|achievement| Some(achievement.id) == unlock)?;
+                Some(format!("{}: {}", name.to_uppercase(), achievement.name.to_uppercase()))
+            })
+            .collect();
+        if !locked.is_empty() {
+            draw.text("LOCKED - EARN THE ACHIEVEMENT", 20, 90, "small", 0x888888ff);
+            for (row, line) in locked.iter().take(3).enumerate() {
+                draw.text(line.clone(), 20, 98 + row as i32 * 8, "small", 0x888888ff);
+            }
+        }
+
+        // Preview, trailing off to the left
+        draw.rect(164, 36, 80, 44, COLOR_UI_BG);
+        let trail = self.outfit(0).trail;
+        for i in 0..6 {
+            let opacity = 1.0 - i as f32 / 6.0;
+            render_trail_particle(draw, 200 - i * 6, 66 + i, 2.0, trail.color, trail.shape, opacity);
+        }
+        self.render_reindeer(draw, 0, 212, 64, 1.0);
+
+        draw.text("[UP/DOWN] Slot  [LEFT/RIGHT] Change  [B] Back", 8, 130, "small", 0xaaaaaaff);
+    }
+}
+
+pub(crate) fn render_trail_particle(draw: &mut DrawList, x: i32, y: i32, size: f32, color: u32, shape: TrailShape, opacity: f32) {
+    let color = apply_opacity(color, opacity);
+    match shape {
+        TrailShape::Dot => draw.circ(x, y, size as u32, color),
+        TrailShape::Square => draw.rect(x - 1, y - 1, size as u32 + 1, size as u32 + 1, color),
+        TrailShape::Spark => {
+            draw.rect(x - 2, y, 5, 1, color);
+            draw.rect(x, y - 2, 1, 5, color);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CHARACTER_SLOT: usize = 0;
+
+    fn wardrobe_on(slot: usize) -> GameState {
+        let mut state = GameState::new();
+        state.wardrobe_cursor = slot;
+        state
+    }
+
+    #[test]
+    fn cycling_skips_locked_options() {
+        // Nothing unlocked yet: there's nowhere to go from the classic reindeer
+        let mut state = wardrobe_on(CHARACTER_SLOT);
+        state.cycle_cosmetic(1);
+        assert_eq!(state.save.wardrobe.character, "classic");
+
+        // Midnight unlocked: Arctic (still locked) is skipped both ways, wrapping around the ends
+        state.save.unlock_achievement("score_5000");
+        state.cycle_cosmetic(1);
+        assert_eq!(state.save.wardrobe.character, "midnight");
+        state.cycle_cosmetic(1);
+        assert_eq!(state.save.wardrobe.character, "classic");
+        state.cycle_cosmetic(-1);
+        assert_eq!(state.save.wardrobe.character, "midnight");
+        state.cycle_cosmetic(-1);
+        assert_eq!(state.save.wardrobe.character, "classic");
+    }
+
+    #[test]
+    fn unknown_ids_fall_back_to_the_first_option() {
+        assert_eq!(find(CHARACTERS, "unicorn"), CHARACTERS[0].look);
+        assert_eq!(find(HATS, ""), HATS[0].look);
+        assert_eq!(find(HATS, "wizard"), HATS[3].look);
+
+        let outfit = Wardrobe {
+            character: "unicorn".to_string(),
+            hat: "crown".to_string(),
+            trail: "rainbow".to_string(),
+        }
+        .outfit();
+        assert_eq!(outfit.character, Outfit::default().character);
+        assert_eq!(outfit.hat, Outfit::default().hat);
+    }
+}
@@ -0,0 +1,49 @@
+use turbo::*;
+
+use crate::replay::fnv1a;
+use crate::{GameState, RunKind};
+
+const MILLIS_PER_DAY: u64 = 24 * 60 * 60 * 1000;
+
+// Days since 1970-01-01, in UTC so everyone rolls over to the next daily run at the same moment
+pub(crate) fn today() -> u32 {
+    (time::now() / MILLIS_PER_DAY) as u32
+}
+
+// (year, month, day) of a day number, using Howard Hinnant's civil_from_days
+pub(crate) fn civil_date(day: u32) -> (u32, u32, u32) {
+    let z = day + 719_468;
+    let era = z / 146_097;
+    let day_of_era = z - era * 146_097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    // Months counted from March, so the leap day falls at the end
+    let march_month = (5 * day_of_year + 2) / 153;
+    let day_of_month = day_of_year - (153 * march_month + 2) / 5 + 1;
+    let month = if march_month < 10 { march_month + 3 } else { march_month - 9 };
+    let year = year_of_era + era * 400 + u32::from(month <= 2);
+    (year, month, day_of_month)
+}
+
+// e.g. "2025-12-24"
+pub(crate) fn date_label(day: u32) -> String {
+    let (year, month, day_of_month) = civil_date(day);
+    format!("{year:04}-{month:02}-{day_of_month:02}")
+}
+
+// The seed every player gets on `day`
+pub(crate) fn seed_for(day: u32) -> u32 {
+    let hash = fnv1a(format!("daily {}", date_label(day)).as_bytes());
+    (hash ^ (hash >> 32)) as u32
+}
+
+impl GameState {
+    // Daily runs always start from today's seed, even when restarting one from before midnight
+    // and race the best run on record for the day, if there is one
+    pub(crate) fn start_daily_run(&mut self) {
+        self.reset_game(RunKind::Daily(today()));
+        if self.ghost.is_none() {
+            self.attach_best_ghost();
+        }
+    }
+}
@@ -0,0 +1,300 @@
+use turbo::*;
+
+use crate::chunks::Difficulty;
+use crate::draw::DrawList;
+use crate::tuning::Tuning;
+use crate::{GameState, RunKind, COLOR_AURORA_RED, COLOR_TEXT};
+
+// Difficulty picked for endless, versus and co-op runs
+// Part of the RunSetup, and each one keeps its own high score
+#[turbo::serialize]
+#[derive(Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum Preset {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+    Insane,
+}
+
+// How a preset bends a run; Normal leaves everything as tuned
+pub(crate) struct PresetRules {
+    // Scroll speed gained per 1000 points, and the most it can gain
+    ramp: f32,
+    ramp_cap: f32,
+    // Scales the gap between chunks
+    spawn_interval: f32,
+    // Odds of rolling an Easy, Medium or Hard chunk once they're in the rotation
+    chunk_weights: [u32; 3],
+    // Open power-up slots hold a gift one time in this many
+    pub(crate) powerup_chance: u32,
+    // Scales the points lost for a missed star or gift
+    miss_penalty: f32,
+}
+
+const EASY: PresetRules = PresetRules {
+    ramp: 0.5,
+    ramp_cap: 1.5,
+    spawn_interval: 1.5,
+    chunk_weights: [4, 2, 1],
+    powerup_chance: 5,
+    miss_penalty: 0.5,
+};
+
+const NORMAL: PresetRules = PresetRules {
+    ramp: 1.0,
+    ramp_cap: 2.5,
+    spawn_interval: 1.0,
+    chunk_weights: [1, 1, 1],
+    powerup_chance: 8,
+    miss_penalty: 1.0,
+};
+
+const HARD: PresetRules = PresetRules {
+    ramp: 1.5,
+    ramp_cap: 3.5,
+    spawn_interval: 0.75,
+    chunk_weights: [1, 2, 3],
+    powerup_chance: 10,
+    miss_penalty: 1.5,
+};
+
+const INSANE: PresetRules = PresetRules {
+    ramp: 2.0,
+    ramp_cap: 5.0,
+    spawn_interval: 0.5,
+    chunk_weights: [1, 2, 6],
+    powerup_chance: 14,
+    miss_penalty: 2.0,
+};
+
+impl Preset {
+    pub(crate) const ALL: [Preset; 4] = [Preset::Easy, Preset::Normal, Preset::Hard, Preset::Insane];
+
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Preset::Easy => "EASY",
+            Preset::Normal => "NORMAL",
+            Preset::Hard => "HARD",
+            Preset::Insane => "INSANE",
+        }
+    }
+
+    // One line for the rules screen
+    pub(crate) fn description(self) -> &'static str {
+        match self {
+            Preset::Easy => "Slower ramp, wider gaps and more gifts",
+            Preset::Normal => "The run as tuned",
+            Preset::Hard => "Faster ramp, tighter gaps and fewer gifts",
+            Preset::Insane => "Top speed, hard chunks, double miss penalty",
+        }
+    }
+
+    pub(crate) fn rules(self) -> &'static PresetRules {
+        match self {
+            Preset::Easy => &EASY,
+            Preset::Normal => &NORMAL,
+            Preset::Hard => &HARD,
+            Preset::Insane => &INSANE,
+        }
+    }
+
+    // Campaign levels and the daily run are the same for everyone, so they always play on Normal
+    pub(crate) fn for_kind(self, kind: RunKind) -> Self {
+        match kind {
+            RunKind::Endless | RunKind::Versus | RunKind::Coop => self,
+            RunKind::Campaign(_) | RunKind::Daily(_) => Preset::Normal,
+        }
+    }
+
+    // The next (`step` = 1) or previous (-1) preset, stopping at either end
+    pub(crate) fn step(self, step: i32) -> Self {
+        let current = Self::ALL.iter().position(|&preset| preset == self).unwrap_or(1) as i32;
+        Self::ALL[(current + step).clamp(0, Self::ALL.len() as i32 - 1) as usize]
+    }
+}
+
+impl PresetRules {
+    // The tuning a run actually plays with, after its modifiers
+    pub(crate) fn apply(&self, tuning: Tuning) -> Tuning {
+        Tuning {
+            obstacle_spawn_interval: (tuning.obstacle_spawn_interval as f32 * self.spawn_interval).round() as u32,
+            miss_penalty: (tuning.miss_penalty as f32 * self.miss_penalty).round() as u32,
+            ..tuning
+        }
+    }
+
+    // Scroll speed on top of the base once the leader has `score` points
+    pub(crate) fn ramp(&self, score: u32) -> f32 {
+        (score as f32 / 1000.0 * self.ramp).min(self.ramp_cap)
+    }
+
+    pub(crate) fn chunk_weight(&self, difficulty: Difficulty) -> u32 {
+        match difficulty {
+            Difficulty::Easy => self.chunk_weights[0],
+            Difficulty::Medium => self.chunk_weights[1],
+            Difficulty::Hard => self.chunk_weights[2],
+        }
+    }
+}
+
+// Where the title screen's picker sits: "[1-4] < NORMAL > HIGH SCORE: 0"
+const PICKER_Y: i32 = 105;
+const PICKER_LEFT_X: i32 = 44;
+const PICKER_NAME_X: i32 = 52;
+const PICKER_RIGHT_X: i32 = 84;
+
+impl GameState {
+    // Pick the preset on the title screen: click either arrow, or press 1-4
+    pub(crate) fn update_preset_picker(&mut self) {
+        let mouse = mouse::screen();
+        let keys = keyboard::get();
+        let clicked = |x: i32| mouse.left_clicked_bounds(Bounds::new(x - 4, PICKER_Y - 2, 12, 10));
+        let mut preset = self.save.preset;
+        if clicked(PICKER_LEFT_X) {
+            preset = preset.step(-1);
+        } else if clicked(PICKER_RIGHT_X) {
+            preset = preset.step(1);
+        }
+        let digits = [keys.digit_1(), keys.digit_2(), keys.digit_3(), keys.digit_4()];
+        if let Some(pressed) = digits.iter().position(|key| key.just_pressed()) {
+            preset = Preset::ALL[pressed];
+        }
+        self.pick_preset(preset);
+    }
+
+    pub(crate) fn pick_preset(&mut self, preset: Preset) {
+        if preset != self.save.preset {
+            self.save.preset = preset;
+            self.write_save();
+        }
+    }
+
+    // Records are kept per difficulty; show the one the next run is on
+    pub(crate) fn render_preset_picker(&self, draw: &mut DrawList) {
+        let preset = self.save.preset;
+        let color = if preset == Preset::Normal { COLOR_TEXT } else { COLOR_AURORA_RED };
+        draw.text("[1-4]", 14, PICKER_Y, "small", 0x666666ff);
+        draw.text("<", PICKER_LEFT_X, PICKER_Y, "small", color);
+        // Names are centred in the six characters INSANE takes
+        let name_x = PICKER_NAME_X + (6 - preset.name().len() as i32) * 5 / 2;
+        draw.text(preset.name(), name_x, PICKER_Y, "small", color);
+        draw.text(">", PICKER_RIGHT_X, PICKER_Y, "small", color);
+        draw.text(format!("HIGH SCORE: {}", self.save.high_score(preset)), 96, PICKER_Y, "small", COLOR_TEXT);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::replay::RunSetup;
+    use crate::shop::Modifiers;
+    use crate::tests::scripted_input;
+    use crate::{GameMode, InputFrame, Star};
+
+    fn start_on(preset: Preset, seed: u32) -> GameState {
+        let mut state = GameState::new();
+        state.start_run(RunSetup {
+            seed,
+            tuning: state.tuning_config,
+            kind: RunKind::Endless,
+            modifiers: Modifiers::default(),
+            hearts: 1,
+            preset,
+        });
+        state
+    }
+
+    #[test]
+    fn presets_scale_the_gap_and_the_miss_penalty() {
+        let tuning = Tuning::default();
+        assert_eq!(NORMAL.apply(tuning), tuning);
+
+        let easy = EASY.apply(tuning);
+        assert_eq!(easy.obstacle_spawn_interval, 135);
+        assert_eq!(easy.miss_penalty, 13);
+        assert_eq!(easy.gravity, tuning.gravity);
+
+        let insane = INSANE.apply(tuning);
+        assert_eq!(insane.obstacle_spawn_interval, 45);
+        assert_eq!(insane.miss_penalty, 50);
+    }
+
+    #[test]
+    fn the_speed_ramp_stops_at_each_presets_cap() {
+        for preset in Preset::ALL {
+            let rules = preset.rules();
+            assert_eq!(rules.ramp(0), 0.0);
+            assert_eq!(rules.ramp(1000), rules.ramp);
+            assert_eq!(rules.ramp(u32::MAX), rules.ramp_cap, "{}", preset.name());
+        }
+        // Harder presets speed up sooner and go faster in the end
+        for pair in Preset::ALL.windows(2) {
+            let (easier, harder) = (pair[0].rules(), pair[1].rules());
+            assert!(easier.ramp(2000) < harder.ramp(2000));
+            assert!(easier.ramp_cap < harder.ramp_cap);
+        }
+    }
+
+    // Points lost when a star scrolls away on `preset`
+    fn miss_penalty_on(preset: Preset) -> u32 {
+        let mut missed = start_on(preset, 3);
+        let mut kept = start_on(preset, 3);
+        for state in [&mut missed, &mut kept] {
+            state.players[0].score = 1000;
+            state.obstacles.clear();
+            state.stars.clear();
+        }
+        missed.stars.push(Star {
+            x: -7.9,
+            y: 60.0,
+            collected: false,
+            missed: false,
+        });
+        missed.step(&[InputFrame::default()]);
+        kept.step(&[InputFrame::default()]);
+        assert_eq!(missed.mode, GameMode::Playing);
+        kept.player().score - missed.player().score
+    }
+
+    #[test]
+    fn missed_items_cost_more_on_harder_presets() {
+        let penalties: Vec<u32> = Preset::ALL.iter().map(|&preset| miss_penalty_on(preset)).collect();
+        assert!(penalties.windows(2).all(|pair| pair[0] < pair[1]), "{penalties:?}");
+        assert_eq!(penalties[3], 2 * penalties[1]);
+    }
+
+    // Play a run on the saved preset the way `update` does, until it ends
+    fn finish_on(state: &mut GameState, preset: Preset, seed: u32) {
+        state.pick_preset(preset);
+        state.play_run(state.own_setup(RunKind::Endless, seed));
+        while state.mode == GameMode::Playing {
+            let inputs = [scripted_input(state.run_frames)];
+            state.replay.push(&inputs);
+            state.step(&inputs);
+            state.record_checkpoint();
+        }
+        state.finish_run();
+    }
+
+    #[test]
+    fn each_preset_keeps_its_own_high_score_and_ghost() {
+        let mut state = GameState::new();
+        finish_on(&mut state, Preset::Easy, 11);
+        let easy = state.player().score;
+        finish_on(&mut state, Preset::Hard, 12);
+        let hard = state.player().score;
+        assert!(easy > 0 && hard > 0);
+
+        assert_eq!(state.save.high_score(Preset::Easy), easy);
+        assert_eq!(state.save.high_score(Preset::Hard), hard);
+        assert_eq!(state.save.high_score(Preset::Normal), 0);
+
+        let bests: Vec<(&str, Preset)> =
+            state.ghosts.iter().map(|ghost| (ghost.name.as_str(), ghost.replay.setup().preset)).collect();
+        assert!(bests.contains(&("YOUR BEST EASY", Preset::Easy)), "{bests:?}");
+        assert!(bests.contains(&("YOUR BEST HARD", Preset::Hard)), "{bests:?}");
+        assert!(!bests.iter().any(|&(name, _)| name == "YOUR BEST"));
+    }
+}
@@ -0,0 +1,177 @@
+use turbo::*;
+
+// One primitive the renderer wants on screen
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum DrawCommand {
+    Clear { color: u32 },
+    Rect { x: i32, y: i32, w: u32, h: u32, color: u32 },
+    Circle { x: i32, y: i32, d: u32, color: u32 },
+    Sprite { name: &'static str, x: i32, y: i32, opacity: f32 },
+    Text { text: String, x: i32, y: i32, font: &'static str, color: u32 },
+}
+
+// Ordered draw commands for one frame
+// Rendering code only records into this; `flush` hands it to Turbo
+#[derive(Debug, Default)]
+pub(crate) struct DrawList {
+    commands: Vec<DrawCommand>,
+}
+
+impl DrawList {
+    pub(crate) fn commands(&self) -> &[DrawCommand] {
+        &self.commands
+    }
+
+    pub(crate) fn clear(&mut self, color: u32) {
+        self.commands.push(DrawCommand::Clear { color });
+    }
+
+    pub(crate) fn rect(&mut self, x: i32, y: i32, w: u32, h: u32, color: u32) {
+        self.commands.push(DrawCommand::Rect { x, y, w, h, color });
+    }
+
+    pub(crate) fn circ(&mut self, x: i32, y: i32, d: u32, color: u32) {
+        self.commands.push(DrawCommand::Circle { x, y, d, color });
+    }
+
+    pub(crate) fn sprite(&mut self, name: &'static str, x: i32, y: i32, opacity: f32) {
+        self.commands.push(DrawCommand::Sprite { name, x, y, opacity });
+    }
+
+    pub(crate) fn text(&mut self, text: impl Into<String>, x: i32, y: i32, font: &'static str, color: u32) {
+        self.commands.push(DrawCommand::Text {
+            text: text.into(),
+            x,
+            y,
+            font,
+            color,
+        });
+    }
+
+    // Turbo backend: issue every recorded command in order
+    pub(crate) fn flush(&self) {
+        for command in self.commands() {
+            match command {
+                DrawCommand::Clear { color } => clear(*color),
+                DrawCommand::Rect { x, y, w, h, color } => {
+                    rect!(x = *x, y = *y, w = *w, h = *h, color = *color);
+                }
+                DrawCommand::Circle { x, y, d, color } => {
+                    circ!(x = *x, y = *y, d = *d, color = *color);
+                }
+                DrawCommand::Sprite { name, x, y, opacity } => {
+                    sprite!(name, x = *x, y = *y, opacity = *opacity);
+                }
+                DrawCommand::Text { text, x, y, font, color } => {
+                    text!(text, x = *x, y = *y, font = font, color = *color);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::replay::fnv1a;
+    use crate::tests::{record, start};
+    use crate::{GameMode, GameState, RunKind};
+
+    // Runs of more shapes than this in a row (sky, scenery) are summarised as a count and a hash
+    const SCENERY_RUN: usize = 8;
+
+    fn line(command: &DrawCommand) -> String {
+        match command {
+            DrawCommand::Clear { color } => format!("clear {color:08x}"),
+            DrawCommand::Rect { x, y, w, h, color } => format!("rect {x},{y} {w}x{h} {color:08x}"),
+            DrawCommand::Circle { x, y, d, color } => format!("circ {x},{y} d{d} {color:08x}"),
+            DrawCommand::Sprite { name, x, y, opacity } => format!("sprite {name} {x},{y} {opacity:.2}"),
+            DrawCommand::Text { text, x, y, font, color } => format!("text {x},{y} {font} {color:08x} {text:?}"),
+        }
+    }
+
+    // One line per command, with long runs of plain shapes folded into one
+    fn snapshot(draw: &DrawList) -> String {
+        let mut out = String::new();
+        let mut shapes: Vec<String> = Vec::new();
+        let flush = |shapes: &mut Vec<String>, out: &mut String| {
+            if shapes.len() > SCENERY_RUN {
+                let hash = fnv1a(shapes.join("\n").as_bytes());
+                out.push_str(&format!("{} shapes {hash:016x}\n", shapes.len()));
+            } else {
+                for shape in shapes.iter() {
+                    out.push_str(shape);
+                    out.push('\n');
+                }
+            }
+            shapes.clear();
+        };
+        for command in draw.commands() {
+            match command {
+                DrawCommand::Rect { .. } | DrawCommand::Circle { .. } => shapes.push(line(command)),
+                _ => {
+                    flush(&mut shapes, &mut out);
+                    out.push_str(&line(command));
+                    out.push('\n');
+                }
+            }
+        }
+        flush(&mut shapes, &mut out);
+        out
+    }
+
+    // Compare a frame's commands with testdata/snapshots/<name>.txt
+    // Run with UPDATE_SNAPSHOTS=1 to accept a deliberate layout change
+    fn assert_snapshot(name: &str, draw: &DrawList) {
+        let actual = snapshot(draw);
+        let path = format!("{}/testdata/snapshots/{name}.txt", env!("CARGO_MANIFEST_DIR"));
+        if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+            std::fs::write(&path, &actual).unwrap();
+            return;
+        }
+        let expected = std::fs::read_to_string(&path)
+            .unwrap_or_else(|_| panic!("no snapshot at {path}; run with UPDATE_SNAPSHOTS=1 to record it"));
+        if let Some((line, (expected, actual))) = expected
+            .lines()
+            .zip(actual.lines())
+            .enumerate()
+            .find(|(_, (expected, actual))| expected != actual)
+        {
+            panic!("{name} snapshot differs at command {line}:\n  expected {expected}\n  actual   {actual}");
+        }
+        assert_eq!(
+            expected.lines().count(),
+            actual.lines().count(),
+            "{name} snapshot has a different number of commands"
+        );
+    }
+
+    #[test]
+    fn title_screen() {
+        let state = GameState::new();
+        assert_snapshot("title", &state.draw_frame());
+    }
+
+    #[test]
+    fn playing_hud() {
+        let state = record(RunKind::Endless, 3, 90);
+        assert_eq!(state.mode, GameMode::Playing);
+        assert_snapshot("playing", &state.draw_frame());
+    }
+
+    #[test]
+    fn game_over_screen() {
+        let state = record(RunKind::Endless, 3, 20_000);
+        assert_eq!(state.mode, GameMode::GameOver);
+        assert_snapshot("game-over", &state.draw_frame());
+    }
+
+    #[test]
+    fn versus_hud() {
+        let mut state = start(RunKind::Versus, 3);
+        for _ in 0..60 {
+            state.step(&[]);
+        }
+        assert_snapshot("versus", &state.draw_frame());
+    }
+}
@@ -0,0 +1,712 @@
+use turbo::*;
+
+use crate::chunks::{Chunk, ChunkItem, ChunkObstacle, Difficulty, PowerUpSlot, SPAWN_X};
+use crate::draw::DrawList;
+use crate::levels::{self, LevelFile, MAX_DX, MAX_LIFT};
+use crate::{
+    apply_opacity, GameMode, GameState, Obstacle, ObstacleType, PowerUpType, RunKind, COLOR_AURORA_GREEN, COLOR_AURORA_RED,
+    COLOR_STAR, COLOR_TEXT, COLOR_UI_BG, GROUND_Y,
+};
+
+// Screen x of the chunk's start when the timeline isn't scrolled
+const VIEW_LEFT: f32 = 24.0;
+// Cursor speed while a direction is held (px per frame)
+const CURSOR_STEP: f32 = 2.0;
+// How close (px) the cursor has to be to an entity to erase it
+const PICK_RADIUS: f32 = 8.0;
+// Longest chunk name the rename box takes
+const MAX_NAME_LEN: usize = 24;
+// How long status messages stay up
+const STATUS_FRAMES: u32 = 180;
+// Toolbar along the top of the screen, timeline below it down to the ground
+const TOOLBAR_HEIGHT: i32 = 11;
+const TIMELINE_HEIGHT: u32 = GROUND_Y as u32 - TOOLBAR_HEIGHT as u32;
+
+// What A / left click puts down
+#[turbo::serialize]
+#[derive(PartialEq)]
+pub(crate) enum Tool {
+    Obstacle(ObstacleType),
+    Star,
+    // `None` leaves the slot open for a random gift
+    PowerUp(Option<PowerUpType>),
+}
+
+impl Tool {
+    fn all() -> [Tool; 10] {
+        [
+            Tool::Obstacle(ObstacleType::Crystal),
+            Tool::Obstacle(ObstacleType::FloatingRock),
+            Tool::Obstacle(ObstacleType::CandyCane),
+            Tool::Star,
+            Tool::PowerUp(None),
+            Tool::PowerUp(Some(PowerUpType::Shield)),
+            Tool::PowerUp(Some(PowerUpType::SlowMo)),
+            Tool::PowerUp(Some(PowerUpType::Magnet)),
+            Tool::PowerUp(Some(PowerUpType::DoublePoints)),
+            Tool::PowerUp(Some(PowerUpType::Heart)),
+        ]
+    }
+
+    fn next(&self) -> Tool {
+        let tools = Tool::all();
+        let index = tools.iter().position(|tool| tool == self).unwrap_or(0);
+        tools[(index + 1) % tools.len()].clone()
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            Tool::Obstacle(ObstacleType::Crystal) => "CRYSTAL",
+            Tool::Obstacle(ObstacleType::FloatingRock) => "ROCK",
+            Tool::Obstacle(ObstacleType::CandyCane) => "CANE",
+            Tool::Star => "STAR",
+            Tool::PowerUp(None) => "GIFT ?",
+            Tool::PowerUp(Some(PowerUpType::Shield)) => "GIFT SHLD",
+            Tool::PowerUp(Some(PowerUpType::SlowMo)) => "GIFT SLOW",
+            Tool::PowerUp(Some(PowerUpType::Magnet)) => "GIFT MAG",
+            Tool::PowerUp(Some(PowerUpType::DoublePoints)) => "GIFT 2X",
+            Tool::PowerUp(Some(PowerUpType::Heart)) => "HEART",
+        }
+    }
+}
+
+// Chunk being authored in `GameMode::Editor`
+// Lives in the game state, so a hot reload doesn't lose work in progress
+#[turbo::serialize]
+pub(crate) struct Editor {
+    pub(crate) draft: Chunk,
+    tool: Tool,
+    // Timeline position (dx) at the left edge of the view
+    scroll: f32,
+    cursor_dx: f32,
+    cursor_y: f32,
+    // Last library chunk opened with the library button
+    library_index: usize,
+    // Name being typed while renaming the draft
+    rename: Option<String>,
+    status: String,
+    status_timer: u32,
+    // The current run is a play-test of the draft and returns here when it ends
+    pub(crate) playtesting: bool,
+}
+
+impl Editor {
+    pub(crate) fn new() -> Self {
+        Self {
+            draft: Chunk {
+                name: "new chunk".to_string(),
+                difficulty: Difficulty::Easy,
+                obstacles: Vec::new(),
+                stars: Vec::new(),
+                star_arcs: Vec::new(),
+                powerup: None,
+            },
+            tool: Tool::Obstacle(ObstacleType::Crystal),
+            scroll: 0.0,
+            cursor_dx: 64.0,
+            cursor_y: GROUND_Y,
+            library_index: 0,
+            rename: None,
+            status: String::new(),
+            status_timer: 0,
+            playtesting: false,
+        }
+    }
+
+    fn set_status(&mut self, status: impl Into<String>) {
+        self.status = status.into();
+        self.status_timer = STATUS_FRAMES;
+    }
+
+    // Edit the name being typed: ENTER keeps it, ESC puts the old one back
+    fn update_rename(&mut self, keys: &keyboard::Keyboard) {
+        let Some(name) = &mut self.rename else {
+            return;
+        };
+        if keys.backspace().just_pressed() {
+            name.pop();
+        }
+        for c in keys.chars() {
+            if name.chars().count() < MAX_NAME_LEN && !c.is_control() {
+                name.push(c);
+            }
+        }
+        if keys.escape().just_pressed() {
+            self.rename = None;
+        } else if keys.enter().just_pressed() {
+            let name = self.rename.take().unwrap_or_default();
+            let name = name.trim();
+            if name.is_empty() {
+                self.set_status("A CHUNK NEEDS A NAME");
+            } else {
+                self.draft.name = name.to_string();
+                // Exporting adds it to the library, or replaces the chunk that already has this name
+                self.set_status(format!("RENAMED \"{name}\""));
+            }
+        }
+    }
+
+    fn screen_x(&self, dx: f32) -> i32 {
+        (VIEW_LEFT + dx - self.scroll) as i32
+    }
+
+    // Keep the cursor on screen, scrolling the timeline along with it
+    fn follow_cursor(&mut self) {
+        let view = (256.0 - 2.0 * VIEW_LEFT).max(1.0);
+        if self.cursor_dx - self.scroll > view {
+            self.scroll = self.cursor_dx - view;
+        } else if self.cursor_dx < self.scroll {
+            self.scroll = self.cursor_dx;
+        }
+        self.scroll = self.scroll.clamp(0.0, MAX_DX);
+    }
+
+    // Put the current tool down at the cursor
+    fn place(&mut self) {
+        let dx = self.cursor_dx;
+        let y = self.cursor_y;
+        match &self.tool {
+            Tool::Obstacle(obstacle_type) => {
+                self.draft.obstacles.push(ChunkObstacle {
+                    dx,
+                    obstacle_type: obstacle_type.clone(),
+                    lift: lift_at(obstacle_type, y),
+                });
+                self.draft.obstacles.sort_by(|a, b| a.dx.total_cmp(&b.dx));
+            }
+            Tool::Star => {
+                self.draft.stars.push(ChunkItem { dx, y });
+                self.draft.stars.sort_by(|a, b| a.dx.total_cmp(&b.dx));
+            }
+            // The format has one power-up slot per chunk, so this moves it
+            Tool::PowerUp(powerup_type) => {
+                self.draft.powerup = Some(PowerUpSlot {
+                    dx,
+                    y,
+                    powerup_type: powerup_type.clone(),
+                });
+            }
+        }
+    }
+
+    // Remove whatever is closest to the cursor, if anything is within reach
+    fn erase(&mut self) {
+        let (dx, y) = (self.cursor_dx, self.cursor_y);
+        let near = |x: f32, top: f32, bottom: f32| {
+            (x - dx).abs() <= PICK_RADIUS && y >= top - PICK_RADIUS && y <= bottom + PICK_RADIUS
+        };
+
+        let obstacles: Vec<_> = self.draft.obstacles_at(0.0).collect();
+        if let Some(index) = obstacles
+            .iter()
+            .position(|o| near(o.x + o.width / 2.0, o.y - o.height, o.y))
+        {
+            self.draft.obstacles.remove(index);
+        } else if let Some(index) = self.draft.stars.iter().position(|star| near(star.dx, star.y, star.y)) {
+            self.draft.stars.remove(index);
+        } else if let Some(index) = self
+            .draft
+            .star_arcs
+            .iter()
+            .position(|arc| arc.stars().any(|(x, star_y)| near(x, star_y, star_y)))
+        {
+            self.draft.star_arcs.remove(index);
+        } else if self.draft.powerup.as_ref().is_some_and(|slot| near(slot.dx, slot.y, slot.y)) {
+            self.draft.powerup = None;
+        }
+    }
+
+    // The part of the draft from the cursor on, shifted to start at 0
+    // Open power-up slots are always filled so the play-test shows them
+    fn playtest_chunk(&self, fill: PowerUpType) -> Chunk {
+        let from = self.cursor_dx;
+        let mut chunk = self.draft.clone();
+        chunk.obstacles.retain(|o| o.dx >= from);
+        for obstacle in &mut chunk.obstacles {
+            obstacle.dx -= from;
+        }
+        chunk.stars = self
+            .draft
+            .star_positions()
+            .filter(|&(dx, _)| dx >= from)
+            .map(|(dx, y)| ChunkItem { dx: dx - from, y })
+            .collect();
+        chunk.star_arcs.clear();
+        chunk.powerup = chunk
+            .powerup
+            .filter(|slot| slot.dx >= from)
+            .map(|slot| PowerUpSlot {
+                dx: slot.dx - from,
+                y: slot.y,
+                powerup_type: slot.powerup_type.or(Some(fill)),
+            });
+        chunk
+    }
+}
+
+impl GameState {
+    pub(crate) fn open_editor(&mut self) {
+        audio::stop("music-background");
+        self.mode = GameMode::Editor;
+        self.editor.playtesting = false;
+        self.obstacles.clear();
+        self.stars.clear();
+        self.powerups.clear();
+        self.floating_texts.clear();
+    }
+
+    pub(crate) fn update_editor(&mut self, gp: &gamepad::Gamepad) {
+        let editor = &mut self.editor;
+        editor.status_timer = editor.status_timer.saturating_sub(1);
+
+        // Typing a name takes over the keyboard, which doubles as the gamepad
+        let keys = keyboard::get();
+        if editor.rename.is_some() {
+            editor.update_rename(&keys);
+            return;
+        }
+
+        // Move the cursor along the timeline and up/down (heights matter for rocks, stars and gifts)
+        if gp.left.pressed() {
+            editor.cursor_dx -= CURSOR_STEP;
+        }
+        if gp.right.pressed() {
+            editor.cursor_dx += CURSOR_STEP;
+        }
+        if gp.up.pressed() {
+            editor.cursor_y -= CURSOR_STEP;
+        }
+        if gp.down.pressed() {
+            editor.cursor_y += CURSOR_STEP;
+        }
+
+        let mouse = mouse::screen();
+        // Scroll wheel pans the timeline
+        let wheel = if mouse.delta_y != 0 { mouse.delta_y } else { mouse.delta_x };
+        if wheel != 0 {
+            editor.scroll = (editor.scroll + wheel.signum() as f32 * 16.0).clamp(0.0, MAX_DX);
+        }
+
+        // Toolbar buttons
+        let clicked = |x: i32, w: i32| mouse.left_clicked_bounds(Bounds::new(x, 0, w, TOOLBAR_HEIGHT));
+        let tool_pressed = gp.x.just_pressed() || clicked(0, 52);
+        let difficulty_pressed = gp.y.just_pressed() || clicked(54, 40);
+        let rename_pressed = clicked(96, 62);
+        let library_pressed = clicked(160, 10);
+        let play_pressed = gp.start.just_pressed() || clicked(172, 22);
+        let export_pressed = gp.select.just_pressed() || clicked(196, 32);
+        let exit_pressed = keys.escape().just_pressed() || clicked(230, 26);
+        let mut place_pressed = gp.a.just_pressed();
+        let mut erase_pressed = gp.b.just_pressed();
+
+        // Clicking the timeline moves the cursor there and places/erases
+        let on_timeline = mouse.y > TOOLBAR_HEIGHT && mouse.y as f32 <= GROUND_Y;
+        if on_timeline && (mouse.left.just_pressed() || mouse.right.just_pressed()) {
+            editor.cursor_dx = mouse.x as f32 - VIEW_LEFT + editor.scroll;
+            editor.cursor_y = mouse.y as f32;
+            place_pressed |= mouse.left.just_pressed();
+            erase_pressed |= mouse.right.just_pressed();
+        }
+
+        editor.cursor_dx = editor.cursor_dx.round().clamp(0.0, MAX_DX);
+        editor.cursor_y = editor.cursor_y.round().clamp(TOOLBAR_HEIGHT as f32 + 4.0, GROUND_Y);
+        editor.follow_cursor();
+
+        if tool_pressed {
+            editor.tool = editor.tool.next();
+        }
+        if difficulty_pressed {
+            editor.draft.difficulty = match editor.draft.difficulty {
+                Difficulty::Easy => Difficulty::Medium,
+                Difficulty::Medium => Difficulty::Hard,
+                Difficulty::Hard => Difficulty::Easy,
+            };
+        }
+        if place_pressed {
+            editor.place();
+        }
+        if erase_pressed {
+            editor.erase();
+        }
+        if rename_pressed {
+            editor.rename = Some(editor.draft.name.clone());
+        }
+        if library_pressed {
+            self.open_library_chunk();
+        }
+
+        if export_pressed {
+            self.export_levels();
+        }
+        if exit_pressed {
+            self.mode = GameMode::Title;
+        } else if play_pressed {
+            self.start_playtest();
+        }
+    }
+
+    // Cycle the draft through the loaded chunks, so existing ones can be tweaked
+    fn open_library_chunk(&mut self) {
+        if self.chunks.is_empty() {
+            return;
+        }
+        let index = self.editor.library_index % self.chunks.len();
+        self.editor.library_index = index + 1;
+        self.editor.draft = self.chunks[index].clone();
+        self.editor.cursor_dx = 0.0;
+        self.editor.scroll = 0.0;
+        let name = self.editor.draft.name.clone();
+        self.editor.set_status(format!("OPENED \"{name}\""));
+    }
+
+    // Validate the draft and write out a levels.json with it in place of any chunk of the same name
+    // Turbo can't write files, so the text goes to the save, the host page (`levels` event) and the log
+    fn export_levels(&mut self) {
+        let draft = self.editor.draft.clone();
+        let mut chunks: Vec<Chunk> = self.chunks.iter().filter(|c| c.name != draft.name).cloned().collect();
+        let index = self.chunks.iter().position(|chunk| chunk.name == draft.name).unwrap_or(chunks.len());
+        chunks.insert(index, draft);
+
+        let file = LevelFile {
+            chunks,
+            campaign: self.campaign.clone(),
+        };
+        let text = match serde_json::to_string_pretty(&file) {
+            Ok(text) => text,
+            Err(err) => {
+                self.editor.set_status(format!("EXPORT FAILED: {err}"));
+                return;
+            }
+        };
+        // Same checks as loading the file, so an export always loads
+        match levels::parse(&text) {
+            Ok(file) => {
+                log!("[editor] {}:\n{text}", levels::LEVELS_PATH);
+                events::emit("levels", &text);
+                self.save_levels(file, text);
+                self.editor.set_status("EXPORTED (see log)");
+            }
+            Err(err) => {
+                log!("[editor] not exported: {}", err.message);
+                self.editor.set_status(err.message);
+            }
+        }
+    }
+
+    // Run just the draft, from the cursor onwards
+    fn start_playtest(&mut self) {
+        self.reset_game(RunKind::Endless);
+        let fill = crate::chunks::random_powerup(&mut self.rng);
+        let chunk = self.editor.playtest_chunk(fill);
+        if chunk.obstacles.is_empty() && chunk.stars.is_empty() && chunk.powerup.is_none() {
+            self.open_editor();
+            self.editor.set_status("NOTHING AFTER THE CURSOR");
+            return;
+        }
+        self.place_chunk(&chunk, SPAWN_X);
+        // Nothing else spawns during a play-test
+        self.spawn_cursor = f32::MAX;
+        self.editor.playtesting = true;
+    }
+
+    // A play-test ends when the player dies, everything has scrolled past, or SELECT is pressed
+    pub(crate) fn check_playtest(&mut self, gp: &gamepad::Gamepad) {
+        let cleared = self.obstacles.is_empty() && self.stars.is_empty() && self.powerups.is_empty();
+        let status = if self.mode == GameMode::GameOver {
+            "HIT SOMETHING"
+        } else if cleared {
+            "CLEARED"
+        } else if gp.select.just_pressed() {
+            "STOPPED"
+        } else {
+            return;
+        };
+        let score = self.player().score;
+        self.open_editor();
+        self.editor.set_status(format!("{status} - SCORE {score}"));
+    }
+
+    pub(crate) fn render_editor(&self, draw: &mut DrawList) {
+        let editor = &self.editor;
+        let draft = &editor.draft;
+
+        // Chunk bounds and a ruler every 32px
+        let start = editor.screen_x(0.0);
+        let end = editor.screen_x(draft.length());
+        draw.rect(start, TOOLBAR_HEIGHT, 1, TIMELINE_HEIGHT, COLOR_AURORA_GREEN & 0xffffff88);
+        draw.rect(end, TOOLBAR_HEIGHT, 1, TIMELINE_HEIGHT, COLOR_AURORA_RED & 0xffffff88);
+        let first_tick = (editor.scroll / 32.0).floor() as i32 * 32;
+        for tick in (first_tick..=first_tick + 256).step_by(32) {
+            let x = editor.screen_x(tick as f32);
+            draw.rect(x, GROUND_Y as i32, 1, 4, COLOR_TEXT);
+            draw.text(format!("{tick}"), x + 2, GROUND_Y as i32 + 2, "small", COLOR_TEXT);
+        }
+
+        // Draft entities
+        for (dx, y) in draft.star_positions() {
+            draw.sprite("star", editor.screen_x(dx) - 4, y as i32 - 4, 1.0);
+        }
+        if let Some(slot) = &draft.powerup {
+            let opacity = if slot.powerup_type.is_some() { 1.0 } else { 0.5 };
+            draw.sprite(
+                powerup_sprite(slot.powerup_type.as_ref()),
+                editor.screen_x(slot.dx) - 6,
+                slot.y as i32 - 6,
+                opacity,
+            );
+        }
+        for obstacle in draft.obstacles_at(0.0) {
+            draw.sprite(
+                obstacle_sprite(&obstacle.obstacle_type),
+                editor.screen_x(obstacle.x),
+                (obstacle.y - obstacle.height) as i32,
+                1.0,
+            );
+        }
+
+        // Cursor, with a preview of what would be placed
+        let cursor_x = editor.screen_x(editor.cursor_dx);
+        let cursor_y = editor.cursor_y as i32;
+        match &editor.tool {
+            Tool::Obstacle(obstacle_type) => {
+                let lift = lift_at(obstacle_type, editor.cursor_y);
+                let preview = Obstacle::new(0.0, obstacle_type.clone(), lift);
+                draw.sprite(obstacle_sprite(obstacle_type), cursor_x, (preview.y - preview.height) as i32, 0.5);
+            }
+            Tool::Star => draw.sprite("star", cursor_x - 4, cursor_y - 4, 0.5),
+            Tool::PowerUp(powerup_type) => {
+                draw.sprite(powerup_sprite(powerup_type.as_ref()), cursor_x - 6, cursor_y - 6, 0.5);
+            }
+        }
+        draw.rect(cursor_x, TOOLBAR_HEIGHT, 1, TIMELINE_HEIGHT, COLOR_STAR & 0xffffff66);
+        draw.rect(cursor_x - 3, cursor_y, 7, 1, COLOR_STAR);
+        let position = format!("{},{}", editor.cursor_dx, editor.cursor_y);
+        draw.text(position, cursor_x + 3, cursor_y - 8, "small", COLOR_STAR);
+
+        // Toolbar
+        draw.rect(0, 0, 256, TOOLBAR_HEIGHT as u32, COLOR_UI_BG);
+        let difficulty = match draft.difficulty {
+            Difficulty::Easy => "EASY",
+            Difficulty::Medium => "MEDIUM",
+            Difficulty::Hard => "HARD",
+        };
+        draw.text(editor.tool.label(), 2, 2, "small", COLOR_STAR);
+        draw.text(difficulty, 56, 2, "small", COLOR_AURORA_GREEN);
+        match &editor.rename {
+            // Show the end of what's being typed, with a blinking cursor
+            Some(name) => {
+                let shown: Vec<char> = name.chars().collect();
+                let mut shown: String = shown[shown.len().saturating_sub(11)..].iter().collect();
+                if (self.frame / 20).is_multiple_of(2) {
+                    shown.push('_');
+                }
+                draw.rect(96, 1, 62, TOOLBAR_HEIGHT as u32 - 2, COLOR_TEXT & 0xffffff33);
+                draw.text(shown, 98, 2, "small", COLOR_STAR);
+            }
+            None => {
+                let name: String = draft.name.chars().take(12).collect();
+                draw.text(name, 98, 2, "small", COLOR_TEXT);
+            }
+        }
+        draw.text(">", 162, 2, "small", COLOR_TEXT);
+        draw.text("PLAY", 174, 2, "small", COLOR_TEXT);
+        draw.text("EXPORT", 198, 2, "small", COLOR_TEXT);
+        draw.text("EXIT", 234, 2, "small", COLOR_AURORA_RED);
+
+        // Help line, or the latest status message
+        draw.rect(0, 133, 256, 11, COLOR_UI_BG);
+        if editor.rename.is_some() {
+            draw.text("TYPE A NAME - ENTER keep  ESC cancel", 2, 135, "small", COLOR_STAR);
+        } else if editor.status_timer > 0 {
+            draw.text(&editor.status, 2, 135, "small", COLOR_STAR);
+        } else if (self.frame / 240).is_multiple_of(2) {
+            draw.text("A place B erase X tool Y diff START test", 2, 135, "small", 0xaaaaaaff);
+        } else {
+            draw.text("SELECT export  click name: rename  ESC exit", 2, 135, "small", 0xaaaaaaff);
+        }
+    }
+
+    // Label shown over a play-test run
+    pub(crate) fn render_playtest(&self, draw: &mut DrawList) {
+        if (self.frame / 30).is_multiple_of(2) {
+            draw.text("PLAY-TEST", 4, 126, "medium", COLOR_AURORA_GREEN);
+        }
+        draw.text("[SELECT] Back to editor", 4, 136, "small", apply_opacity(COLOR_TEXT, 0.7));
+    }
+}
+
+// The cursor marks a rock's bottom edge; everything else stands on the ground
+fn lift_at(obstacle_type: &ObstacleType, cursor_y: f32) -> f32 {
+    match obstacle_type {
+        ObstacleType::FloatingRock => (GROUND_Y - cursor_y).clamp(0.0, MAX_LIFT),
+        _ => 0.0,
+    }
+}
+
+fn obstacle_sprite(obstacle_type: &ObstacleType) -> &'static str {
+    match obstacle_type {
+        ObstacleType::Crystal => "crystal",
+        ObstacleType::FloatingRock => "floating-rock",
+        ObstacleType::CandyCane => "candy-cane",
+    }
+}
+
+fn powerup_sprite(powerup_type: Option<&PowerUpType>) -> &'static str {
+    match powerup_type {
+        Some(PowerUpType::Shield) | None => "present-shield",
+        Some(PowerUpType::SlowMo) => "present-slowmo",
+        Some(PowerUpType::Magnet) => "present-magnet",
+        Some(PowerUpType::DoublePoints) => "present-2x",
+        Some(PowerUpType::Heart) => "present-heart",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::replay::{decode_text, encode_text, ReplayError};
+    use crate::tests::scripted_input;
+
+    fn record_run(state: &mut GameState) -> String {
+        state.reset_game(RunKind::Endless);
+        for _ in 0..60 {
+            let input = [scripted_input(state.run_frames)];
+            state.replay.push(&input);
+            state.step(&input);
+        }
+        encode_text(&state.replay)
+    }
+
+    #[test]
+    fn runs_on_exported_chunks_are_not_passed_off_as_built_in() {
+        let mut state = GameState::new();
+        assert!(decode_text(&record_run(&mut state)).is_ok());
+
+        state.editor.draft.name = "fresh powder".to_string();
+        state.editor.draft.obstacles.push(ChunkObstacle {
+            dx: 64.0,
+            obstacle_type: ObstacleType::Crystal,
+            lift: 0.0,
+        });
+        state.export_levels();
+        assert!(state.chunks.iter().any(|chunk| chunk.name == "fresh powder"));
+        assert!(matches!(
+            decode_text(&record_run(&mut state)),
+            Err(ReplayError::SimMismatch { .. })
+        ));
+        // So they don't go in the save either
+        assert!(!state.counts_for_save);
+    }
+
+    fn editor_at(dx: f32, y: f32, tool: Tool) -> Editor {
+        let mut editor = Editor::new();
+        editor.cursor_dx = dx;
+        editor.cursor_y = y;
+        editor.tool = tool;
+        editor
+    }
+
+    #[test]
+    fn place_and_erase_at_the_cursor() {
+        let mut editor = editor_at(64.0, GROUND_Y, Tool::Obstacle(ObstacleType::Crystal));
+        editor.place();
+        editor.cursor_dx = 32.0;
+        editor.tool = Tool::Obstacle(ObstacleType::FloatingRock);
+        editor.cursor_y = GROUND_Y - 20.0;
+        editor.place();
+        // Kept in timeline order, with the rock lifted to the cursor
+        let placed: Vec<(f32, f32)> = editor.draft.obstacles.iter().map(|o| (o.dx, o.lift)).collect();
+        assert_eq!(placed, [(32.0, 20.0), (64.0, 0.0)]);
+
+        editor.tool = Tool::Star;
+        editor.cursor_dx = 100.0;
+        editor.cursor_y = 60.0;
+        editor.place();
+        assert_eq!(editor.draft.stars, [ChunkItem { dx: 100.0, y: 60.0 }]);
+
+        // One power-up slot per chunk: placing again moves it
+        editor.tool = Tool::PowerUp(None);
+        editor.place();
+        editor.cursor_dx = 120.0;
+        editor.place();
+        assert_eq!(editor.draft.powerup.as_ref().map(|slot| slot.dx), Some(120.0));
+
+        // Erasing takes the nearest thing within reach, and nothing when there's nothing near
+        editor.cursor_dx = 200.0;
+        editor.erase();
+        assert!(editor.draft.powerup.is_some() && editor.draft.stars.len() == 1 && editor.draft.obstacles.len() == 2);
+        editor.cursor_dx = 100.0;
+        editor.cursor_y = 60.0;
+        editor.erase();
+        assert!(editor.draft.stars.is_empty());
+        editor.cursor_dx = 120.0;
+        editor.erase();
+        assert!(editor.draft.powerup.is_none());
+        editor.cursor_dx = 64.0;
+        editor.cursor_y = GROUND_Y - 4.0;
+        editor.erase();
+        assert_eq!(editor.draft.obstacles.len(), 1);
+        assert_eq!(editor.draft.obstacles[0].dx, 32.0);
+    }
+
+    #[test]
+    fn playtest_starts_at_the_cursor() {
+        let mut editor = editor_at(10.0, GROUND_Y, Tool::Obstacle(ObstacleType::Crystal));
+        editor.place();
+        editor.cursor_dx = 80.0;
+        editor.place();
+        editor.tool = Tool::Star;
+        editor.cursor_dx = 90.0;
+        editor.cursor_y = 50.0;
+        editor.place();
+        editor.tool = Tool::PowerUp(None);
+        editor.cursor_dx = 100.0;
+        editor.place();
+
+        editor.cursor_dx = 50.0;
+        let chunk = editor.playtest_chunk(PowerUpType::Magnet);
+        let obstacles: Vec<f32> = chunk.obstacles.iter().map(|o| o.dx).collect();
+        assert_eq!(obstacles, [30.0]);
+        assert_eq!(chunk.stars, [ChunkItem { dx: 40.0, y: 50.0 }]);
+        let slot = chunk.powerup.unwrap();
+        // Open slots are filled so the play-test shows a gift
+        assert_eq!((slot.dx, slot.powerup_type), (50.0, Some(PowerUpType::Magnet)));
+        // The draft itself is left alone
+        assert_eq!(editor.draft.obstacles.len(), 2);
+    }
+
+    #[test]
+    fn exports_round_trip_through_the_save() {
+        let mut state = GameState::new();
+        state.editor = editor_at(64.0, GROUND_Y, Tool::Obstacle(ObstacleType::Crystal));
+        state.editor.draft.name = "fresh powder".to_string();
+        state.editor.place();
+        state.editor.tool = Tool::Star;
+        state.editor.cursor_dx = 64.0;
+        state.editor.cursor_y = 40.0;
+        state.editor.place();
+        let draft = state.editor.draft.clone();
+        let chunks = state.chunks.len();
+        state.export_levels();
+
+        let saved = state.save.levels.clone().unwrap();
+        let file = levels::parse(&saved.text).unwrap();
+        assert_eq!(file.chunks.len(), chunks + 1);
+        assert!(file.chunks.contains(&draft));
+        assert_eq!(file.chunks, state.chunks);
+
+        // A fresh session on the same save plays the export
+        let mut next = GameState::new();
+        next.save.levels = Some(saved);
+        next.reload_levels();
+        assert_eq!(next.chunks, state.chunks);
+        assert_eq!(next.sim_hash, state.sim_hash);
+
+        // Exporting a chunk that's already in the library replaces it
+        state.editor.draft.difficulty = Difficulty::Medium;
+        state.export_levels();
+        assert_eq!(state.chunks.len(), chunks + 1);
+        let exported = state.chunks.iter().find(|chunk| chunk.name == "fresh powder").unwrap();
+        assert_eq!(exported.difficulty, Difficulty::Medium);
+    }
+}
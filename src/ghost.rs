@@ -0,0 +1,373 @@
+use turbo::*;
+
+use crate::cosmetics::{self, Wardrobe};
+use crate::daily;
+use crate::difficulty::Preset;
+use crate::draw::DrawList;
+use crate::assets;
+use crate::replay::{Replay, ReplayFile, RunSetup};
+use crate::{GameMode, GameState, RunKind, COLOR_AURORA_GREEN, COLOR_AURORA_RED, COLOR_STAR, COLOR_TEXT, COLOR_UI_BG};
+
+// Teammates' runs to race, kept next to turbo.toml and compiled in (see assets.rs)
+// Each entry is a name plus the text of a `replay` event from the host page
+pub(crate) const GHOSTS_PATH: &str = "ghosts.json";
+pub(crate) const GHOSTS_SOURCE: &str = include_str!("../ghosts.json");
+
+// Rows that fit on the ghost select screen at once
+const VISIBLE_GHOSTS: usize = 6;
+
+// How see-through the ghost reindeer is
+pub(crate) const GHOST_OPACITY: f32 = 0.35;
+
+// A recorded run that can be raced
+#[turbo::serialize]
+pub(crate) struct Ghost {
+    pub(crate) name: String,
+    pub(crate) wardrobe: Wardrobe,
+    pub(crate) replay: Replay,
+}
+
+impl Ghost {
+    // Score the recorded run finished with
+    pub(crate) fn score(&self) -> u32 {
+        self.replay.checkpoints().last().map_or(0, |checkpoint| checkpoint.result.score)
+    }
+}
+
+// The ghost racing the current run
+#[turbo::serialize]
+pub(crate) struct GhostRace {
+    pub(crate) name: String,
+    wardrobe: Wardrobe,
+    pub(crate) setup: RunSetup,
+    replay: Replay,
+    // Headless run of the ghost's inputs, stepped alongside the player's
+    sim: Box<GameState>,
+    // Where the ghost was after the last frame `sim` played
+    latest: GhostFrame,
+}
+
+#[turbo::serialize]
+#[derive(Copy, PartialEq)]
+struct GhostFrame {
+    y: f32,
+    score: u32,
+}
+
+impl GhostRace {
+    fn new(ghost: &Ghost) -> Self {
+        let setup = ghost.replay.setup();
+        let mut sim = GameState::new();
+        sim.start_run(setup);
+        Self {
+            name: ghost.name.clone(),
+            wardrobe: ghost.wardrobe.clone(),
+            setup,
+            replay: ghost.replay.clone(),
+            sim: Box::new(sim),
+            latest: GhostFrame { y: 0.0, score: 0 },
+        }
+    }
+
+    // Play the ghost's inputs up to `run_frames` frames into its run, starting over if the player did
+    fn catch_up(&mut self, run_frames: u32) {
+        if run_frames < self.sim.run_frames {
+            self.sim.start_run(self.setup);
+            self.latest = GhostFrame { y: 0.0, score: 0 };
+        }
+        while self.sim.mode == GameMode::Playing && self.sim.run_frames < run_frames {
+            let Some(inputs) = self.replay.get(self.sim.run_frames) else {
+                break;
+            };
+            self.sim.step(&inputs);
+            self.latest = GhostFrame {
+                y: self.sim.player().y,
+                score: self.sim.player().score,
+            };
+        }
+    }
+
+    // Ghost after `run_frames` frames; None before the first frame and once its run is over
+    fn at(&self, run_frames: u32) -> Option<GhostFrame> {
+        (run_frames > 0 && self.sim.run_frames == run_frames).then_some(self.latest)
+    }
+
+    // Ghost's score so far (a finished ghost keeps its final score)
+    fn score(&self) -> u32 {
+        self.latest.score
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub(crate) enum GhostError {
+    SeedMismatch { ghost: u32, run: u32 },
+    SetupMismatch,
+}
+
+impl std::fmt::Display for GhostError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::SeedMismatch { ghost, run } => {
+                write!(f, "ghost was recorded on seed {ghost:08X} but this run is seed {run:08X}")
+            }
+            Self::SetupMismatch => write!(f, "ghost was recorded with different tuning or in a different mode"),
+        }
+    }
+}
+
+// ghosts.json
+#[turbo::serialize]
+#[serde(deny_unknown_fields)]
+struct GhostFile {
+    ghosts: Vec<GhostEntry>,
+}
+
+#[turbo::serialize]
+#[serde(deny_unknown_fields)]
+struct GhostEntry {
+    name: String,
+    // What the ghost wears; the classic look if left out
+    #[serde(default)]
+    wardrobe: Wardrobe,
+    replay: ReplayFile,
+}
+
+pub(crate) fn source_hash() -> u64 {
+    assets::fingerprint(GHOSTS_SOURCE)
+}
+
+// Saved replay -> ghost, logging replays this build can't play back
+fn decode(name: String, wardrobe: Wardrobe, file: &ReplayFile, ghosts: &mut Vec<Ghost>) {
+    match file.clone().into_replay() {
+        Ok(replay) => ghosts.push(Ghost { name, wardrobe, replay }),
+        Err(err) => log!("[ghosts] {name}: {err}"),
+    }
+}
+
+impl GameState {
+    // Rebuild the list of raceable ghosts: personal bests from the save (one per preset), then ghosts.json
+    pub(crate) fn refresh_ghosts(&mut self) {
+        self.ghosts_hash = source_hash();
+        let mut ghosts = Vec::new();
+        for preset in Preset::ALL {
+            let best = self.save.high_scores.iter().find(|best| best.preset == preset);
+            if let Some(file) = best.and_then(|best| best.replay.as_ref()) {
+                let name = match preset {
+                    Preset::Normal => "YOUR BEST".to_string(),
+                    preset => format!("YOUR BEST {}", preset.name()),
+                };
+                decode(name, self.save.wardrobe.clone(), file, &mut ghosts);
+            }
+        }
+        if let Some(file) = &self.save.daily.replay {
+            let name = format!("YOUR DAILY {}", daily::date_label(self.save.daily.day));
+            decode(name, self.save.wardrobe.clone(), file, &mut ghosts);
+        }
+        match assets::parse::<GhostFile>(GHOSTS_PATH, GHOSTS_SOURCE) {
+            Ok(file) => {
+                for entry in file.ghosts {
+                    decode(entry.name, entry.wardrobe, &entry.replay, &mut ghosts);
+                }
+            }
+            Err(err) => log!("[ghosts] {err}"),
+        }
+        self.ghosts = ghosts;
+        self.ghost_cursor = self.ghost_cursor.min(self.ghosts.len().saturating_sub(1));
+    }
+
+    // Race `ghost` in the run that just started
+    // Only a replay of this exact run (same seed and tuning) stays in step with the player
+    pub(crate) fn attach_ghost(&mut self, ghost: &Ghost) -> Result<(), GhostError> {
+        let run = self.replay.setup();
+        let recorded = ghost.replay.setup();
+        if recorded.seed != run.seed {
+            return Err(GhostError::SeedMismatch {
+                ghost: recorded.seed,
+                run: run.seed,
+            });
+        }
+        if recorded != run {
+            return Err(GhostError::SetupMismatch);
+        }
+        self.ghost = Some(GhostRace::new(ghost));
+        Ok(())
+    }
+
+    // Keep the ghost level with the run being played or watched
+    pub(crate) fn update_ghost(&mut self) {
+        let run_frames = self.run_frames;
+        if let Some(ghost) = &mut self.ghost {
+            ghost.catch_up(run_frames);
+        }
+    }
+
+    // Best recorded run on the current run's seed, if there is one
+    pub(crate) fn attach_best_ghost(&mut self) {
+        let setup = self.replay.setup();
+        let best = self
+            .ghosts
+            .iter()
+            .filter(|ghost| ghost.replay.setup() == setup)
+            .max_by_key(|ghost| ghost.score())
+            .cloned();
+        if let Some(ghost) = best {
+            // Same setup, so this can't fail
+            let _ = self.attach_ghost(&ghost);
+        }
+    }
+
+    pub(crate) fn open_ghost_select(&mut self) {
+        audio::stop("music-background");
+        self.mode = GameMode::GhostSelect;
+    }
+
+    pub(crate) fn update_ghost_select(&mut self, gp: &gamepad::Gamepad) {
+        self.update_backdrop();
+
+        if gp.up.just_pressed() {
+            self.ghost_cursor = self.ghost_cursor.saturating_sub(1);
+        }
+        if gp.down.just_pressed() && self.ghost_cursor + 1 < self.ghosts.len() {
+            self.ghost_cursor += 1;
+        }
+        if gp.b.just_pressed() {
+            self.mode = GameMode::Title;
+        } else if gp.start.just_pressed() || gp.a.just_pressed() {
+            let Some(ghost) = self.ghosts.get(self.ghost_cursor).cloned() else {
+                return;
+            };
+            // Replay the ghost's own run so it lines up with the player
+            self.play_run(ghost.replay.setup());
+            if let Err(err) = self.attach_ghost(&ghost) {
+                log!("[ghosts] {}: {err}", ghost.name);
+            }
+        }
+    }
+
+    pub(crate) fn render_ghost_select(&self, draw: &mut DrawList) {
+        draw.text("GHOSTS", 104, 12, "large", COLOR_AURORA_GREEN);
+
+        if self.ghosts.is_empty() {
+            draw.text("Set a high score or add runs", 56, 56, "small", COLOR_TEXT);
+            draw.text("to ghosts.json to race them", 58, 66, "small", COLOR_TEXT);
+        }
+        let first = self.ghost_cursor.saturating_sub(VISIBLE_GHOSTS - 1);
+        for (row, (i, ghost)) in self.ghosts.iter().enumerate().skip(first).take(VISIBLE_GHOSTS).enumerate() {
+            let y = 34 + row as i32 * 14;
+            let selected = i == self.ghost_cursor;
+            if selected {
+                draw.rect(16, y - 2, 224, 12, COLOR_UI_BG);
+                draw.text(">", 20, y, "small", COLOR_STAR);
+            }
+            let color = if selected { COLOR_TEXT } else { 0xccccccff };
+            draw.text(ghost.name.to_uppercase(), 28, y, "small", color);
+            let mode = match ghost.replay.setup().kind {
+                RunKind::Endless => "ENDLESS".to_string(),
+                RunKind::Campaign(level) => format!("LEVEL {}", level + 1),
+                RunKind::Daily(_) => "DAILY".to_string(),
+                RunKind::Versus => "VERSUS".to_string(),
+                RunKind::Coop => "CO-OP".to_string(),
+            };
+            draw.text(mode, 140, y, "small", 0xaaaaaaff);
+            draw.text(format!("{}", ghost.score()), 194, y, "small", COLOR_STAR);
+        }
+
+        // Racing someone else's upgrades, hearts or difficulty is just for fun
+        if let Some(ghost) = self.ghosts.get(self.ghost_cursor) {
+            let setup = ghost.replay.setup();
+            if setup != self.own_setup(setup.kind, setup.seed) {
+                draw.text("Not your setup - won't count for records", 28, 118, "small", COLOR_AURORA_RED);
+            }
+        }
+        draw.text("[UP/DOWN] Choose  [A] Race  [B] Back", 36, 130, "small", 0xaaaaaaff);
+    }
+
+    // Translucent reindeer where the ghost was at this point of its run
+    pub(crate) fn render_ghost(&self, draw: &mut DrawList) {
+        let Some(ghost) = &self.ghost else {
+            return;
+        };
+        let Some(frame) = ghost.at(self.run_frames) else {
+            return;
+        };
+        let outfit = ghost.wardrobe.outfit();
+        cosmetics::render_outfit(draw, &outfit, self.player().x as i32, frame.y as i32, GHOST_OPACITY);
+    }
+
+    // Live points ahead of (+) or behind (-) the ghost
+    pub(crate) fn render_ghost_gap(&self, draw: &mut DrawList) {
+        let Some(ghost) = &self.ghost else {
+            return;
+        };
+        let gap = self.player().score as i64 - ghost.score() as i64;
+        let color = if gap >= 0 { COLOR_AURORA_GREEN } else { COLOR_AURORA_RED };
+        draw.text(format!("{gap:+} vs {}", ghost.name.to_uppercase()), 132, 18, "small", color);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::save::SaveData;
+    use crate::tests::{record, scripted_input};
+    use crate::InputFrame;
+
+    fn finish(state: &mut GameState, setup: RunSetup) {
+        state.play_run(setup);
+        while state.mode == GameMode::Playing {
+            state.step(&[InputFrame::default()]);
+        }
+        state.finish_run();
+    }
+
+    #[test]
+    fn runs_on_someone_elses_setup_leave_the_save_alone() {
+        let mut state = GameState::new();
+        let own = state.own_setup(RunKind::Endless, 5);
+        let teammate = RunSetup { hearts: 3, ..own };
+
+        finish(&mut state, teammate);
+        assert!(!state.counts_for_save);
+        assert_eq!(state.save, SaveData::default());
+
+        finish(&mut state, own);
+        assert!(state.counts_for_save);
+        assert_ne!(state.save, SaveData::default());
+    }
+
+    #[test]
+    fn ghosts_are_played_frame_by_frame_alongside_the_run() {
+        let recorded = record(RunKind::Endless, 21, 900);
+        let ghost = Ghost {
+            name: "rival".to_string(),
+            wardrobe: Wardrobe::default(),
+            replay: recorded.replay.clone(),
+        };
+
+        let mut state = GameState::new();
+        state.play_run(ghost.replay.setup());
+        state.attach_ghost(&ghost).unwrap();
+        // Attaching doesn't run ahead of the player
+        let race = state.ghost.as_ref().unwrap();
+        assert_eq!(race.sim.run_frames, 0);
+        assert_eq!(race.at(0), None);
+
+        // Same inputs, so the ghost is exactly where the player is every frame
+        while state.mode == GameMode::Playing && state.run_frames < recorded.run_frames {
+            let inputs = [scripted_input(state.run_frames)];
+            state.step(&inputs);
+            state.update_ghost();
+            let race = state.ghost.as_ref().unwrap();
+            let frame = race.at(state.run_frames).unwrap();
+            assert_eq!((frame.y, frame.score), (state.player().y, state.player().score));
+        }
+
+        // Starting over takes the ghost back to the start too
+        state.play_run(ghost.replay.setup());
+        state.update_ghost();
+        assert_eq!(state.ghost.as_ref().unwrap().score(), 0);
+        state.step(&[scripted_input(0)]);
+        state.update_ghost();
+        assert_eq!(state.ghost.as_ref().unwrap().sim.run_frames, 1);
+    }
+}
@@ -0,0 +1,307 @@
+use turbo::*;
+
+use crate::difficulty::Preset;
+use crate::draw::DrawList;
+use crate::player::Player;
+use crate::{GameEvent, GameMode, GameState, RunKind, COLOR_AURORA_GREEN, COLOR_AURORA_RED, COLOR_STAR, COLOR_TEXT, COLOR_UI_BG};
+
+// Frames a reindeer can't be hurt again after losing a heart
+const INVULNERABLE_FRAMES: u32 = 90;
+
+// A hit throws the reindeer up and back; it then runs back up to its spot (px, px per frame)
+const KNOCKBACK_BOUNCE: f32 = -5.0;
+const KNOCKBACK_DISTANCE: f32 = 16.0;
+const KNOCKBACK_RECOVERY: f32 = 0.25;
+
+// Open power-up slots hold a heart one time in this many while someone is missing one
+pub(crate) const HEART_CHANCE: u32 = 3;
+
+// Hearts a mode can be set to; 1 is the classic run where any hit ends it
+const HEART_OPTIONS: [u32; 4] = [1, 2, 3, 5];
+
+const RULE_ROWS: [&str; 4] = ["ENDLESS", "CAMPAIGN", "VERSUS", "CO-OP"];
+
+// The rules screen's first row picks the difficulty; the heart rows follow it
+const DIFFICULTY_ROW: usize = 0;
+
+// Hearts each mode starts a run with, picked on the rules screen (the daily run is always classic)
+#[turbo::serialize]
+#[derive(PartialEq)]
+pub(crate) struct HeartRules {
+    pub(crate) endless: u32,
+    pub(crate) campaign: u32,
+    pub(crate) versus: u32,
+    pub(crate) coop: u32,
+}
+
+impl Default for HeartRules {
+    fn default() -> Self {
+        Self {
+            endless: 1,
+            campaign: 1,
+            versus: 1,
+            coop: 1,
+        }
+    }
+}
+
+impl HeartRules {
+    pub(crate) fn for_kind(&self, kind: RunKind) -> u32 {
+        match kind {
+            RunKind::Endless => self.endless,
+            RunKind::Campaign(_) => self.campaign,
+            // Everyone plays the same daily run, so it's one heart for all
+            RunKind::Daily(_) => 1,
+            RunKind::Versus => self.versus,
+            RunKind::Coop => self.coop,
+        }
+    }
+
+    fn row_mut(&mut self, row: usize) -> &mut u32 {
+        match row {
+            0 => &mut self.endless,
+            1 => &mut self.campaign,
+            2 => &mut self.versus,
+            _ => &mut self.coop,
+        }
+    }
+}
+
+impl Player {
+    // Count down the invulnerability window and run back from a knockback
+    pub(crate) fn recover_from_hit(&mut self) {
+        self.invulnerable_timer = self.invulnerable_timer.saturating_sub(1);
+        if self.x < self.home_x {
+            self.x = (self.x + KNOCKBACK_RECOVERY).min(self.home_x);
+        }
+    }
+
+    // Blinks while it can't be hurt
+    pub(crate) fn blinking(&self, frame: u32) -> bool {
+        self.invulnerable_timer > 0 && (frame / 4) % 2 == 1
+    }
+}
+
+impl GameState {
+    // A reindeer ran into something without a shield: lose a heart, or go down on the last one
+    pub(crate) fn hurt(&mut self, index: usize, events: &mut Vec<GameEvent>) {
+        let player = &mut self.players[index];
+        if player.invulnerable_timer > 0 {
+            return;
+        }
+        if player.hearts <= 1 {
+            player.hearts = 0;
+            self.knock_out(index);
+            return;
+        }
+        player.hearts -= 1;
+        player.invulnerable_timer = INVULNERABLE_FRAMES;
+        player.combo = 0;
+        player.x -= KNOCKBACK_DISTANCE;
+        player.velocity_y = KNOCKBACK_BOUNCE;
+        player.is_jumping = false;
+        player.is_on_ground = false;
+        let (x, y) = (player.x, player.y);
+
+        self.shake_frames = 8;
+        self.shake_intensity = 3.0;
+        self.spawn_floating_text(x, y - 20.0, "OUCH!", COLOR_AURORA_RED);
+        events.push(GameEvent::HeartLost(index));
+    }
+
+    // Hearts only turn up while someone still running has room for one
+    pub(crate) fn wants_heart(&self) -> bool {
+        self.players.iter().any(|player| player.alive && player.hearts < player.max_hearts)
+    }
+
+    // Row of hearts, when the run has more than one
+    pub(crate) fn render_hearts(&self, draw: &mut DrawList, player: &Player, x: i32, y: i32) {
+        if player.max_hearts <= 1 {
+            return;
+        }
+        for heart in 0..player.max_hearts {
+            let opacity = if heart < player.hearts { 1.0 } else { 0.25 };
+            draw.sprite("heart", x + heart as i32 * 8, y, opacity);
+        }
+    }
+
+    pub(crate) fn open_rules(&mut self) {
+        self.rules_cursor = 0;
+        self.mode = GameMode::Rules;
+    }
+
+    pub(crate) fn update_rules(&mut self, gp: &gamepad::Gamepad) {
+        self.update_backdrop();
+
+        if gp.up.just_pressed() {
+            self.rules_cursor = self.rules_cursor.saturating_sub(1);
+        }
+        if gp.down.just_pressed() && self.rules_cursor < RULE_ROWS.len() {
+            self.rules_cursor += 1;
+        }
+        if (gp.left.just_pressed() || gp.right.just_pressed()) && self.rules_cursor == DIFFICULTY_ROW {
+            let step = if gp.right.just_pressed() { 1 } else { -1 };
+            self.pick_preset(self.save.preset.step(step));
+        } else if gp.left.just_pressed() || gp.right.just_pressed() {
+            let hearts = self.save.hearts.row_mut(self.rules_cursor - 1);
+            let current = HEART_OPTIONS.iter().position(|option| option == hearts).unwrap_or(0);
+            let next = if gp.right.just_pressed() {
+                (current + 1).min(HEART_OPTIONS.len() - 1)
+            } else {
+                current.saturating_sub(1)
+            };
+            *hearts = HEART_OPTIONS[next];
+            self.write_save();
+        }
+        if gp.b.just_pressed() || gp.start.just_pressed() {
+            self.mode = GameMode::Title;
+        }
+    }
+
+    pub(crate) fn render_rules(&self, draw: &mut DrawList) {
+        draw.text("RULES", 100, 10, "large", COLOR_AURORA_GREEN);
+
+        let preset = self.save.preset;
+        if self.rules_cursor == DIFFICULTY_ROW {
+            draw.rect(40, 25, 176, 11, COLOR_UI_BG);
+            draw.text(">", 44, 28, "small", COLOR_STAR);
+        }
+        draw.text("DIFFICULTY", 54, 28, "small", COLOR_TEXT);
+        let color = if preset == Preset::Normal { COLOR_TEXT } else { COLOR_AURORA_RED };
+        draw.text(format!("< {} >", preset.name()), 140, 28, "small", color);
+
+        draw.text("HEARTS PER RUN", 90, 44, "small", COLOR_TEXT);
+        let rules = &self.save.hearts;
+        let values = [rules.endless, rules.campaign, rules.versus, rules.coop];
+        for (row, (name, hearts)) in RULE_ROWS.iter().zip(values).enumerate() {
+            let y = 56 + row as i32 * 11;
+            if row + 1 == self.rules_cursor {
+                draw.rect(40, y - 3, 176, 11, COLOR_UI_BG);
+                draw.text(">", 44, y, "small", COLOR_STAR);
+            }
+            draw.text(*name, 54, y, "small", COLOR_TEXT);
+            let label = if hearts == 1 { "CLASSIC".to_string() } else { format!("{hearts} HEARTS") };
+            draw.text(format!("< {label} >"), 140, y, "small", if hearts == 1 { COLOR_TEXT } else { COLOR_AURORA_RED });
+        }
+
+        if self.rules_cursor == DIFFICULTY_ROW {
+            draw.text(preset.description(), 22, 112, "small", 0x888888ff);
+            draw.text("Campaign and daily runs are always Normal", 22, 120, "small", 0x888888ff);
+        } else {
+            draw.text("Daily runs always start with one heart", 22, 104, "small", 0x888888ff);
+            draw.text("More hearts: hits knock you back instead of", 22, 112, "small", 0x888888ff);
+            draw.text("ending the run, and hearts turn up as gifts", 22, 120, "small", 0x888888ff);
+        }
+        draw.text("[UP/DOWN] Row  [LEFT/RIGHT] Change  [B] Back", 12, 132, "small", 0xaaaaaaff);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::replay::RunSetup;
+    use crate::tests::start;
+    use crate::{InputFrame, Obstacle, ObstacleType, PowerUp, PowerUpType};
+
+    // An endless run on `hearts` hearts with nothing on the track
+    fn start_with_hearts(hearts: u32) -> GameState {
+        let mut state = start(RunKind::Endless, 4);
+        state.start_run(RunSetup { hearts, ..state.replay.setup() });
+        state.obstacles.clear();
+        state.spawn_cursor = f32::MAX;
+        state
+    }
+
+    // Put a crystal right on player one and run a frame into it
+    fn run_into_crystal(state: &mut GameState) -> Vec<GameEvent> {
+        state.obstacles.clear();
+        let x = state.player().x;
+        state.obstacles.push(Obstacle::new(x - 4.0, ObstacleType::Crystal, 0.0));
+        state.step(&[InputFrame::default()])
+    }
+
+    fn idle(state: &mut GameState, frames: u32) {
+        state.obstacles.clear();
+        for _ in 0..frames {
+            state.step(&[InputFrame::default()]);
+        }
+    }
+
+    #[test]
+    fn a_hit_costs_a_heart_and_knocks_the_reindeer_back() {
+        let mut state = start_with_hearts(3);
+        let home = state.player().x;
+        let events = run_into_crystal(&mut state);
+        assert!(events.contains(&GameEvent::HeartLost(0)));
+        assert_eq!(state.mode, GameMode::Playing);
+        assert_eq!(state.player().hearts, 2);
+        assert_eq!(state.player().x, home - KNOCKBACK_DISTANCE);
+        assert!(!state.player().is_on_ground);
+
+        // Then it runs back up to its spot
+        idle(&mut state, (KNOCKBACK_DISTANCE / KNOCKBACK_RECOVERY) as u32);
+        assert_eq!(state.player().x, home);
+        assert!(state.player().is_on_ground);
+    }
+
+    #[test]
+    fn hits_while_blinking_are_free() {
+        let mut state = start_with_hearts(3);
+        run_into_crystal(&mut state);
+        assert!(!run_into_crystal(&mut state).contains(&GameEvent::HeartLost(0)));
+        assert_eq!(state.player().hearts, 2);
+
+        idle(&mut state, INVULNERABLE_FRAMES);
+        assert_eq!(state.player().invulnerable_timer, 0);
+        assert!(run_into_crystal(&mut state).contains(&GameEvent::HeartLost(0)));
+        assert_eq!(state.player().hearts, 1);
+    }
+
+    #[test]
+    fn the_last_heart_ends_the_run() {
+        let mut state = start_with_hearts(2);
+        run_into_crystal(&mut state);
+        idle(&mut state, INVULNERABLE_FRAMES);
+        let events = run_into_crystal(&mut state);
+        assert!(events.contains(&GameEvent::GameOver));
+        assert!(!state.player().alive);
+    }
+
+    #[test]
+    fn heart_gifts_refill_up_to_the_start() {
+        let mut state = start_with_hearts(3);
+        run_into_crystal(&mut state);
+        assert!(state.wants_heart());
+
+        for expected in [3, 3] {
+            let (x, y) = (state.player().x, state.player().y);
+            state.powerups.push(PowerUp {
+                x,
+                y,
+                powerup_type: PowerUpType::Heart,
+                collected: false,
+                missed: false,
+            });
+            let events = state.step(&[InputFrame::default()]);
+            assert!(events.contains(&GameEvent::PowerUpCollected(0, PowerUpType::Heart)));
+            assert_eq!(state.player().hearts, expected);
+        }
+        assert!(!state.wants_heart());
+    }
+
+    #[test]
+    fn daily_runs_end_on_the_first_hit() {
+        let mut state = GameState::new();
+        state.save.hearts = HeartRules {
+            endless: 3,
+            campaign: 3,
+            versus: 3,
+            coop: 3,
+        };
+        state.play_run(state.own_setup(RunKind::Daily(20_000), 8));
+        assert_eq!(state.player().hearts, 1);
+        let events = run_into_crystal(&mut state);
+        assert!(!events.contains(&GameEvent::HeartLost(0)));
+        assert!(events.contains(&GameEvent::GameOver));
+    }
+}
@@ -0,0 +1,406 @@
+use turbo::*;
+
+use crate::campaign::{CampaignLevel, Rating};
+use crate::chunks::{Chunk, ChunkItem, ChunkObstacle, Difficulty, PowerUpSlot, StarArc, SPAWN_X};
+use crate::reach::JumpEnvelope;
+use crate::assets::{self, check_range, AssetError};
+use crate::replay;
+use crate::tuning;
+use crate::{GameState, ObstacleType, PowerUpType, GROUND_Y};
+
+// Designer-authored level content, kept next to turbo.toml and compiled in (see assets.rs)
+// The compiled-in copy is the fallback: an editor export kept in the save is played instead
+pub(crate) const LEVELS_PATH: &str = "levels.json";
+pub(crate) const LEVELS_SOURCE: &str = include_str!("../levels.json");
+
+// Limits on authored positions (px)
+pub(crate) const MAX_DX: f32 = 1024.0;
+pub(crate) const MAX_LIFT: f32 = GROUND_Y - 16.0;
+const MAX_STARS_PER_ARC: u32 = 16;
+
+// Limits on campaign level length (px travelled)
+const MIN_LEVEL_DISTANCE: f32 = 500.0;
+const MAX_LEVEL_DISTANCE: f32 = 100_000.0;
+
+// Top level of levels.json
+#[turbo::serialize]
+#[serde(try_from = "RawLevelFile")]
+pub(crate) struct LevelFile {
+    pub(crate) chunks: Vec<Chunk>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub(crate) campaign: Vec<CampaignLevel>,
+}
+
+// A levels.json kept in the save and played in place of the built-in one
+#[turbo::serialize]
+#[derive(PartialEq)]
+pub(crate) struct SavedLevels {
+    pub(crate) text: String,
+    // `source_hash` of the built-in levels.json it was exported over; a rebuilt file replaces it
+    pub(crate) built_in: u64,
+}
+
+// Parse and validate level content
+pub(crate) fn parse(source: &str) -> Result<LevelFile, AssetError> {
+    assets::parse(LEVELS_PATH, source)
+}
+
+pub(crate) fn source_hash() -> u64 {
+    assets::fingerprint(LEVELS_SOURCE)
+}
+
+pub(crate) fn is_zero<T: Default + PartialEq>(value: &T) -> bool {
+    *value == T::default()
+}
+
+// File-side shapes, validated into the chunk types by `TryFrom`
+// Validation runs while parsing, so errors carry the line they were found on
+
+// Chunks are checked against each other as they're read so errors point at the offending one
+fn chunk_list<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Vec<Chunk>, D::Error> {
+    struct ChunkList;
+
+    impl<'de> serde::de::Visitor<'de> for ChunkList {
+        type Value = Vec<Chunk>;
+
+        fn expecting(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.write_str("a list of chunks")
+        }
+
+        fn visit_seq<A: serde::de::SeqAccess<'de>>(self, mut seq: A) -> Result<Vec<Chunk>, A::Error> {
+            let mut chunks: Vec<Chunk> = Vec::new();
+            while let Some(chunk) = seq.next_element::<Chunk>()? {
+                if chunks.iter().any(|other| other.name == chunk.name) {
+                    return Err(serde::de::Error::custom(format!(
+                        "more than one chunk is named \"{}\"",
+                        chunk.name
+                    )));
+                }
+                chunks.push(chunk);
+            }
+            if !chunks.iter().any(|chunk| chunk.difficulty == Difficulty::Easy) {
+                return Err(serde::de::Error::custom(
+                    "`chunks` needs at least one \"easy\" chunk so runs have something to start with",
+                ));
+            }
+            Ok(chunks)
+        }
+    }
+
+    deserializer.deserialize_seq(ChunkList)
+}
+
+#[turbo::serialize]
+#[serde(deny_unknown_fields)]
+struct RawLevelFile {
+    #[serde(deserialize_with = "chunk_list")]
+    chunks: Vec<Chunk>,
+    #[serde(default)]
+    campaign: Vec<CampaignLevel>,
+}
+
+// Campaign levels refer to chunks by name, so they're checked once both lists are in
+impl TryFrom<RawLevelFile> for LevelFile {
+    type Error = String;
+
+    fn try_from(raw: RawLevelFile) -> Result<Self, String> {
+        for (i, level) in raw.campaign.iter().enumerate() {
+            if raw.campaign[..i].iter().any(|other| other.name == level.name) {
+                return Err(format!("more than one campaign level is named \"{}\"", level.name));
+            }
+            if let Some(missing) = level.script.iter().find(|name| !raw.chunks.iter().any(|c| &c.name == *name)) {
+                return Err(format!(
+                    "campaign level \"{}\" uses chunk \"{missing}\", which isn't in `chunks`",
+                    level.name
+                ));
+            }
+        }
+        Ok(Self {
+            chunks: raw.chunks,
+            campaign: raw.campaign,
+        })
+    }
+}
+
+#[turbo::serialize]
+#[serde(deny_unknown_fields)]
+pub(crate) struct RawCampaignLevel {
+    name: String,
+    distance: f32,
+    script: Vec<String>,
+    ratings: [Rating; 3],
+}
+
+impl TryFrom<RawCampaignLevel> for CampaignLevel {
+    type Error = String;
+
+    fn try_from(raw: RawCampaignLevel) -> Result<Self, String> {
+        if raw.name.trim().is_empty() {
+            return Err("`name` can't be empty".to_string());
+        }
+        if raw.script.is_empty() {
+            return Err(format!("campaign level \"{}\" has an empty `script`", raw.name));
+        }
+        // Each star has to be at least as hard to get as the one before
+        let ascending = raw.ratings.windows(2).all(|pair| {
+            pair[0].score <= pair[1].score && pair[0].stars_collected <= pair[1].stars_collected
+        });
+        if !ascending {
+            return Err(format!("`ratings` of \"{}\" must not get easier from one star to the next", raw.name));
+        }
+        Ok(Self {
+            distance: check_range("distance", raw.distance, MIN_LEVEL_DISTANCE, MAX_LEVEL_DISTANCE)?,
+            name: raw.name,
+            script: raw.script,
+            ratings: raw.ratings,
+        })
+    }
+}
+
+#[turbo::serialize]
+#[serde(deny_unknown_fields)]
+pub(crate) struct RawChunk {
+    name: String,
+    difficulty: Difficulty,
+    #[serde(default)]
+    obstacles: Vec<ChunkObstacle>,
+    #[serde(default)]
+    stars: Vec<ChunkItem>,
+    #[serde(default)]
+    star_arcs: Vec<StarArc>,
+    #[serde(default)]
+    powerup: Option<PowerUpSlot>,
+}
+
+impl TryFrom<RawChunk> for Chunk {
+    type Error = String;
+
+    fn try_from(raw: RawChunk) -> Result<Self, String> {
+        if raw.name.trim().is_empty() {
+            return Err("`name` can't be empty".to_string());
+        }
+        let chunk = Chunk {
+            name: raw.name,
+            difficulty: raw.difficulty,
+            obstacles: raw.obstacles,
+            stars: raw.stars,
+            star_arcs: raw.star_arcs,
+            powerup: raw.powerup,
+        };
+        if chunk.obstacles.is_empty() && chunk.stars.is_empty() && chunk.star_arcs.is_empty() && chunk.powerup.is_none() {
+            return Err(format!("chunk \"{}\" has nothing in it", chunk.name));
+        }
+        // Checked against the shipped tuning; the spawner re-checks at whatever a run is using
+        let tuning = tuning::built_in();
+        let obstacles: Vec<_> = chunk.obstacles_at(SPAWN_X).collect();
+        if !JumpEnvelope::new(&tuning).clearable(&obstacles, tuning.scroll_speed_base) {
+            return Err(format!(
+                "chunk \"{}\" can't be cleared by any jump at the starting scroll speed",
+                chunk.name
+            ));
+        }
+        Ok(chunk)
+    }
+}
+
+#[turbo::serialize]
+#[serde(deny_unknown_fields)]
+pub(crate) struct RawObstacle {
+    dx: f32,
+    #[serde(rename = "type")]
+    obstacle_type: ObstacleType,
+    #[serde(default)]
+    lift: f32,
+}
+
+impl TryFrom<RawObstacle> for ChunkObstacle {
+    type Error = String;
+
+    fn try_from(raw: RawObstacle) -> Result<Self, String> {
+        let lift = match raw.obstacle_type {
+            ObstacleType::FloatingRock => check_range("lift", raw.lift, 0.0, MAX_LIFT)?,
+            _ if raw.lift != 0.0 => return Err("`lift` only applies to floating_rock".to_string()),
+            _ => 0.0,
+        };
+        Ok(Self {
+            dx: check_range("dx", raw.dx, 0.0, MAX_DX)?,
+            obstacle_type: raw.obstacle_type,
+            lift,
+        })
+    }
+}
+
+#[turbo::serialize]
+#[serde(deny_unknown_fields)]
+pub(crate) struct RawItem {
+    dx: f32,
+    y: f32,
+}
+
+impl TryFrom<RawItem> for ChunkItem {
+    type Error = String;
+
+    fn try_from(raw: RawItem) -> Result<Self, String> {
+        Ok(Self {
+            dx: check_range("dx", raw.dx, 0.0, MAX_DX)?,
+            y: check_range("y", raw.y, 0.0, GROUND_Y)?,
+        })
+    }
+}
+
+#[turbo::serialize]
+#[serde(deny_unknown_fields)]
+pub(crate) struct RawPowerUpSlot {
+    dx: f32,
+    y: f32,
+    #[serde(default, rename = "type")]
+    powerup_type: Option<PowerUpType>,
+}
+
+impl TryFrom<RawPowerUpSlot> for PowerUpSlot {
+    type Error = String;
+
+    fn try_from(raw: RawPowerUpSlot) -> Result<Self, String> {
+        Ok(Self {
+            dx: check_range("dx", raw.dx, 0.0, MAX_DX)?,
+            y: check_range("y", raw.y, 0.0, GROUND_Y)?,
+            powerup_type: raw.powerup_type,
+        })
+    }
+}
+
+#[turbo::serialize]
+#[serde(deny_unknown_fields)]
+pub(crate) struct RawStarArc {
+    dx: f32,
+    y: f32,
+    width: f32,
+    height: f32,
+    count: u32,
+}
+
+impl TryFrom<RawStarArc> for StarArc {
+    type Error = String;
+
+    fn try_from(raw: RawStarArc) -> Result<Self, String> {
+        if raw.count == 0 || raw.count > MAX_STARS_PER_ARC {
+            return Err(format!("`count` must be between 1 and {MAX_STARS_PER_ARC}, got {}", raw.count));
+        }
+        let dx = check_range("dx", raw.dx, 0.0, MAX_DX)?;
+        let y = check_range("y", raw.y, 0.0, GROUND_Y)?;
+        Ok(Self {
+            dx,
+            y,
+            width: check_range("width", raw.width, 0.0, MAX_DX - dx)?,
+            height: check_range("height", raw.height, -(GROUND_Y - y), y)?,
+            count: raw.count,
+        })
+    }
+}
+
+impl GameState {
+    // (Re)load the chunk library: the levels.json in the save if there is one, else the built-in one
+    // Keeps the current chunks if it doesn't validate
+    pub(crate) fn reload_levels(&mut self) {
+        self.levels_hash = source_hash();
+        if self.save.levels.as_ref().is_some_and(|saved| saved.built_in != self.levels_hash) {
+            log!("[levels] {LEVELS_PATH} was rebuilt, so it replaces the one in the save");
+            self.save.levels = None;
+            self.write_save();
+        }
+        let (source, from) = match &self.save.levels {
+            Some(saved) => (saved.text.clone(), "save"),
+            None => (LEVELS_SOURCE.to_string(), "build"),
+        };
+        match parse(&source) {
+            Ok(file) => self.use_levels(file, &source),
+            Err(err) => log!("[levels] {err} (in the {from})"),
+        }
+    }
+
+    // Keep `source` in the save and play on it from the next run, until levels.json is rebuilt
+    pub(crate) fn save_levels(&mut self, file: LevelFile, source: String) {
+        self.use_levels(file, &source);
+        self.save.levels = Some(SavedLevels {
+            text: source,
+            built_in: source_hash(),
+        });
+        self.write_save();
+    }
+
+    fn use_levels(&mut self, file: LevelFile, source: &str) {
+        self.chunks = file.chunks;
+        self.campaign = file.campaign;
+        // Runs on anything but the built-in file can't be played back by anyone without it
+        self.sim_hash = replay::library_sim_hash(source);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn edited_levels() -> String {
+        let mut file = parse(LEVELS_SOURCE).unwrap();
+        let mut chunk = file.chunks[0].clone();
+        chunk.name = "fresh powder".to_string();
+        file.chunks.push(chunk);
+        serde_json::to_string_pretty(&file).unwrap()
+    }
+
+    #[test]
+    fn shipped_levels_parse() {
+        let file = parse(LEVELS_SOURCE).unwrap();
+        assert!(file.chunks.iter().any(|chunk| chunk.difficulty == Difficulty::Easy));
+        assert!(!file.campaign.is_empty());
+    }
+
+    #[test]
+    fn errors_point_at_the_line_column_and_field() {
+        let source = r#"{
+  "chunks": [
+    { "name": "a", "difficulty": "easy", "obstacles": [{ "dx": 0, "type": "crystal", "lift": 5 }] }
+  ]
+}"#;
+        let err = parse(source).unwrap_err();
+        assert_eq!((err.path, err.line), (LEVELS_PATH, 3));
+        assert!(err.column > 0);
+        assert_eq!(err.message, "`lift` only applies to floating_rock");
+        assert_eq!(err.to_string(), format!("levels.json:3:{}: `lift` only applies to floating_rock", err.column));
+
+        let err = parse(r#"{ "chunks": [{ "name": "a", "difficulty": "easy", "stars": [{ "dx": 0, "y": 999 }] }] }"#).unwrap_err();
+        assert_eq!(err.line, 1);
+        assert!(err.message.contains("`y` must be between 0 and"), "{}", err.message);
+
+        let err = parse("{\n  \"chunkz\": []\n}").unwrap_err();
+        assert_eq!(err.line, 2);
+        assert!(err.message.starts_with("unknown field `chunkz`"), "{}", err.message);
+    }
+
+    #[test]
+    fn levels_in_the_save_are_played_instead_of_the_built_in_ones() {
+        let mut state = GameState::new();
+        let text = edited_levels();
+        state.save.levels = Some(SavedLevels {
+            text: text.clone(),
+            built_in: source_hash(),
+        });
+        state.reload_levels();
+        assert!(state.chunks.iter().any(|chunk| chunk.name == "fresh powder"));
+        assert_eq!(state.sim_hash, replay::library_sim_hash(&text));
+        assert_ne!(state.sim_hash, replay::sim_hash());
+    }
+
+    #[test]
+    fn a_rebuilt_levels_json_replaces_the_saved_one() {
+        let mut state = GameState::new();
+        state.save.levels = Some(SavedLevels {
+            text: edited_levels(),
+            built_in: source_hash() ^ 1,
+        });
+        state.reload_levels();
+        assert_eq!(state.save.levels, None);
+        assert!(!state.chunks.iter().any(|chunk| chunk.name == "fresh powder"));
+        assert_eq!(state.sim_hash, replay::sim_hash());
+    }
+}
@@ -1,15 +1,58 @@
 use turbo::*;
 
-// Constants
-const GRAVITY: f32 = 0.8;
-const JUMP_POWER: f32 = -9.5;
-const MIN_JUMP_POWER: f32 = -5.0;
-const MAX_JUMP_HOLD: u32 = 12;
+mod achievements;
+mod assets;
+mod campaign;
+mod chunks;
+mod coop;
+mod cosmetics;
+mod daily;
+mod difficulty;
+mod draw;
+mod editor;
+mod ghost;
+mod health;
+mod levels;
+mod missions;
+mod net;
+mod player;
+mod reach;
+mod replay;
+mod rng;
+mod save;
+mod shop;
+mod tuning;
+mod versus;
+
+// Headless tooling (soak testing, leaderboard verification), not called by the game itself
+#[cfg_attr(not(test), allow(dead_code))]
+mod bot;
+#[cfg_attr(not(test), allow(dead_code))]
+mod verify;
+
+use achievements::{RunProgress, Toast};
+use campaign::CampaignLevel;
+use chunks::Chunk;
+use cosmetics::{TrailShape, TrailStyle};
+use difficulty::Preset;
+use draw::DrawList;
+use editor::Editor;
+use ghost::{Ghost, GhostRace};
+use missions::MissionRun;
+use net::Race;
+use player::Player;
+use replay::{Replay, RunSetup};
+use rng::Rng;
+use save::SaveData;
+use shop::Modifiers;
+use tuning::Tuning;
+use versus::PLAYER_COLORS;
+
+// Constants (balance values live in tuning.json)
 const PLAYER_X: f32 = 40.0;
 const GROUND_Y: f32 = 100.0;
-const SCROLL_SPEED_BASE: f32 = 3.0;
-const OBSTACLE_SPAWN_INTERVAL: u32 = 90;
 const PARTICLE_SPAWN_RATE: u32 = 2;
+const SPAWN_ATTEMPTS: u32 = 4;
 
 // Colors - Christmas palette
 const COLOR_SKY_TOP: u32 = 0x001133ff;      // Dark blue night
@@ -18,12 +61,8 @@ const COLOR_SKY_BOTTOM: u32 = 0x4488aaff;   // Light blue snowy
 const COLOR_AURORA_RED: u32 = 0xff0000ff;   // Christmas red
 const COLOR_AURORA_GREEN: u32 = 0x00ff00ff; // Christmas green
 const COLOR_AURORA_GOLD: u32 = 0xffd700ff;  // Christmas gold
-const COLOR_PLAYER: u32 = 0xffffffff;
 const COLOR_PLAYER_GLOW: u32 = 0x00ffffff;
-const COLOR_OBSTACLE: u32 = 0x88ffffff;
 const COLOR_STAR: u32 = 0xffff00ff;
-const COLOR_PRESENT: u32 = 0xff0000ff;
-const COLOR_SNOWFLAKE: u32 = 0xeeeeeeff;
 const COLOR_TEXT: u32 = 0xffffffff;
 const COLOR_UI_BG: u32 = 0x000000aa;
 const COLOR_PERFECT: u32 = 0xffff00ff;
@@ -32,8 +71,6 @@ const COLOR_TREE_TRUNK: u32 = 0x663300ff;
 const COLOR_TREE_GREEN: u32 = 0x00aa00ff;
 const COLOR_SNOW_WHITE: u32 = 0xffffffff;
 const COLOR_SNOW_BLUE: u32 = 0xddeeffff;
-const COLOR_MOUNTAIN_DARK: u32 = 0x223344ff;
-const COLOR_MOUNTAIN_SNOW: u32 = 0xeeeeffff;
 
 // Game mode enum
 #[turbo::serialize]
@@ -42,11 +79,46 @@ enum GameMode {
     Title,
     Playing,
     GameOver,
+    Replay,
+    Editor,
+    LevelSelect,
+    LevelClear,
+    GhostSelect,
+    Lobby,
+    Achievements,
+    Shop,
+    Wardrobe,
+    Rules,
+}
+
+// What kind of run is being played
+#[turbo::serialize]
+#[derive(Copy, PartialEq)]
+enum RunKind {
+    Endless,
+    // Index into the campaign in levels.json
+    Campaign(usize),
+    // Seeded from the date (days since 1970-01-01 UTC) so everyone gets the same run that day
+    Daily(u32),
+    // Two players on one screen, first one down loses
+    Versus,
+    // Two players on one screen sharing a pool of lives, over once both are down
+    Coop,
+}
+
+impl RunKind {
+    fn players(self) -> usize {
+        match self {
+            RunKind::Versus | RunKind::Coop => 2,
+            _ => 1,
+        }
+    }
 }
 
 // Obstacle type enum
 #[turbo::serialize]
 #[derive(PartialEq)]
+#[serde(rename_all = "snake_case")]
 enum ObstacleType {
     Crystal,
     FloatingRock,
@@ -56,11 +128,27 @@ enum ObstacleType {
 // Power-up types
 #[turbo::serialize]
 #[derive(PartialEq)]
+#[serde(rename_all = "snake_case")]
 enum PowerUpType {
     Shield,
     SlowMo,
     Magnet,
     DoublePoints,
+    // Gives back a lost heart (only turns up when runs have more than one)
+    Heart,
+}
+
+impl PowerUpType {
+    // One bit per kind, for tracking which gifts a run has picked up
+    fn bit(&self) -> u8 {
+        match self {
+            PowerUpType::Shield => 0b0001,
+            PowerUpType::SlowMo => 0b0010,
+            PowerUpType::Magnet => 0b0100,
+            PowerUpType::DoublePoints => 0b1000,
+            PowerUpType::Heart => 0b1_0000,
+        }
+    }
 }
 
 // Obstacle struct
@@ -71,14 +159,16 @@ struct Obstacle {
     width: f32,
     height: f32,
     obstacle_type: ObstacleType,
-    passed: bool,
+    // One bit per player that has got past it
+    passed: u8,
 }
 
 impl Obstacle {
-    fn new(x: f32, obstacle_type: ObstacleType, rng: &mut u32) -> Self {
+    // `lift` raises a floating rock's bottom edge off the ground
+    fn new(x: f32, obstacle_type: ObstacleType, lift: f32) -> Self {
         let (y, width, height) = match obstacle_type {
             ObstacleType::Crystal => (GROUND_Y, 12.0, 20.0),
-            ObstacleType::FloatingRock => (GROUND_Y - 10.0 - (rand_quick(rng) % 25) as f32, 16.0, 16.0),  // Spawn closer to ground (65-90 range)
+            ObstacleType::FloatingRock => (GROUND_Y - lift, 16.0, 16.0),
             ObstacleType::CandyCane => (GROUND_Y, 16.0, 32.0),
         };
 
@@ -88,9 +178,20 @@ impl Obstacle {
             width,
             height,
             obstacle_type,
-            passed: false,
+            passed: 0,
         }
     }
+    
+    fn passed_by(&self, player: usize) -> bool {
+        self.passed & (1 << player) != 0
+    }
+
+    // Would this obstacle, moved to `x`, overlap the player's 16x16 hitbox centred on (player_x, player_y)?
+    fn hits_at(&self, x: f32, player_x: f32, player_y: f32) -> bool {
+        let (px, py) = (player_x - 8.0, player_y - 8.0);
+        let oy = self.y - self.height;
+        px < x + self.width && px + 16.0 > x && py < oy + self.height && py + 16.0 > oy
+    }
 }
 
 // Star struct
@@ -131,7 +232,8 @@ struct Particle {
 enum ParticleType {
     Snowflake,
     Sparkle,
-    Trail,
+    // Behind an airborne reindeer, styled by its outfit
+    Trail { color: u32, shape: TrailShape },
 }
 
 // Aurora wave struct
@@ -155,19 +257,75 @@ struct FloatingText {
     color: u32,
 }
 
+// Player input for a single simulation step
+#[turbo::serialize]
+#[derive(Copy, PartialEq, Default)]
+struct InputFrame {
+    up_pressed: bool,
+    up_just_pressed: bool,
+}
+
+impl InputFrame {
+    fn from_gamepad(gp: &gamepad::Gamepad) -> Self {
+        Self {
+            up_pressed: gp.up.pressed(),
+            up_just_pressed: gp.up.just_pressed(),
+        }
+    }
+}
+
+// Gameplay events produced by a simulation step (sound and other side effects hook in here)
+#[turbo::serialize]
+#[derive(PartialEq)]
+enum GameEvent {
+    Jumped,
+    // `altitude` is how high the player was, 0.0 on the ground to 1.0 at the top of the screen
+    ObstaclePassed { player: usize, combo: u32, altitude: f32 },
+    ComboMilestone(u32),
+    NearMiss,
+    // Points player n got from flying high
+    AltitudeBonus(usize, u32),
+    // By player n
+    StarCollected(usize),
+    PowerUpCollected(usize, PowerUpType),
+    StarMissed,
+    PowerUpMissed,
+    // By player n's shield
+    ObstacleCrushed(usize),
+    GameOver,
+    LevelCleared,
+    ShieldPassed,
+    Revived,
+    // Player n lost a heart but kept running
+    HeartLost(usize),
+}
+
+impl GameEvent {
+    // Whether player one did this (or it happened to the whole run); only player one's play goes in the save
+    fn is_local(&self) -> bool {
+        let player = match *self {
+            GameEvent::ObstaclePassed { player, .. }
+            | GameEvent::AltitudeBonus(player, _)
+            | GameEvent::StarCollected(player)
+            | GameEvent::PowerUpCollected(player, _)
+            | GameEvent::ObstacleCrushed(player)
+            | GameEvent::HeartLost(player) => player,
+            _ => return true,
+        };
+        player == 0
+    }
+}
+
 // Main game state
 #[turbo::game]
 struct GameState {
     mode: GameMode,
     
-    // Player
-    player_x: f32,
-    player_y: f32,
-    player_velocity_y: f32,
-    player_is_jumping: bool,
-    player_jump_hold_frames: u32,
-    player_is_on_ground: bool,
-    player_rotation: f32,
+    // Reindeer in the current run (player one first)
+    players: Vec<Player>,
+    // Co-op: lives the pair share, and frames until a shield can be handed over again
+    lives: u32,
+    shield_pass_cooldown: u32,
     
     // Game entities
     obstacles: Vec<Obstacle>,
@@ -178,27 +336,13 @@ struct GameState {
     floating_texts: Vec<FloatingText>,
     
     // Gameplay
-    score: u32,
-    high_score: u32,
-    combo: u32,
-    max_combo: u32,
     distance: f32,
     scroll_speed: f32,
     
-    // Power-up effects
-    has_shield: bool,
-    shield_timer: u32,
-    slow_mo_timer: u32,
-    magnet_timer: u32,
-    double_points_timer: u32,
-    
-    // Streak system
-    perfect_landings: u32,
-    near_miss_count: u32,
-    
     // Timing
     frame: u32,
-    spawn_timer: u32,
+    run_frames: u32,
+    spawn_cursor: f32,
     particle_timer: u32,
     snowflake_timer: u32,
     
@@ -208,34 +352,83 @@ struct GameState {
     title_pulse: f32,
     screen_flash: u32,
     
-    // Achievements
-    stars_collected: u32,
-    total_distance: f32,
-    
     // Background scrolling (parallax layers)
     tree_scroll_offset: f32,
     mountain_scroll_offset: f32,
     aurora_scroll_offset: f32,
     
-    // RNG
-    rng_state: u32,
+    // RNG (reseeded for every run)
+    rng: Rng,
+    
+    // Obstacle patterns the spawner stitches together, from levels.json
+    chunks: Vec<Chunk>,
+    levels_hash: u64,
+    // `replay::sim_hash` of the chunks and campaign actually loaded (an editor export replaces them)
+    sim_hash: u64,
+    
+    // Hand-built levels, also from levels.json
+    campaign: Vec<CampaignLevel>,
+    run_kind: RunKind,
+    // Difficulty preset of the current run
+    preset: Preset,
+    // Next chunk of the level's script
+    script_index: usize,
+    // Highlighted row on the level select screen
+    level_cursor: usize,
+    
+    // Recorded runs to race: personal bests and ghosts.json
+    ghosts: Vec<Ghost>,
+    ghosts_hash: u64,
+    ghost_cursor: usize,
+    // Ghost racing the current run
+    ghost: Option<GhostRace>,
+    // The run is on the player's own setup and the shipped chunk library, so it counts towards their records
+    // (racing a teammate's ghost or an online race can bring other upgrades, hearts and difficulty,
+    // and a run on an editor export couldn't be raced or verified by anyone else)
+    counts_for_save: bool,
+    // Online race, while in one
+    race: Option<Race>,
+    
+    // Achievements: the run's progress towards them, unlock popups waiting to show, gallery row
+    run_progress: RunProgress,
+    toasts: Vec<Toast>,
+    achievement_cursor: usize,
+    // The current run's progress on each mission in the save
+    mission_runs: Vec<MissionRun>,
+    // Highlighted row in the shop
+    shop_cursor: usize,
+    // Highlighted slot on the wardrobe screen
+    wardrobe_cursor: usize,
+    // Highlighted row on the rules screen: the difficulty, then one per mode
+    rules_cursor: usize,
+    
+    // Balance values for the current run, and the tuning.json the next run will start with
+    tuning: Tuning,
+    tuning_config: Tuning,
+    tuning_source_hash: u64,
+    
+    // Chunk authoring tool
+    editor: Editor,
+    
+    // Input recording of the current/last run
+    replay: Replay,
+    
+    // Persistent high score and lifetime stats
+    save: SaveData,
+    save_loaded: bool,
 }
 
 impl GameState {
     fn new() -> Self {
-        let mut rng = 12345u32;
+        let mut rng = Rng::new(12345);
         
-        Self {
+        let mut state = Self {
             mode: GameMode::Title,
             
-            // Player
-            player_x: PLAYER_X,
-            player_y: GROUND_Y,
-            player_velocity_y: 0.0,
-            player_is_jumping: false,
-            player_jump_hold_frames: 0,
-            player_is_on_ground: true,
-            player_rotation: 0.0,
+            // Players
+            players: Player::lineup(RunKind::Endless, 1),
+            lives: 0,
+            shield_pass_cooldown: 0,
             
             // Entities
             obstacles: Vec::new(),
@@ -245,7 +438,7 @@ impl GameState {
             floating_texts: Vec::new(),
             aurora_waves: vec![
                 AuroraWave {
-                    offset: (rand_quick(&mut rng) % 100) as f32,
+                    offset: rng.below(100) as f32,
                     frequency: 0.05,
                     amplitude: 15.0,
                     speed: 0.35,
@@ -253,7 +446,7 @@ impl GameState {
                     y_base: 30.0,
                 },
                 AuroraWave {
-                    offset: (rand_quick(&mut rng) % 100) as f32,
+                    offset: rng.below(100) as f32,
                     frequency: 0.03,
                     amplitude: 20.0,
                     speed: 0.1,  
@@ -261,7 +454,7 @@ impl GameState {
                     y_base: 50.0,
                 },
                 AuroraWave {
-                    offset: (rand_quick(&mut rng) % 100) as f32,
+                    offset: rng.below(100) as f32,
                     frequency: 0.04,
                     amplitude: 10.0,
                     speed: 0.2,  
@@ -271,27 +464,13 @@ impl GameState {
             ],
             
             // Gameplay
-            score: 0,
-            high_score: 0,
-            combo: 0,
-            max_combo: 0,
             distance: 0.0,
-            scroll_speed: SCROLL_SPEED_BASE,
-            
-            // Power-ups
-            has_shield: false,
-            shield_timer: 0,
-            slow_mo_timer: 0,
-            magnet_timer: 0,
-            double_points_timer: 0,
-            
-            // Streaks
-            perfect_landings: 0,
-            near_miss_count: 0,
+            scroll_speed: 0.0,
             
             // Timing
             frame: 0,
-            spawn_timer: 0,
+            run_frames: 0,
+            spawn_cursor: 0.0,
             particle_timer: 0,
             snowflake_timer: 0,
             
@@ -301,30 +480,132 @@ impl GameState {
             title_pulse: 0.0,
             screen_flash: 0,
             
-            // Achievements
-            stars_collected: 0,
-            total_distance: 0.0,
-            
             // Background scrolling (parallax layers)
             tree_scroll_offset: 0.0,
             mountain_scroll_offset: 0.0,
             aurora_scroll_offset: 0.0,
             
             // RNG
-            rng_state: rng,
-        }
+            rng,
+            chunks: Vec::new(),
+            levels_hash: 0,
+            sim_hash: replay::sim_hash(),
+            
+            // Campaign
+            campaign: Vec::new(),
+            run_kind: RunKind::Endless,
+            preset: Preset::Normal,
+            script_index: 0,
+            level_cursor: 0,
+            
+            // Ghosts (loaded with the save)
+            ghosts: Vec::new(),
+            ghosts_hash: 0,
+            ghost_cursor: 0,
+            ghost: None,
+            counts_for_save: true,
+            race: None,
+            
+            // Achievements
+            run_progress: RunProgress::default(),
+            toasts: Vec::new(),
+            achievement_cursor: 0,
+            mission_runs: Vec::new(),
+            shop_cursor: 0,
+            wardrobe_cursor: 0,
+            rules_cursor: 0,
+            
+            // Tuning
+            tuning: Tuning::default(),
+            tuning_config: Tuning::default(),
+            tuning_source_hash: 0,
+            
+            // Editor
+            editor: Editor::new(),
+            
+            // Replay
+            replay: Replay::new(
+                RunSetup {
+                    seed: 0,
+                    tuning: Tuning::default(),
+                    kind: RunKind::Endless,
+                    modifiers: Modifiers::default(),
+                    hearts: 1,
+                    preset: Preset::Normal,
+                },
+                replay::sim_hash(),
+            ),
+            
+            // Save data (loaded on the first update)
+            save: SaveData::default(),
+            save_loaded: false,
+        };
+        state.reload_levels();
+        state.reload_tuning();
+        state.tuning = state.tuning_config;
+        state
     }
     
     fn update(&mut self) {
         self.frame += 1;
-        self.rng_state = self.rng_state.wrapping_mul(1664525).wrapping_add(1013904223);
+        
+        if !self.save_loaded {
+            self.load_save();
+        }
+        // A hot reload keeps the old state but may bring a new levels.json or tuning.json
+        if self.levels_hash != levels::source_hash() {
+            self.reload_levels();
+            // Recorded runs depend on the chunk library too
+            self.refresh_ghosts();
+        }
+        if self.tuning_source_hash != tuning::source_hash() {
+            self.reload_tuning();
+        }
+        if self.ghosts_hash != ghost::source_hash() {
+            self.refresh_ghosts();
+        }
         
         let gp = gamepad::get(0);
         
         match self.mode {
             GameMode::Title => self.update_title(&gp),
-            GameMode::Playing => self.update_playing(&gp),
+            GameMode::Playing => {
+                // Player n is on gamepad n
+                let inputs: Vec<InputFrame> = (0..self.players.len())
+                    .map(|index| InputFrame::from_gamepad(&gamepad::get(index)))
+                    .collect();
+                self.replay.push(&inputs);
+                let events = self.update_playing(&inputs);
+                self.record_checkpoint();
+                // Play-tests go back to the editor and don't count towards the save
+                if self.editor.playtesting {
+                    self.check_playtest(&gp);
+                } else {
+                    if self.counts_for_save {
+                        self.track_achievements(&events);
+                        self.track_missions(&events);
+                    }
+                    if self.mode != GameMode::Playing {
+                        self.finish_run();
+                    }
+                }
+            }
             GameMode::GameOver => self.update_game_over(&gp),
+            GameMode::Replay => self.update_replay(&gp),
+            GameMode::Editor => self.update_editor(&gp),
+            GameMode::LevelSelect => self.update_level_select(&gp),
+            GameMode::LevelClear => self.update_level_clear(&gp),
+            GameMode::GhostSelect => self.update_ghost_select(&gp),
+            GameMode::Lobby => self.update_lobby(&gp),
+            GameMode::Achievements => self.update_achievements(&gp),
+            GameMode::Shop => self.update_shop(&gp),
+            GameMode::Wardrobe => self.update_wardrobe(&gp),
+            GameMode::Rules => self.update_rules(&gp),
+        }
+        self.update_toasts();
+        self.update_ghost();
+        if self.race.is_some() {
+            self.update_race();
         }
         
         self.render();
@@ -341,7 +622,7 @@ impl GameState {
         // Spawn snowflake particles
         self.snowflake_timer += 1;
         if self.snowflake_timer >= 5 {
-            let x = (random::u32() % 256) as f32;
+            let x = self.rng.below(256) as f32;
             let y = -10.0;
             self.spawn_snowflake(x, y);
             self.snowflake_timer = 0;
@@ -355,114 +636,101 @@ impl GameState {
         }
         self.particles.retain(|p| p.life < p.max_life && p.y < 150.0);
         
+        self.update_preset_picker();
+
         // Start game
         if gp.start.just_pressed() || gp.up.just_pressed() {
-            self.reset_game();
+            self.reset_game(RunKind::Endless);
+        } else if gp.down.just_pressed() {
+            self.open_level_select();
+        } else if gp.right.just_pressed() {
+            self.start_daily_run();
+        } else if gp.left.just_pressed() {
+            self.open_ghost_select();
+        } else if gp.x.just_pressed() || gamepad::get(1).start.just_pressed() {
+            // Player two pressing start joins for versus
+            self.reset_game(RunKind::Versus);
+        } else if gp.y.just_pressed() {
+            self.reset_game(RunKind::Coop);
+        } else if gp.b.just_pressed() {
+            self.open_lobby();
+        } else if gp.a.just_pressed() {
+            self.open_achievements();
+        } else if gp.select.just_pressed() {
+            self.open_editor();
         }
     }
     
-    fn update_playing(&mut self, gp: &gamepad::Gamepad) {
-        // Dynamic scroll speed (slower progression)
-        self.scroll_speed = SCROLL_SPEED_BASE + (self.score as f32 / 1000.0).min(2.5);
-        
-        // Apply slow-mo effect
-        let actual_speed = if self.slow_mo_timer > 0 {
-            self.slow_mo_timer -= 1;
+    fn update_playing(&mut self, inputs: &[InputFrame]) -> Vec<GameEvent> {
+        let events = self.step(inputs);
+
+        // Sound effects for this frame's events
+        for event in &events {
+            match event {
+                GameEvent::Jumped => audio::play("jump"),
+                GameEvent::ComboMilestone(_) => audio::play("combo-milestone"),
+                GameEvent::StarCollected(_) => audio::play("collect-star"),
+                GameEvent::PowerUpCollected(..) | GameEvent::ShieldPassed => audio::play("collect-powerup"),
+                GameEvent::Revived => audio::play("combo-milestone"),
+                GameEvent::ObstacleCrushed(_) | GameEvent::HeartLost(_) => audio::play("shield-break"),
+                GameEvent::GameOver => {
+                    audio::stop("music-background");
+                    audio::play("game-over");
+                }
+                GameEvent::LevelCleared => {
+                    audio::stop("music-background");
+                    audio::play("combo-milestone");
+                }
+                _ => {}
+            }
+        }
+
+        let running = matches!(self.mode, GameMode::Playing | GameMode::Replay);
+        if running && !audio::is_playing("music-background") {
+            audio::play("music-background");
+        }
+        events
+    }
+
+    // Advance the current run by one frame without touching the Turbo runtime
+    // `inputs[n]` drives player n; a missing input counts as nothing pressed
+    fn step(&mut self, inputs: &[InputFrame]) -> Vec<GameEvent> {
+        let mut events = Vec::new();
+        let tuning = self.tuning;
+        self.run_frames += 1;
+
+        // Dynamic scroll speed (slower progression), set by whoever is ahead
+        let lead_score = self.players.iter().map(|player| player.score).max().unwrap_or(0);
+        self.scroll_speed = tuning.scroll_speed_base + self.preset.rules().ramp(lead_score);
+        
+        // Apply slow-mo effect (whoever grabbed it slows the world for everyone)
+        let slowed = self.slowed();
+        for player in &mut self.players {
+            if player.slow_mo_timer > 0 {
+                player.slow_mo_timer -= 1;
+            }
+        }
+        let actual_speed = if slowed {
             self.scroll_speed * 0.5
         } else {
             self.scroll_speed
         };
         
         self.distance += actual_speed;
-        self.total_distance += actual_speed;
         
         // Update parallax layers (each layer moves at different speed for depth)
         self.mountain_scroll_offset += actual_speed * 0.15;  // Slowest - distant mountains
         self.aurora_scroll_offset += actual_speed * 0.25;     // Slow - aurora waves
         self.tree_scroll_offset += actual_speed * 0.5;        // Medium - trees
         
-        // Score based on survival
-        if self.frame % 10 == 0 {
-            let points = 1 + self.combo;
-            let multiplier = if self.double_points_timer > 0 { 2 } else { 1 };
-            self.score += points * multiplier;
-        }
-        
-        // Update power-up timers
-        if self.shield_timer > 0 {
-            self.shield_timer -= 1;
-            if self.shield_timer == 0 {
-                self.has_shield = false;
-            }
-        }
-        if self.double_points_timer > 0 {
-            self.double_points_timer -= 1;
-        }
-        if self.magnet_timer > 0 {
-            self.magnet_timer -= 1;
-        }
-        
-        // Player input
-        if gp.up.just_pressed() && self.player_is_on_ground {
-            self.player_is_jumping = true;
-            self.player_velocity_y = JUMP_POWER;
-            self.player_jump_hold_frames = 0;
-            self.player_is_on_ground = false;
-            
-            // Play jump sound
-            audio::play("jump");
-            
-            // Perfect landing bonus
-            if self.perfect_landings > 0 {
-                self.spawn_floating_text(self.player_x, self.player_y - 20.0, "PERFECT!", COLOR_PERFECT);
-            }
-        }
-        
-        // Variable jump height
-        if gp.up.pressed() && self.player_is_jumping && self.player_jump_hold_frames < MAX_JUMP_HOLD {
-            self.player_velocity_y += (JUMP_POWER - MIN_JUMP_POWER) / MAX_JUMP_HOLD as f32;
-            self.player_jump_hold_frames += 1;
-        } else {
-            self.player_is_jumping = false;
+        // Read before anyone moves, so the tap that starts a jump isn't taken for a hand-off
+        let pass_taps = self.shield_pass_taps(inputs);
+        for index in 0..self.players.len() {
+            let input = inputs.get(index).copied().unwrap_or_default();
+            self.step_player(index, input, &mut events);
         }
-        
-        // Apply gravity
-        self.player_velocity_y += GRAVITY;
-        if self.player_velocity_y > 15.0 {
-            self.player_velocity_y = 15.0;
-        }
-        
-        // Update position
-        self.player_y += self.player_velocity_y;
-        
-        // Ground collision
-        if self.player_y >= GROUND_Y {
-            self.player_y = GROUND_Y;
-            self.player_velocity_y = 0.0;
-            
-            // Check for perfect landing
-            if !self.player_is_on_ground && self.player_velocity_y.abs() < 2.0 {
-                self.perfect_landings += 1;
-            } else {
-                self.perfect_landings = 0;
-            }
-            
-            self.player_is_on_ground = true;
-            self.player_rotation = 0.0;
-        } else {
-            self.player_rotation = (self.player_velocity_y * 2.0).clamp(-30.0, 30.0);
-        }
-        
-        // Altitude bonus
-        let altitude = (GROUND_Y - self.player_y) / GROUND_Y;
-        if altitude > 0.3 && self.frame % 5 == 0 {
-            let bonus = (altitude * 5.0) as u32;
-            let multiplier = if self.double_points_timer > 0 { 2 } else { 1 };
-            self.score += bonus * multiplier;
-        }
-        
-        if !audio::is_playing("music-background") {
-            audio::play("music-background");
+        if self.run_kind == RunKind::Coop {
+            self.update_coop(&pass_taps, &mut events);
         }
         
         // Update aurora waves
@@ -470,98 +738,80 @@ impl GameState {
             wave.offset += wave.speed;
         }
         
-        // Spawn obstacles
-        self.spawn_timer += 1;
-        if self.spawn_timer >= OBSTACLE_SPAWN_INTERVAL {
-            let obstacle_type = match random::u32() % 10 {
-                0..=4 => ObstacleType::Crystal,
-                5..=7 => ObstacleType::FloatingRock,
-                _ => ObstacleType::CandyCane,
-            };
-            self.obstacles.push(Obstacle::new(256.0, obstacle_type, &mut self.rng_state));
-            self.spawn_timer = 0;
-            
-            // Spawn star
-            if random::u32() % 3 == 0 {
-                self.stars.push(Star {
-                    x: 256.0 + 40.0,
-                    y: 40.0 + (random::u32() % 40) as f32,
-                    collected: false,
-                    missed: false,
-                });
-            }
-            
-            // Spawn power-ups (more frequent - gifts/presents)
-            if random::u32() % 8 == 0 {
-                let powerup_type = match random::u32() % 6 {
-                    0 | 1 => PowerUpType::Shield,  // 33% chance for shield (2/6)
-                    2 => PowerUpType::SlowMo,      // 17% chance
-                    3 | 4 => PowerUpType::Magnet,  // 33% chance for magnet (2/6)
-                    _ => PowerUpType::DoublePoints, // 17% chance
-                };
-                self.powerups.push(PowerUp {
-                    x: 256.0 + 60.0,
-                    y: 50.0 + (random::u32() % 30) as f32,
-                    powerup_type,
-                    collected: false,
-                    missed: false,
-                });
-            }
-        }
+        // Spawn obstacles, stars and power-ups a chunk at a time
+        self.spawn_cursor -= actual_speed;
+        self.spawn_chunks();
         
         // Update obstacles
-        let player_x = self.player_x;
-        let player_y = self.player_y;
-        let mut particles_to_spawn = 0;
-        let mut near_miss_detected = false;
+        let mut particles_to_spawn = vec![0; self.players.len()];
+        let mut near_miss_detected = vec![false; self.players.len()];
         let mut combo_milestones = Vec::new();
         
         for obstacle in &mut self.obstacles {
             obstacle.x -= actual_speed;
             
-            if !obstacle.passed && obstacle.x + obstacle.width < player_x {
-                obstacle.passed = true;
-                self.combo += 1;
-                if self.combo > self.max_combo {
-                    self.max_combo = self.combo;
+            for (index, player) in self.players.iter_mut().enumerate() {
+                if !player.alive {
+                    continue;
                 }
                 
-                let multiplier = if self.double_points_timer > 0 { 2 } else { 1 };
-                self.score += 10 * self.combo * multiplier;
-                particles_to_spawn += 5;
+                if !obstacle.passed_by(index) && obstacle.x + obstacle.width < player.x {
+                    obstacle.passed |= 1 << index;
+                    player.combo += 1;
+                    if player.combo > player.max_combo {
+                        player.max_combo = player.combo;
+                    }
+                    
+                    player.score += 10 * player.combo * player.multiplier();
+                    particles_to_spawn[index] += 5;
+                    events.push(GameEvent::ObstaclePassed {
+                        player: index,
+                        combo: player.combo,
+                        altitude: (GROUND_Y - player.y) / GROUND_Y,
+                    });
+                    
+                    // Combo milestone feedback
+                    if player.combo.is_multiple_of(10) {
+                        combo_milestones.push((index, player.combo));
+                        self.screen_flash = 10;
+                    }
+                }
                 
-                // Combo milestone feedback
-                if self.combo % 10 == 0 {
-                    combo_milestones.push(self.combo);
-                    self.screen_flash = 10;
+                // Near miss detection
+                let distance_to_obstacle = (obstacle.x - player.x).abs();
+                if distance_to_obstacle < 20.0 && distance_to_obstacle > 5.0 && !obstacle.passed_by(index) {
+                    near_miss_detected[index] = true;
                 }
             }
-            
-            // Near miss detection
-            let distance_to_obstacle = (obstacle.x - player_x).abs();
-            if distance_to_obstacle < 20.0 && distance_to_obstacle > 5.0 && !obstacle.passed {
-                near_miss_detected = true;
-            }
         }
         
         // Spawn floating text for combo milestones
-        for combo_value in combo_milestones {
-            audio::play("combo-milestone");
-            self.spawn_floating_text(player_x, player_y - 30.0, &format!("{}x COMBO!", combo_value), COLOR_STAR);
+        for (index, combo_value) in combo_milestones {
+            let (x, y) = (self.players[index].x, self.players[index].y);
+            events.push(GameEvent::ComboMilestone(combo_value));
+            self.spawn_floating_text(x, y - 30.0, &format!("{}x COMBO!", combo_value), COLOR_STAR);
         }
         
         // Near miss bonus
-        if near_miss_detected {
-            self.near_miss_count += 1;
-            if self.near_miss_count % 3 == 0 {
-                self.spawn_floating_text(player_x, player_y, "CLOSE!", COLOR_AURORA_GREEN);
-                self.score += 5;
+        for (index, detected) in near_miss_detected.into_iter().enumerate() {
+            let player = &mut self.players[index];
+            if detected {
+                player.near_miss_count += 1;
+                if player.near_miss_count.is_multiple_of(3) {
+                    player.score += 5;
+                    let (x, y) = (player.x, player.y);
+                    self.spawn_floating_text(x, y, "CLOSE!", COLOR_AURORA_GREEN);
+                    events.push(GameEvent::NearMiss);
+                }
             }
         }
         
         // Spawn celebration particles
-        for _ in 0..particles_to_spawn {
-            self.spawn_sparkle(player_x, player_y);
+        for (index, count) in particles_to_spawn.into_iter().enumerate() {
+            let (x, y) = (self.players[index].x, self.players[index].y);
+            for _ in 0..count {
+                self.spawn_sparkle(x, y);
+            }
         }
         
         self.obstacles.retain(|o| o.x + o.width >= 0.0);
@@ -572,33 +822,38 @@ impl GameState {
         for star in &mut self.stars {
             star.x -= actual_speed;
             
-            // Magnet pulls stars toward player (increased range and strength)
-            if self.magnet_timer > 0 && !star.collected {
-                let dx = self.player_x - star.x;
-                let dy = self.player_y - star.y;
-                let dist = (dx * dx + dy * dy).sqrt();
-                if dist < 120.0 {  // Increased from 80 to 120
-                    star.x += dx * 0.15;  // Increased pull strength from 0.1 to 0.15
-                    star.y += dy * 0.15;
+            // Magnet pulls stars toward whoever has one (increased range and strength)
+            for player in &self.players {
+                if player.alive && player.magnet_timer > 0 && !star.collected {
+                    let dx = player.x - star.x;
+                    let dy = player.y - star.y;
+                    let dist = (dx * dx + dy * dy).sqrt();
+                    if dist < tuning.magnet_radius {
+                        star.x += dx * 0.15;  // Increased pull strength from 0.1 to 0.15
+                        star.y += dy * 0.15;
+                    }
                 }
             }
             
             // Check if star is going off-screen without being collected
             if star.x < -8.0 && !star.collected && !star.missed {
                 star.missed = true;
-                // Apply penalty: combo and score reduction
-                if self.combo > 0 {
-                    self.combo = self.combo.saturating_sub(3);
+                // Apply penalty to everyone still running: combo and score reduction
+                for player in self.players.iter_mut().filter(|player| player.alive) {
+                    if player.combo > 0 {
+                        player.combo = player.combo.saturating_sub(3);
+                    }
+                    player.score = player.score.saturating_sub(tuning.miss_penalty);
                 }
-                self.score = self.score.saturating_sub(25);
                 missed_star_positions.push((star.x, star.y));
+                events.push(GameEvent::StarMissed);
             }
         }
         
         // Spawn penalty text for missed stars
         for (x, y) in missed_star_positions {
             self.spawn_floating_text(x.max(10.0), y, "-3 COMBO", COLOR_AURORA_GOLD);
-            self.spawn_floating_text(x.max(10.0), y + 10.0, "-25 PTS", COLOR_AURORA_RED);
+            self.spawn_floating_text(x.max(10.0), y + 10.0, &format!("-{} PTS", tuning.miss_penalty), COLOR_AURORA_RED);
         }
         
         self.stars.retain(|s| s.x >= -8.0);
@@ -612,19 +867,22 @@ impl GameState {
             // Check if powerup is going off-screen without being collected
             if powerup.x < -16.0 && !powerup.collected && !powerup.missed {
                 powerup.missed = true;
-                // Apply penalty: combo and score reduction
-                if self.combo > 0 {
-                    self.combo = self.combo.saturating_sub(3);
+                // Apply penalty to everyone still running: combo and score reduction
+                for player in self.players.iter_mut().filter(|player| player.alive) {
+                    if player.combo > 0 {
+                        player.combo = player.combo.saturating_sub(3);
+                    }
+                    player.score = player.score.saturating_sub(tuning.miss_penalty);
                 }
-                self.score = self.score.saturating_sub(25);
                 missed_powerup_positions.push((powerup.x, powerup.y));
+                events.push(GameEvent::PowerUpMissed);
             }
         }
         
         // Spawn penalty text for missed power-ups
         for (x, y) in missed_powerup_positions {
             self.spawn_floating_text(x.max(10.0), y, "-3 COMBO", COLOR_AURORA_GOLD);
-            self.spawn_floating_text(x.max(10.0), y + 10.0, "-25 PTS", COLOR_AURORA_RED);
+            self.spawn_floating_text(x.max(10.0), y + 10.0, &format!("-{} PTS", tuning.miss_penalty), COLOR_AURORA_RED);
         }
         
         self.powerups.retain(|p| p.x >= -16.0);
@@ -632,16 +890,27 @@ impl GameState {
         // Spawn snowflakes
         self.snowflake_timer += 1;
         if self.snowflake_timer >= 10 {
-            let x = (random::u32() % 256) as f32;
+            let x = self.rng.below(256) as f32;
             self.spawn_snowflake(x, -10.0);
             self.snowflake_timer = 0;
         }
         
-        // Spawn trail particles
+        // Spawn trail particles behind everyone in the air
         self.particle_timer += 1;
-        if self.particle_timer >= PARTICLE_SPAWN_RATE && !self.player_is_on_ground {
-            self.spawn_trail(self.player_x, self.player_y);
-            self.particle_timer = 0;
+        if self.particle_timer >= PARTICLE_SPAWN_RATE {
+            let airborne: Vec<(f32, f32, TrailStyle)> = self
+                .players
+                .iter()
+                .enumerate()
+                .filter(|(_, player)| player.alive && !player.is_on_ground)
+                .map(|(index, player)| (player.x, player.y, self.outfit(index).trail))
+                .collect();
+            if !airborne.is_empty() {
+                for (x, y, style) in airborne {
+                    self.spawn_trail(x, y, style);
+                }
+                self.particle_timer = 0;
+            }
         }
         
         // Update particles
@@ -660,7 +929,14 @@ impl GameState {
         self.floating_texts.retain(|t| t.life < 60);
         
         // Check collisions
-        self.check_collisions();
+        self.check_collisions(&mut events);
+        
+        // Campaign levels end at the finish line
+        let running = matches!(self.mode, GameMode::Playing | GameMode::Replay);
+        if running && self.finish_distance().is_some_and(|finish| self.distance >= finish) {
+            self.mode = GameMode::LevelClear;
+            events.push(GameEvent::LevelCleared);
+        }
         
         // Update screen effects
         if self.shake_frames > 0 {
@@ -669,9 +945,130 @@ impl GameState {
         if self.screen_flash > 0 {
             self.screen_flash -= 1;
         }
+        
+        events
+    }
+    
+    // Survival points, power-up timers, jumping and gravity for one player
+    fn step_player(&mut self, index: usize, input: InputFrame, events: &mut Vec<GameEvent>) {
+        let tuning = self.tuning;
+        let run_frames = self.run_frames;
+        let player = &mut self.players[index];
+        if !player.alive {
+            return;
+        }
+        
+        // Score based on survival
+        if run_frames.is_multiple_of(10) {
+            let points = 1 + player.combo;
+            player.score += points * player.multiplier();
+        }
+        
+        // Update power-up timers
+        if player.shield_timer > 0 {
+            player.shield_timer -= 1;
+            if player.shield_timer == 0 {
+                player.has_shield = false;
+            }
+        }
+        if player.double_points_timer > 0 {
+            player.double_points_timer -= 1;
+        }
+        if player.magnet_timer > 0 {
+            player.magnet_timer -= 1;
+        }
+        player.recover_from_hit();
+        
+        // Player input
+        let mut perfect_at = None;
+        if input.up_just_pressed && player.is_on_ground {
+            player.is_jumping = true;
+            player.velocity_y = tuning.jump_power;
+            player.jump_hold_frames = 0;
+            player.is_on_ground = false;
+            events.push(GameEvent::Jumped);
+            
+            // Perfect landing bonus
+            if player.perfect_landings > 0 {
+                perfect_at = Some((player.x, player.y - 20.0));
+            }
+        }
+        
+        // Variable jump height
+        if input.up_pressed && player.is_jumping && player.jump_hold_frames < tuning.max_jump_hold {
+            player.velocity_y += (tuning.jump_power - tuning.min_jump_power) / tuning.max_jump_hold as f32;
+            player.jump_hold_frames += 1;
+        } else {
+            player.is_jumping = false;
+        }
+        
+        // Apply gravity
+        player.velocity_y += tuning.gravity;
+        if player.velocity_y > 15.0 {
+            player.velocity_y = 15.0;
+        }
+        
+        // Update position
+        player.y += player.velocity_y;
+        
+        // Ground collision
+        if player.y >= GROUND_Y {
+            player.y = GROUND_Y;
+            player.velocity_y = 0.0;
+            
+            // Check for perfect landing
+            if !player.is_on_ground && player.velocity_y.abs() < 2.0 {
+                player.perfect_landings += 1;
+            } else {
+                player.perfect_landings = 0;
+            }
+            
+            player.is_on_ground = true;
+            player.rotation = 0.0;
+        } else {
+            player.rotation = (player.velocity_y * 2.0).clamp(-30.0, 30.0);
+        }
+        
+        // Altitude bonus
+        let altitude = (GROUND_Y - player.y) / GROUND_Y;
+        if altitude > 0.3 && run_frames.is_multiple_of(5) {
+            let bonus = (altitude * 5.0) as u32 * player.multiplier();
+            player.score += bonus;
+            events.push(GameEvent::AltitudeBonus(index, bonus));
+        }
+        
+        if let Some((x, y)) = perfect_at {
+            self.spawn_floating_text(x, y, "PERFECT!", COLOR_PERFECT);
+        }
     }
     
     fn update_game_over(&mut self, gp: &gamepad::Gamepad) {
+        self.update_backdrop();
+        
+        // Restart with Space/A button
+        if gp.start.just_pressed() || gp.a.just_pressed() {
+            // Online races restart on the race's run, ghost races on the ghost's
+            if self.race.is_some() {
+                self.start_race();
+                return;
+            }
+            match (&self.ghost, self.run_kind) {
+                (Some(ghost), _) => self.play_run(ghost.setup),
+                (None, RunKind::Daily(_)) => self.start_daily_run(),
+                (None, kind) => self.reset_game(kind),
+            }
+        } else if gp.down.just_pressed() && self.replay.frames() > 0 {
+            self.start_replay();
+        } else if gp.b.just_pressed() && matches!(self.run_kind, RunKind::Campaign(_)) {
+            self.open_level_select();
+        } else if gp.b.just_pressed() && self.race.is_some() {
+            self.leave_race();
+            self.mode = GameMode::Title;
+        }
+    }
+    
+    // Aurora, snowfall and particles behind the menus
+    fn update_backdrop(&mut self) {
         // Update aurora waves
         for wave in &mut self.aurora_waves {
             wave.offset += wave.speed;
@@ -680,7 +1077,7 @@ impl GameState {
         // Spawn snowflakes
         self.snowflake_timer += 1;
         if self.snowflake_timer >= 5 {
-            let x = (random::u32() % 256) as f32;
+            let x = self.rng.below(256) as f32;
             self.spawn_snowflake(x, -10.0);
             self.snowflake_timer = 0;
         }
@@ -692,16 +1089,11 @@ impl GameState {
             particle.life += 1;
         }
         self.particles.retain(|p| p.life < p.max_life && p.y < 150.0);
-        
-        // Restart with Space/A button
-        if gp.start.just_pressed() || gp.a.just_pressed() {
-            self.reset_game();
-        }
     }
     
     fn spawn_snowflake(&mut self, x: f32, y: f32) {
-        let speed_x = ((random::u32() % 20) as f32 - 10.0) * 0.1;
-        let speed_y = 0.5 + (random::u32() % 10) as f32 * 0.1;
+        let speed_x = (self.rng.below(20) as f32 - 10.0) * 0.1;
+        let speed_y = 0.5 + self.rng.below(10) as f32 * 0.1;
         
         self.particles.push(Particle {
             x,
@@ -711,13 +1103,13 @@ impl GameState {
             life: 0,
             max_life: 200,
             particle_type: ParticleType::Snowflake,
-            size: 1.0 + (random::u32() % 3) as f32,
+            size: 1.0 + self.rng.below(3) as f32,
         });
     }
     
     fn spawn_sparkle(&mut self, x: f32, y: f32) {
-        let angle = (random::u32() % 360) as f32 * 3.14159 / 180.0;
-        let speed = 0.5 + (random::u32() % 20) as f32 / 10.0;
+        let angle = self.rng.below(360) as f32 * std::f32::consts::PI / 180.0;
+        let speed = 0.5 + self.rng.below(20) as f32 / 10.0;
         
         self.particles.push(Particle {
             x,
@@ -725,21 +1117,24 @@ impl GameState {
             velocity_x: angle.cos() * speed - self.scroll_speed,
             velocity_y: angle.sin() * speed,
             life: 0,
-            max_life: 30 + (random::u32() % 20),
+            max_life: 30 + self.rng.below(20),
             particle_type: ParticleType::Sparkle,
-            size: 1.0 + (random::u32() % 3) as f32,
+            size: 1.0 + self.rng.below(3) as f32,
         });
     }
     
-    fn spawn_trail(&mut self, x: f32, y: f32) {
+    fn spawn_trail(&mut self, x: f32, y: f32, style: TrailStyle) {
         self.particles.push(Particle {
             x,
             y,
             velocity_x: -self.scroll_speed,
             velocity_y: 0.0,
             life: 0,
-            max_life: 20,
-            particle_type: ParticleType::Trail,
+            max_life: style.lifetime,
+            particle_type: ParticleType::Trail {
+                color: style.color,
+                shape: style.shape,
+            },
             size: 2.0,
         });
     }
@@ -754,9 +1149,21 @@ impl GameState {
         });
     }
     
-    fn check_collisions(&mut self) {
-        let px = self.player_x - 8.0;
-        let py = self.player_y - 8.0;
+    fn check_collisions(&mut self, events: &mut Vec<GameEvent>) {
+        for index in 0..self.players.len() {
+            if self.players[index].alive {
+                self.check_player_collisions(index, events);
+            }
+        }
+        if self.run_over() {
+            self.game_over(events);
+        }
+    }
+    
+    fn check_player_collisions(&mut self, index: usize, events: &mut Vec<GameEvent>) {
+        let player = &self.players[index];
+        let px = player.x - 8.0;
+        let py = player.y - 8.0;
         let pw = 16.0;
         let ph = 16.0;
         
@@ -764,28 +1171,29 @@ impl GameState {
         let mut obstacles_to_destroy = Vec::new();
         
         // Check obstacle collisions
+        let mut hit = false;
         for (i, obstacle) in self.obstacles.iter().enumerate() {
-            let ox = obstacle.x;
-            let oy = obstacle.y - obstacle.height;
-            let ow = obstacle.width;
-            let oh = obstacle.height;
-            
-            if px < ox + ow && px + pw > ox && py < oy + oh && py + ph > oy {
-                if self.has_shield {
+            if obstacle.hits_at(obstacle.x, player.x, player.y) {
+                if player.has_shield {
                     // Shield DESTROYS the obstacle!
-                    log!("🛡️ Shield crushed obstacle!");
-                    obstacles_to_destroy.push((i, ox + ow / 2.0, oy + oh / 2.0));
+                    let oy = obstacle.y - obstacle.height;
+                    obstacles_to_destroy.push((i, obstacle.x + obstacle.width / 2.0, oy + obstacle.height / 2.0));
                 } else {
-                    log!("  GAME OVER!");
-                    self.game_over();
-                    return;
+                    hit = true;
+                    break;
                 }
             }
         }
+        if hit {
+            self.hurt(index, events);
+            if !self.players[index].alive {
+                return;
+            }
+        }
         
         // Destroy obstacles and spawn effects
-        for (index, x, y) in obstacles_to_destroy.iter().rev() {
-            self.obstacles.remove(*index);
+        for (obstacle, x, y) in obstacles_to_destroy.iter().rev() {
+            self.obstacles.remove(*obstacle);
             
             // Spawn explosion particles
             for _ in 0..15 {
@@ -795,12 +1203,12 @@ impl GameState {
             // Show crushed text
             self.spawn_floating_text(*x, *y, "CRUSHED!", COLOR_SHIELD);
             
-            // Play shield break sound for feedback
-            audio::play("shield-break");
+            events.push(GameEvent::ObstacleCrushed(index));
         }
         
         // Check star collection
         let mut stars_collected = Vec::new();
+        let player = &mut self.players[index];
         for star in &mut self.stars {
             if !star.collected {
                 let sx = star.x - 4.0;
@@ -810,9 +1218,8 @@ impl GameState {
                 
                 if px < sx + sw && px + pw > sx && py < sy + sh && py + ph > sy {
                     star.collected = true;
-                    let multiplier = if self.double_points_timer > 0 { 2 } else { 1 };
-                    self.score += 50 * multiplier;
-                    self.stars_collected += 1;
+                    player.score += self.tuning.star_value * player.multiplier();
+                    player.stars_collected += 1;
                     stars_collected.push((star.x, star.y));
                 }
             }
@@ -820,17 +1227,18 @@ impl GameState {
         
         // Spawn particles for collected stars
         for (x, y) in stars_collected {
-            audio::play("collect-star");
+            events.push(GameEvent::StarCollected(index));
             for _ in 0..10 {
                 self.spawn_sparkle(x, y);
             }
-            self.spawn_floating_text(x, y, "+50", COLOR_STAR);
+            self.spawn_floating_text(x, y, &format!("+{}", self.tuning.star_value), COLOR_STAR);
         }
         
         self.stars.retain(|s| !s.collected);
         
         // Check power-up collection
         let mut powerup_effects = Vec::new();
+        let player = &mut self.players[index];
         
         for powerup in &mut self.powerups {
             if !powerup.collected {
@@ -844,32 +1252,36 @@ impl GameState {
                     
                     let (text, color) = match powerup.powerup_type {
                         PowerUpType::Shield => {
-                            self.has_shield = true;
-                            self.shield_timer = 600;  // 10 seconds at 60fps
+                            player.has_shield = true;
+                            player.shield_timer = self.tuning.shield_frames;
                             ("SHIELD!", COLOR_SHIELD)
                         }
                         PowerUpType::SlowMo => {
-                            self.slow_mo_timer = 600;  // 10 seconds at 60fps
+                            player.slow_mo_timer = self.tuning.slow_mo_frames;
                             ("SLOW-MO!", COLOR_AURORA_GREEN)
                         }
                         PowerUpType::Magnet => {
-                            self.magnet_timer = 420;  // Increased from 240 to 420 frames (7 seconds)
+                            player.magnet_timer = self.tuning.magnet_frames;
                             ("MAGNET!", COLOR_AURORA_GREEN)
                         }
                         PowerUpType::DoublePoints => {
-                            self.double_points_timer = 300;
+                            player.double_points_timer = self.tuning.double_points_frames;
                             ("2x POINTS!", COLOR_STAR)
                         }
+                        PowerUpType::Heart => {
+                            player.hearts = (player.hearts + 1).min(player.max_hearts);
+                            ("+1 HEART!", COLOR_AURORA_RED)
+                        }
                     };
                     
                     powerup_effects.push((powerup.x, powerup.y, text.to_string(), color));
+                    events.push(GameEvent::PowerUpCollected(index, powerup.powerup_type.clone()));
                 }
             }
         }
         
         // Spawn effects after iteration
         for (x, y, text, color) in powerup_effects {
-            audio::play("collect-powerup");
             self.spawn_floating_text(x, y, &text, color);
             
             for _ in 0..20 {
@@ -880,90 +1292,178 @@ impl GameState {
         self.powerups.retain(|p| !p.collected);
     }
     
-    fn game_over(&mut self) {
-        self.mode = GameMode::GameOver;
-        
-        audio::stop("music-background");
-        // Play game over sound
-        audio::play("game-over");
-        
+    // A reindeer ran into something without a shield
+    fn knock_out(&mut self, index: usize) {
         self.shake_frames = 15;
         self.shake_intensity = 5.0;
-        self.combo = 0;
         
-        if self.score > self.high_score {
-            self.high_score = self.score;
-        }
+        let player = &mut self.players[index];
+        player.alive = false;
+        player.combo = 0;
         
+        let (x, y) = (player.x, player.y);
         for _ in 0..30 {
-            self.spawn_sparkle(self.player_x, self.player_y);
+            self.spawn_sparkle(x, y);
+        }
+        if self.run_kind == RunKind::Coop {
+            self.spend_life(index);
         }
     }
     
-    fn reset_game(&mut self) {
-        self.mode = GameMode::Playing;
+    // Versus ends as soon as one reindeer is down; otherwise the run lasts while anyone is still going
+    fn run_over(&self) -> bool {
+        match self.run_kind {
+            RunKind::Versus => self.players.iter().any(|player| !player.alive),
+            _ => self.players.iter().all(|player| !player.alive),
+        }
+    }
+    
+    fn game_over(&mut self, events: &mut Vec<GameEvent>) {
+        self.mode = GameMode::GameOver;
+        events.push(GameEvent::GameOver);
+    }
+    
+    // Slow-mo is on while anyone still running has some left
+    pub(crate) fn slowed(&self) -> bool {
+        self.players.iter().any(|player| player.alive && player.slow_mo_timer > 0)
+    }
+    
+    // Player one (the only player outside versus)
+    pub(crate) fn player(&self) -> &Player {
+        &self.players[0]
+    }
+    
+    // Bookkeeping for a run the player just finished (not for replays)
+    fn finish_run(&mut self) {
+        if self.counts_for_save {
+            let result = self.run_result();
+            self.save.record_run(&result, self.distance);
+            match self.run_kind {
+                RunKind::Endless => self.save.record_high_score(self.preset, result.score, &self.replay),
+                RunKind::Campaign(level) if self.mode == GameMode::LevelClear => self.record_level_clear(level),
+                RunKind::Campaign(_) => {}
+                RunKind::Daily(day) => self.save.record_daily(day, result.score, &self.replay),
+                RunKind::Versus | RunKind::Coop => {}
+            }
+            self.check_achievements(true);
+            self.finish_missions();
+            self.write_save();
+            self.refresh_ghosts();
+        }
         
+        // Hand the finished run to the host page so it can be saved or shared
+        events::emit("replay", &replay::encode_text(&self.replay));
+    }
+    
+    fn reset_game(&mut self, kind: RunKind) {
+        // Fresh seed per run - the only entropy a run ever sees (daily runs share the day's seed)
+        let seed = match kind {
+            RunKind::Daily(day) => daily::seed_for(day),
+            _ => random::u32(),
+        };
+        self.play_run(self.own_setup(kind, seed));
+    }
+    
+    // How this player's own runs are set up: their tuning, upgrades, heart rules and difficulty
+    fn own_setup(&self, kind: RunKind, seed: u32) -> RunSetup {
+        // Everyone plays the same daily run, so upgrades stay at home
+        let modifiers = match kind {
+            RunKind::Daily(_) => Modifiers::default(),
+            _ => self.save.modifiers(),
+        };
+        RunSetup {
+            seed,
+            tuning: self.tuning_config,
+            kind,
+            modifiers,
+            hearts: self.save.hearts.for_kind(kind),
+            preset: self.save.preset.for_kind(kind),
+        }
+    }
+    
+    fn play_run(&mut self, setup: RunSetup) {
+        self.counts_for_save = setup == self.own_setup(setup.kind, setup.seed) && self.sim_hash == replay::sim_hash();
         audio::stop("music-background");
         // Start background music (looping)
         if !audio::is_playing("music-background") {
             audio::play("music-background");
         }
         
-        self.player_x = PLAYER_X;
-        self.player_y = GROUND_Y;
-        self.player_velocity_y = 0.0;
-        self.player_is_jumping = false;
-        self.player_jump_hold_frames = 0;
-        self.player_is_on_ground = true;
-        self.player_rotation = 0.0;
+        self.start_run(setup);
+    }
+
+    // Reset run state, reseed the RNG and switch to the run's tuning (no Turbo calls, usable headless)
+    fn start_run(&mut self, setup: RunSetup) {
+        // A ghost only races the run it was recorded on
+        if self.ghost.as_ref().is_some_and(|ghost| ghost.setup != setup) {
+            self.ghost = None;
+        }
+        let tuning = setup.preset.rules().apply(setup.modifiers.apply(setup.tuning));
+        self.mode = GameMode::Playing;
+        self.rng = Rng::new(setup.seed);
+        self.tuning = tuning;
+        self.run_kind = setup.kind;
+        self.preset = setup.preset;
+        self.script_index = 0;
+        self.replay = Replay::new(setup, self.sim_hash);
+
+        self.players = Player::lineup(setup.kind, setup.hearts);
+        if setup.modifiers.starting_shield {
+            for player in &mut self.players {
+                player.has_shield = true;
+                player.shield_timer = tuning.shield_frames;
+            }
+        }
+        self.lives = if setup.kind == RunKind::Coop { coop::COOP_LIVES } else { 0 };
+        self.shield_pass_cooldown = 0;
+        self.run_progress = RunProgress::default();
+        self.reset_missions();
         self.obstacles.clear();
         self.stars.clear();
         self.powerups.clear();
         self.particles.clear();
         self.floating_texts.clear();
-        self.score = 0;
-        self.combo = 0;
         self.distance = 0.0;
-        self.scroll_speed = SCROLL_SPEED_BASE;
-        self.spawn_timer = 0;
+        self.run_frames = 0;
+        self.scroll_speed = tuning.scroll_speed_base;
+        self.spawn_cursor = 256.0 + tuning.obstacle_spawn_interval as f32 * tuning.scroll_speed_base;
         self.particle_timer = 0;
         self.snowflake_timer = 0;
         self.shake_frames = 0;
-        self.has_shield = false;
-        self.shield_timer = 0;
-        self.slow_mo_timer = 0;
-        self.magnet_timer = 0;
-        self.double_points_timer = 0;
-        self.perfect_landings = 0;
-        self.near_miss_count = 0;
         self.screen_flash = 0;
         self.tree_scroll_offset = 0.0;
         self.mountain_scroll_offset = 0.0;
         self.aurora_scroll_offset = 0.0;
-        self.stars_collected = 0;
     }
     
-    fn render(&mut self) {
+    fn render(&self) {
+        self.draw_frame().flush();
+    }
+    
+    // Everything on screen this frame as a plain command list (no Turbo calls)
+    fn draw_frame(&self) -> DrawList {
+        let mut draw = DrawList::default();
+        
         // Clear screen
-        clear(0x00ffffff);
+        draw.clear(0x00ffffff);
         
         // Flash effect
         if self.screen_flash > 0 {
             let flash_alpha = (self.screen_flash as f32 / 10.0 * 50.0) as u32;
-            clear(0xffffff00 | flash_alpha);
+            draw.clear(0xffffff00 | flash_alpha);
         }
         
         // Draw Christmas gradient sky (dark blue to light blue)
         for y in 0..72 {
             let t = y as f32 / 72.0;
             let color = lerp_color(COLOR_SKY_TOP, COLOR_SKY_MID, t);
-            rect!(y = y, w = 256, h = 1, color = color);
+            draw.rect(0, y, 256, 1, color);
         }
         
         for y in 72..144 {
             let t = (y - 72) as f32 / 72.0;
             let color = lerp_color(COLOR_SKY_MID, COLOR_SKY_BOTTOM, t);
-            rect!(y = y, w = 256, h = 1, color = color);
+            draw.rect(0, y, 256, 1, color);
         }
         
         // Draw Christmas aurora waves with parallax scrolling (red, green, gold)
@@ -987,13 +1487,7 @@ impl GameState {
                     let y2 = wave.y_base + (((parallax_x + 2.0) * wave.frequency + wave.offset).sin() * wave.amplitude);
                     let height = ((y2 - y1).abs() + wave.amplitude / 2.0) as u32;
                     
-                    rect!(
-                        x = x as i32,
-                        y = y1 as i32 - height as i32 / 2,
-                        w = 2,
-                        h = height.max(1),
-                        color = xmas_color & 0xffffff33
-                    );
+                    draw.rect(x, y1 as i32 - height as i32 / 2, 2, height.max(1), xmas_color & 0xffffff33);
                 }
             }
         } else {
@@ -1010,13 +1504,7 @@ impl GameState {
                     let y2 = wave.y_base + (((x + 2) as f32 * wave.frequency + wave.offset).sin() * wave.amplitude);
                     let height = ((y2 - y1).abs() + wave.amplitude / 2.0) as u32;
                     
-                    rect!(
-                        x = x as i32,
-                        y = y1 as i32 - height as i32 / 2,
-                        w = 2,
-                        h = height.max(1),
-                        color = xmas_color & 0xffffff33
-                    );
+                    draw.rect(x, y1 as i32 - height as i32 / 2, 2, height.max(1), xmas_color & 0xffffff33);
                 }
             }
         }
@@ -1068,7 +1556,7 @@ impl GameState {
                         base_color
                     };
                     
-                    rect!(x = x as i32, y = y, w = 1, h = 1, color = textured_color);
+                    draw.rect(x, y, 1, 1, textured_color);
                 }
             }
             
@@ -1079,7 +1567,7 @@ impl GameState {
                 let tree_x = ((base_x - self.tree_scroll_offset) % 350.0 + 350.0) % 350.0;
                 
                 // Only draw if tree is on screen (with buffer)
-                if tree_x >= -30.0 && tree_x <= 280.0 {
+                if (-30.0..=280.0).contains(&tree_x) {
                     let tree_y = 130 - (i % 3) as i32 * 2;  // Positioned on snow layer
                     let tree_offset = ((self.frame as f32 * 0.02) + (i as f32 * 2.0)).sin() * 2.0;
                     let tree_x = tree_x as i32;
@@ -1088,18 +1576,12 @@ impl GameState {
                     for drift_x in -12i32..=12i32 {
                         let drift_height = 3 - (drift_x.abs() / 4);
                         if drift_height > 0 {
-                            rect!(
-                                x = tree_x + 6 + drift_x,
-                                y = tree_y + 8,
-                                w = 1,
-                                h = drift_height,
-                                color = COLOR_SNOW_WHITE
-                            );
+                            draw.rect(tree_x + 6 + drift_x, tree_y + 8, 1, drift_height as u32, COLOR_SNOW_WHITE);
                         }
                     }
                     
                     // Tree trunk
-                    rect!(x = tree_x + 4, y = tree_y, w = 4, h = 8, color = COLOR_TREE_TRUNK);
+                    draw.rect(tree_x + 4, tree_y, 4, 8, COLOR_TREE_TRUNK);
                     
                     // Tree layers (3 triangles)
                     // Top layer with snow cap
@@ -1110,13 +1592,7 @@ impl GameState {
                         } else {
                             0x006600ff
                         };
-                        rect!(
-                            x = tree_x + 6 + x_offset,
-                            y = (tree_y - 15 + y_offset) as i32 + tree_offset as i32,
-                            w = 1,
-                            h = 1,
-                            color = tree_color
-                        );
+                        draw.rect(tree_x + 6 + x_offset, (tree_y - 15 + y_offset) + tree_offset as i32, 1, 1, tree_color);
                     }
                     
                     // Middle layer with snow accents
@@ -1127,13 +1603,7 @@ impl GameState {
                         } else {
                             0x008800ff
                         };
-                        rect!(
-                            x = tree_x + 6 + x_offset,
-                            y = (tree_y - 8 + y_offset) as i32 + tree_offset as i32,
-                            w = 1,
-                            h = 1,
-                            color = tree_color
-                        );
+                        draw.rect(tree_x + 6 + x_offset, (tree_y - 8 + y_offset) + tree_offset as i32, 1, 1, tree_color);
                     }
                     
                     // Bottom layer with snow accents
@@ -1144,18 +1614,12 @@ impl GameState {
                         } else {
                             COLOR_TREE_GREEN
                         };
-                        rect!(
-                            x = tree_x + 6 + x_offset,
-                            y = (tree_y - 1 + y_offset) as i32 + tree_offset as i32,
-                            w = 1,
-                            h = 1,
-                            color = tree_color
-                        );
+                        draw.rect(tree_x + 6 + x_offset, (tree_y - 1 + y_offset) + tree_offset as i32, 1, 1, tree_color);
                     }
                     
                     // Star on top (blinking)
-                    if (self.frame / 15 + i as u32) % 2 == 0 {
-                        circ!(x = tree_x + 6, y = (tree_y - 18) as i32 + tree_offset as i32, d = 3, color = COLOR_STAR);
+                    if (self.frame / 15 + i as u32).is_multiple_of(2) {
+                        draw.circ(tree_x + 6, (tree_y - 18) + tree_offset as i32, 3, COLOR_STAR);
                     }
                 }
             }
@@ -1165,74 +1629,114 @@ impl GameState {
         for particle in &self.particles {
             if particle.particle_type == ParticleType::Snowflake {
                 let opacity = 1.0 - (particle.life as f32 / particle.max_life as f32);
-                sprite!("snowflake", x = particle.x as i32, y = particle.y as i32, opacity = opacity);
+                draw.sprite("snowflake", particle.x as i32, particle.y as i32, opacity);
             }
         }
         
         match self.mode {
-            GameMode::Title => self.render_title(),
-            GameMode::Playing => self.render_playing(),
-            GameMode::GameOver => self.render_game_over(),
+            GameMode::Title => self.render_title(&mut draw),
+            GameMode::Playing => {
+                self.render_playing(&mut draw);
+                self.render_finish_line(&mut draw);
+                self.render_racers(&mut draw);
+                if self.editor.playtesting {
+                    self.render_playtest(&mut draw);
+                }
+            }
+            GameMode::GameOver => {
+                self.render_game_over(&mut draw);
+                self.render_standings(&mut draw);
+            }
+            GameMode::Replay => {
+                self.render_playing(&mut draw);
+                self.render_finish_line(&mut draw);
+                if (self.frame / 30).is_multiple_of(2) {
+                    draw.text("REPLAY", 4, 126, "medium", COLOR_AURORA_RED);
+                }
+                draw.text("[SPACE] Skip", 4, 136, "small", 0xaaaaaaff);
+            }
+            GameMode::Editor => self.render_editor(&mut draw),
+            GameMode::LevelSelect => self.render_level_select(&mut draw),
+            GameMode::LevelClear => self.render_level_clear(&mut draw),
+            GameMode::GhostSelect => self.render_ghost_select(&mut draw),
+            GameMode::Lobby => self.render_lobby(&mut draw),
+            GameMode::Achievements => self.render_achievements(&mut draw),
+            GameMode::Shop => self.render_shop(&mut draw),
+            GameMode::Wardrobe => self.render_wardrobe(&mut draw),
+            GameMode::Rules => self.render_rules(&mut draw),
         }
         
         // Draw sparkle and trail particles (foreground)
         for particle in &self.particles {
-            if particle.particle_type != ParticleType::Snowflake {
-                let opacity = 1.0 - (particle.life as f32 / particle.max_life as f32);
-                let color = if particle.particle_type == ParticleType::Sparkle {
-                    apply_opacity(COLOR_STAR, opacity)
-                } else {
-                    apply_opacity(COLOR_PLAYER_GLOW, opacity)
-                };
-                circ!(x = particle.x as i32, y = particle.y as i32, d = particle.size as u32, color = color);
+            let opacity = 1.0 - (particle.life as f32 / particle.max_life as f32);
+            let (x, y) = (particle.x as i32, particle.y as i32);
+            match particle.particle_type {
+                ParticleType::Snowflake => {}
+                ParticleType::Sparkle => draw.circ(x, y, particle.size as u32, apply_opacity(COLOR_STAR, opacity)),
+                ParticleType::Trail { color, shape } => {
+                    cosmetics::render_trail_particle(&mut draw, x, y, particle.size, color, shape, opacity)
+                }
             }
         }
         
         // Draw heavy snowfall overlay
-        if self.mode == GameMode::Playing {
+        if matches!(self.mode, GameMode::Playing | GameMode::Replay) {
             for i in 0..20 {
-                let x = ((self.frame as u32 * 13 + i * 37) % 256) as i32;
-                let y = ((self.frame as u32 * 3 + i * 17) % 144) as i32;
-                circ!(x = x, y = y, d = 2, color = 0xffffffaa);
+                let x = ((self.frame * 13 + i * 37) % 256) as i32;
+                let y = ((self.frame * 3 + i * 17) % 144) as i32;
+                draw.circ(x, y, 2, 0xffffffaa);
             }
         }
         
         // Draw floating texts
         for ftext in &self.floating_texts {
             let opacity = 1.0 - (ftext.life as f32 / 60.0);
-            text!(&ftext.text, x = ftext.x as i32, y = ftext.y as i32, font = "small", color = apply_opacity(ftext.color, opacity));
+            draw.text(&ftext.text, ftext.x as i32, ftext.y as i32, "small", apply_opacity(ftext.color, opacity));
         }
+        
+        self.render_toast(&mut draw);
+        
+        draw
     }
     
-    fn render_title(&self) {
+    fn render_title(&self, draw: &mut DrawList) {
         let pulse = self.title_pulse.sin() * 0.3 + 1.0;
         let title_y = 30 + (self.title_pulse.sin() * 3.0) as i32;
         
         // Centered title text
-        text!("AURORA", x = 92, y = title_y, font = "large", color = apply_opacity(COLOR_AURORA_RED, pulse));
-        text!("RUNNER", x = 92, y = title_y + 16, font = "large", color = apply_opacity(COLOR_AURORA_GREEN, pulse));
+        draw.text("AURORA", 92, title_y, "large", apply_opacity(COLOR_AURORA_RED, pulse));
+        draw.text("RUNNER", 92, title_y + 16, "large", apply_opacity(COLOR_AURORA_GREEN, pulse));
         
-        // Draw cute reindeer centered below title
-        sprite!("reindeer", x = 105, y = title_y + 32);
+        // Draw cute reindeer centered below title, in player one's pick
+        draw.sprite(self.outfit(0).character, 105, title_y + 32, 1.0);
         
-        if (self.frame / 30) % 2 == 0 {
-            text!("PRESS [UP] TO START", x = 60, y = 85, font = "medium", color = COLOR_TEXT);
+        if (self.frame / 30).is_multiple_of(2) {
+            draw.text("PRESS [UP] TO START", 60, 85, "medium", COLOR_TEXT);
         }
+        draw.text("[LEFT] Ghosts  [DOWN] Campaign  [RIGHT] Daily", 18, 96, "small", COLOR_AURORA_GREEN);
         
-        text!("HIGH SCORE: {}", self.high_score; x = 70, y = 105, font = "small", color = COLOR_TEXT);
+        self.render_preset_picker(draw);
         
-        if self.max_combo > 0 {
-            text!("BEST COMBO: {}x", self.max_combo; x = 70, y = 115, font = "small", color = COLOR_AURORA_GREEN);
+        if self.save.best_combo > 0 {
+            draw.text(format!("BEST COMBO: {}x", self.save.best_combo), 70, 115, "small", COLOR_AURORA_GREEN);
         }
         
-        text!("Hold [UP] for higher jump!", x = 45, y = 130, font = "small", color = 0xaaaaaaff);
+        self.render_missions(draw);
+        draw.text("Hold [UP] for higher jump!", 45, 135, "small", 0xaaaaaaff);
+        draw.text("[SELECT] Level Editor", 4, 4, "small", 0x666666ff);
+        draw.text("[X] 2P Versus  [Y] 2P Co-op  [B] Online Race", 4, 12, "small", 0x666666ff);
+        draw.text("[A] Achievements/Shop/Rules", 4, 20, "small", 0x666666ff);
+        
+        // Today's daily best, gone once the day rolls over
+        let daily_best = self.save.daily_best(daily::today());
+        draw.text(format!("DAILY BEST: {daily_best}"), 168, 4, "small", 0x666666ff);
     }
     
-    fn render_playing(&self) {
+    fn render_playing(&self, draw: &mut DrawList) {
         // Draw stars with sprite
         for star in &self.stars {
             if !star.collected {
-                sprite!("star", x = star.x as i32 - 4, y = star.y as i32 - 4);
+                draw.sprite("star", star.x as i32 - 4, star.y as i32 - 4, 1.0);
             }
         }
         
@@ -1244,8 +1748,9 @@ impl GameState {
                     PowerUpType::SlowMo => "present-slowmo",
                     PowerUpType::Magnet => "present-magnet",
                     PowerUpType::DoublePoints => "present-2x",
+                    PowerUpType::Heart => "present-heart",
                 };
-                sprite!(sprite_name, x = powerup.x as i32 - 6, y = powerup.y as i32 - 6);
+                draw.sprite(sprite_name, powerup.x as i32 - 6, powerup.y as i32 - 6, 1.0);
             }
         }
         
@@ -1253,202 +1758,264 @@ impl GameState {
         for obstacle in &self.obstacles {
             match obstacle.obstacle_type {
                 ObstacleType::Crystal => {
-                    sprite!("crystal", x = obstacle.x as i32, y = (obstacle.y - obstacle.height) as i32);
+                    draw.sprite("crystal", obstacle.x as i32, (obstacle.y - obstacle.height) as i32, 1.0);
                 }
                 ObstacleType::FloatingRock => {
-                    sprite!("floating-rock", x = obstacle.x as i32, y = (obstacle.y - obstacle.height) as i32);
+                    draw.sprite("floating-rock", obstacle.x as i32, (obstacle.y - obstacle.height) as i32, 1.0);
                 }
                 ObstacleType::CandyCane => {
-                    sprite!("candy-cane", x = obstacle.x as i32, y = (obstacle.y - obstacle.height) as i32);
+                    draw.sprite("candy-cane", obstacle.x as i32, (obstacle.y - obstacle.height) as i32, 1.0);
                 }
             }
         }
         
-        // Draw player with animated rotating shield rings
-        if self.has_shield {
-            let time = self.frame as f32 * 0.1;
-            let pulse = (time * 0.5).sin() * 2.0;  // Pulsing effect
-            
-            // Calculate fade effect for last 3 seconds (180 frames)
-            let fade_multiplier = if self.shield_timer < 180 {
-                // Fade out in last 3 seconds
-                self.shield_timer as f32 / 180.0
-            } else {
-                1.0
-            };
-            
-            // Draw 3 rotating shield rings
-            let ring_configs = [
-                (12.0 + pulse, time * 2.0, 8),        // Inner ring - clockwise, fast
-                (16.0 + pulse, -time * 1.5, 10),      // Middle ring - counter-clockwise, medium
-                (20.0 + pulse, time * 1.0, 12),       // Outer ring - clockwise, slow
-            ];
+        // Ghost first, so the live players stay on top
+        self.render_ghost(draw);
+        
+        for (index, player) in self.players.iter().enumerate() {
+            if player.has_shield {
+                self.render_shield(draw, player);
+            }
             
-            for (radius, rotation, segments) in ring_configs {
-                for i in 0..segments {
-                    let angle = (i as f32 / segments as f32) * std::f32::consts::PI * 2.0 + rotation;
-                    let x = self.player_x + angle.cos() * radius;
-                    let y = self.player_y + angle.sin() * radius;
-                    
-                    // Draw ring segment with trail effect and fade
-                    let base_opacity = 0.6 + (time + i as f32 * 0.5).sin() * 0.4;
-                    let segment_opacity = base_opacity * fade_multiplier;
-                    circ!(
-                        x = x as i32,
-                        y = y as i32,
-                        d = 3,
-                        color = apply_opacity(COLOR_SHIELD, segment_opacity)
-                    );
-                }
+            // Draw reindeer player in its outfit (faded once knocked out, blinking just after losing a heart)
+            let opacity = if player.alive { 1.0 } else { 0.4 };
+            if !player.blinking(self.frame) {
+                self.render_reindeer(draw, index, player.x as i32, player.y as i32, opacity);
             }
             
-            // Add connecting lines for force field effect
-            let line_rotation = time * 1.5;
-            for i in 0..4 {
-                let angle = (i as f32 / 4.0) * std::f32::consts::PI * 2.0 + line_rotation;
-                let x1 = self.player_x + angle.cos() * 10.0;
-                let y1 = self.player_y + angle.sin() * 10.0;
-                let x2 = self.player_x + angle.cos() * 22.0;
-                let y2 = self.player_y + angle.sin() * 22.0;
-                
-                // Simple line using circles with fade
-                let dist = ((x2 - x1).powi(2) + (y2 - y1).powi(2)).sqrt();
-                let steps = dist as i32;
-                for step in 0..steps {
-                    let t = step as f32 / dist;
-                    let x = x1 + (x2 - x1) * t;
-                    let y = y1 + (y2 - y1) * t;
-                    circ!(x = x as i32, y = y as i32, d = 1, color = apply_opacity(COLOR_SHIELD, 0.3 * fade_multiplier));
-                }
+            // Tag each reindeer when there's more than one
+            if self.players.len() > 1 {
+                draw.text(format!("P{}", index + 1), player.x as i32 - 5, player.y as i32 - 28, "small", PLAYER_COLORS[index]);
             }
         }
         
-        // Draw reindeer player with Santa hat
-        sprite!("santa-hat", x = self.player_x as i32 - 4, y = self.player_y as i32 - 18);
-        sprite!("reindeer", x = self.player_x as i32 - 8, y = self.player_y as i32 - 8);
+        let player = self.player();
         
         // Draw UI (expanded to fit shield timer)
-        rect!(x = 4, y = 4, w = 120, h = 44, color = COLOR_UI_BG);
-        text!("SCORE: {}", self.score; x = 8, y = 8, font = "small", color = COLOR_TEXT);
-        
-        if self.combo > 0 {
-            let combo_color = if self.combo >= 20 {
-                COLOR_PERFECT
-            } else if self.combo >= 10 {
-                COLOR_STAR
-            } else if self.combo >= 5 {
-                COLOR_AURORA_GREEN
-            } else {
-                COLOR_AURORA_GOLD
-            };
-            text!("COMBO x{}", self.combo; x = 8, y = 18, font = "small", color = combo_color);
+        draw.rect(4, 4, 120, 44, COLOR_UI_BG);
+        draw.text(format!("SCORE: {}", player.score), 8, 8, "small", COLOR_TEXT);
+        self.render_hearts(draw, player, 84, 8);
+        if let RunKind::Daily(day) = self.run_kind {
+            draw.text(format!("DAILY {}", daily::date_label(day)), 176, 8, "small", COLOR_STAR);
+        }
+        self.render_ghost_gap(draw);
+        
+        if player.combo > 0 {
+            draw.text(format!("COMBO x{}", player.combo), 8, 18, "small", combo_color(player.combo));
         }
         
         // Power-up indicators
         // Shield timer - prominent in last 3 seconds
-        if self.shield_timer > 0 {
-            let seconds_left = self.shield_timer / 60;
-            let frames_left = self.shield_timer % 60;
+        if player.shield_timer > 0 {
+            let seconds_left = player.shield_timer / 60;
+            let frames_left = player.shield_timer % 60;
             
-            if self.shield_timer < 180 {
+            if player.shield_timer < 180 {
                 // Last 3 seconds - show countdown with ticking animation
-                let is_half_second = (frames_left / 30) % 2 == 0;
-                if is_half_second || self.shield_timer < 60 {
+                let is_half_second = (frames_left / 30).is_multiple_of(2);
+                if is_half_second || player.shield_timer < 60 {
                     // Blink faster in last second
-                    let warning_color = if self.shield_timer < 60 {
+                    let warning_color = if player.shield_timer < 60 {
                         COLOR_AURORA_RED  // Red in last second
                     } else {
                         0xffaa00ff  // Orange in 2-3 seconds
                     };
-                    text!("SHIELD: {}s", seconds_left + 1; x = 8, y = 28, font = "small", color = warning_color);
+                    draw.text(format!("SHIELD: {}s", seconds_left + 1), 8, 28, "small", warning_color);
                 }
             } else {
                 // Normal display - just show timer
-                text!("SHIELD: {}s", seconds_left + 1; x = 8, y = 28, font = "small", color = COLOR_SHIELD);
+                draw.text(format!("SHIELD: {}s", seconds_left + 1), 8, 28, "small", COLOR_SHIELD);
             }
         }
         
         // Slow-mo timer - prominent in last 3 seconds
-        if self.slow_mo_timer > 0 {
-            let seconds_left = self.slow_mo_timer / 60;
-            let frames_left = self.slow_mo_timer % 60;
+        if player.slow_mo_timer > 0 {
+            let seconds_left = player.slow_mo_timer / 60;
+            let frames_left = player.slow_mo_timer % 60;
             
-            if self.slow_mo_timer < 180 {
+            if player.slow_mo_timer < 180 {
                 // Last 3 seconds - show countdown with ticking animation
-                let is_half_second = (frames_left / 30) % 2 == 0;
-                if is_half_second || self.slow_mo_timer < 60 {
+                let is_half_second = (frames_left / 30).is_multiple_of(2);
+                if is_half_second || player.slow_mo_timer < 60 {
                     // Blink faster in last second
-                    let warning_color = if self.slow_mo_timer < 60 {
+                    let warning_color = if player.slow_mo_timer < 60 {
                         COLOR_AURORA_RED  // Red in last second
                     } else {
                         0xffaa00ff  // Orange in 2-3 seconds
                     };
-                    text!("SLOW: {}s", seconds_left + 1; x = 8, y = 38, font = "small", color = warning_color);
+                    draw.text(format!("SLOW: {}s", seconds_left + 1), 8, 38, "small", warning_color);
                 }
             } else {
                 // Normal display - just show timer
-                text!("SLOW: {}s", seconds_left + 1; x = 8, y = 38, font = "small", color = COLOR_AURORA_GREEN);
+                draw.text(format!("SLOW: {}s", seconds_left + 1), 8, 38, "small", COLOR_AURORA_GREEN);
             }
         }
         
-        if self.double_points_timer > 0 {
-            text!("2x {}", self.double_points_timer / 60; x = 50, y = 38, font = "small", color = COLOR_STAR);
+        if player.double_points_timer > 0 {
+            draw.text(format!("2x {}", player.double_points_timer / 60), 50, 38, "small", COLOR_STAR);
         }
-        if self.magnet_timer > 0 {
-            text!("MAG {}", self.magnet_timer / 60; x = 90, y = 38, font = "small", color = COLOR_AURORA_GREEN);
+        if player.magnet_timer > 0 {
+            draw.text(format!("MAG {}", player.magnet_timer / 60), 90, 38, "small", COLOR_AURORA_GREEN);
+        }
+        
+        if self.run_kind == RunKind::Coop {
+            self.render_lives(draw);
+        }
+        if self.players.len() > 1 {
+            self.render_rival_hud(draw);
+            return;
         }
         
         // Altitude meter
-        let altitude = ((GROUND_Y - self.player_y) / GROUND_Y * 100.0) as u32;
+        let altitude = ((GROUND_Y - player.y) / GROUND_Y * 100.0) as u32;
         if altitude > 30 {
-            rect!(x = 236, y = 4, w = 16, h = 60, color = COLOR_UI_BG);
+            draw.rect(236, 4, 16, 60, COLOR_UI_BG);
             let bar_height = (altitude as f32 / 100.0 * 56.0) as u32;
-            rect!(x = 238, y = 62 - bar_height as i32, w = 12, h = bar_height, color = COLOR_AURORA_GREEN);
-            text!("{}%", altitude; x = 232, y = 68, font = "small", color = COLOR_TEXT);
+            draw.rect(238, 62 - bar_height as i32, 12, bar_height, COLOR_AURORA_GREEN);
+            draw.text(format!("{}%", altitude), 232, 68, "small", COLOR_TEXT);
+        }
+        
+        // Live runs only: replays, play-tests and races have their own captions down there
+        if self.mode == GameMode::Playing && !self.editor.playtesting && self.race.is_none() {
+            self.render_mission_hud(draw);
+        }
+    }
+    
+    // Animated rotating shield rings around a player
+    fn render_shield(&self, draw: &mut DrawList, player: &Player) {
+        let time = self.frame as f32 * 0.1;
+        let pulse = (time * 0.5).sin() * 2.0;  // Pulsing effect
+        
+        // Calculate fade effect for last 3 seconds (180 frames)
+        let fade_multiplier = if player.shield_timer < 180 {
+            // Fade out in last 3 seconds
+            player.shield_timer as f32 / 180.0
+        } else {
+            1.0
+        };
+        
+        // Draw 3 rotating shield rings
+        let ring_configs = [
+            (12.0 + pulse, time * 2.0, 8),        // Inner ring - clockwise, fast
+            (16.0 + pulse, -time * 1.5, 10),      // Middle ring - counter-clockwise, medium
+            (20.0 + pulse, time * 1.0, 12),       // Outer ring - clockwise, slow
+        ];
+        
+        for (radius, rotation, segments) in ring_configs {
+            for i in 0..segments {
+                let angle = (i as f32 / segments as f32) * std::f32::consts::PI * 2.0 + rotation;
+                let x = player.x + angle.cos() * radius;
+                let y = player.y + angle.sin() * radius;
+                
+                // Draw ring segment with trail effect and fade
+                let base_opacity = 0.6 + (time + i as f32 * 0.5).sin() * 0.4;
+                let segment_opacity = base_opacity * fade_multiplier;
+                draw.circ(x as i32, y as i32, 3, apply_opacity(COLOR_SHIELD, segment_opacity));
+            }
+        }
+        
+        // Add connecting lines for force field effect
+        let line_rotation = time * 1.5;
+        for i in 0..4 {
+            let angle = (i as f32 / 4.0) * std::f32::consts::PI * 2.0 + line_rotation;
+            let x1 = player.x + angle.cos() * 10.0;
+            let y1 = player.y + angle.sin() * 10.0;
+            let x2 = player.x + angle.cos() * 22.0;
+            let y2 = player.y + angle.sin() * 22.0;
+            
+            // Simple line using circles with fade
+            let dist = ((x2 - x1).powi(2) + (y2 - y1).powi(2)).sqrt();
+            let steps = dist as i32;
+            for step in 0..steps {
+                let t = step as f32 / dist;
+                let x = x1 + (x2 - x1) * t;
+                let y = y1 + (y2 - y1) * t;
+                draw.circ(x as i32, y as i32, 1, apply_opacity(COLOR_SHIELD, 0.3 * fade_multiplier));
+            }
+        }
+    }
+    
+    fn render_game_over(&self, draw: &mut DrawList) {
+        match self.run_kind {
+            RunKind::Versus => return self.render_winner(draw),
+            RunKind::Coop => return self.render_team_over(draw),
+            _ => {}
+        }
+        self.render_frozen_run(draw);
+        
+        let player = self.player();
+        draw.rect(0, 0, 256, 144, 0x00000099);
+        draw.rect(28, 30, 200, 90, COLOR_UI_BG);
+        
+        draw.text("GAME OVER", 75, 38, "large", COLOR_TEXT);
+        draw.text(format!("SCORE: {}", player.score), 85, 58, "medium", COLOR_STAR);
+        let best = match self.run_kind {
+            RunKind::Endless | RunKind::Versus | RunKind::Coop => {
+                format!("{} HIGH: {}", self.preset.name(), self.save.high_score(self.preset))
+            }
+            RunKind::Campaign(level) => {
+                let best = self.campaign.get(level).and_then(|level| self.save.level_best(&level.name));
+                format!("BEST: {}", best.map_or(0, |best| best.score))
+            }
+            RunKind::Daily(day) => format!("DAILY BEST: {}", self.save.daily_best(day)),
+        };
+        draw.text(best, 85, 72, "small", COLOR_TEXT);
+        draw.text(format!("MAX COMBO: {}x", player.max_combo), 77, 82, "small", COLOR_AURORA_GREEN);
+        draw.text(format!("STARS: {}", player.stars_collected), 89, 92, "small", COLOR_STAR);
+        
+        if (self.frame / 30).is_multiple_of(2) {
+            draw.text("[SPACE] Play Again", 70, 105, "small", COLOR_TEXT);
+        }
+        if let RunKind::Campaign(_) = self.run_kind {
+            draw.text("[DOWN] Replay  [B] Levels", 58, 113, "small", 0xaaaaaaff);
+        } else {
+            draw.text("[DOWN] Watch Replay", 68, 113, "small", 0xaaaaaaff);
+        }
+        
+        // Seed of the run that just ended (for reproducing it)
+        draw.text(format!("SEED: {:08X}", self.rng.seed()), 4, 134, "small", 0xaaaaaaff);
+        if let RunKind::Daily(day) = self.run_kind {
+            draw.text(format!("DAILY {}", daily::date_label(day)), 176, 134, "small", 0xaaaaaaff);
         }
     }
     
-    fn render_game_over(&self) {
-        // Draw frozen game with sprites
+    // Obstacles and players where the run stopped, faded behind a results panel
+    fn render_frozen_run(&self, draw: &mut DrawList) {
         for obstacle in &self.obstacles {
             match obstacle.obstacle_type {
                 ObstacleType::Crystal => {
-                    sprite!("crystal", x = obstacle.x as i32, y = (obstacle.y - obstacle.height) as i32, opacity = 0.5);
+                    draw.sprite("crystal", obstacle.x as i32, (obstacle.y - obstacle.height) as i32, 0.5);
                 }
                 ObstacleType::FloatingRock => {
-                    sprite!("floating-rock", x = obstacle.x as i32, y = (obstacle.y - obstacle.height) as i32, opacity = 0.5);
+                    draw.sprite("floating-rock", obstacle.x as i32, (obstacle.y - obstacle.height) as i32, 0.5);
                 }
                 ObstacleType::CandyCane => {
-                    sprite!("candy-cane", x = obstacle.x as i32, y = (obstacle.y - obstacle.height) as i32, opacity = 0.5);
+                    draw.sprite("candy-cane", obstacle.x as i32, (obstacle.y - obstacle.height) as i32, 0.5);
                 }
             }
         }
         
-        // Draw player
-        sprite!("santa-hat", x = self.player_x as i32 - 4, y = self.player_y as i32 - 18);
-        sprite!("reindeer", x = self.player_x as i32 - 8, y = self.player_y as i32 - 8);
-        
-        rect!(w = 256, h = 144, color = 0x00000099);
-        rect!(x = 28, y = 30, w = 200, h = 90, color = COLOR_UI_BG);
-        
-        text!("GAME OVER", x = 75, y = 38, font = "large", color = COLOR_TEXT);
-        text!("SCORE: {}", self.score; x = 85, y = 58, font = "medium", color = COLOR_STAR);
-        text!("HIGH: {}", self.high_score; x = 85, y = 72, font = "small", color = COLOR_TEXT);
-        text!("MAX COMBO: {}x", self.max_combo; x = 77, y = 82, font = "small", color = COLOR_AURORA_GREEN);
-        text!("STARS: {}", self.stars_collected; x = 89, y = 92, font = "small", color = COLOR_STAR);
-        
-        if (self.frame / 30) % 2 == 0 {
-            text!("[SPACE] Play Again", x = 70, y = 105, font = "small", color = COLOR_TEXT);
+        // Draw players
+        for (index, player) in self.players.iter().enumerate() {
+            self.render_reindeer(draw, index, player.x as i32, player.y as i32, 1.0);
         }
     }
 }
 
-// Utility functions
-fn rand_quick(seed: &mut u32) -> u32 {
-    *seed = seed.wrapping_mul(1664525).wrapping_add(1013904223);
-    *seed
+// HUD colour for a combo streak
+fn combo_color(combo: u32) -> u32 {
+    if combo >= 20 {
+        COLOR_PERFECT
+    } else if combo >= 10 {
+        COLOR_STAR
+    } else if combo >= 5 {
+        COLOR_AURORA_GREEN
+    } else {
+        COLOR_AURORA_GOLD
+    }
 }
 
+// Utility functions
 fn lerp_color(c1: u32, c2: u32, t: f32) -> u32 {
     let r1 = ((c1 >> 24) & 0xff) as f32;
     let g1 = ((c1 >> 16) & 0xff) as f32;
@@ -1473,3 +2040,147 @@ fn apply_opacity(color: u32, opacity: f32) -> u32 {
     let new_a = (a * opacity.clamp(0.0, 1.0)) as u32;
     (color & 0xffffff00) | new_a
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::replay::RunResult;
+
+    // A fresh endless run on the shipped tuning and levels
+    pub(crate) fn start(kind: RunKind, seed: u32) -> GameState {
+        let mut state = GameState::new();
+        state.start_run(RunSetup {
+            seed,
+            tuning: state.tuning_config,
+            kind,
+            modifiers: Modifiers::default(),
+            hearts: 1,
+            preset: Preset::Normal,
+        });
+        state
+    }
+
+    // Fixed input script: a jump every 40 frames, held for 8
+    pub(crate) fn scripted_input(frame: u32) -> InputFrame {
+        let phase = frame % 40;
+        InputFrame {
+            up_pressed: phase < 8,
+            up_just_pressed: phase == 0,
+        }
+    }
+
+    // Play the script the way `update` does, recording inputs and checkpoints, until the run ends or `max_frames`
+    pub(crate) fn record(kind: RunKind, seed: u32, max_frames: u32) -> GameState {
+        let mut state = start(kind, seed);
+        while state.mode == GameMode::Playing && state.run_frames < max_frames {
+            let inputs = vec![scripted_input(state.run_frames); state.players.len()];
+            state.replay.push(&inputs);
+            state.step(&inputs);
+            state.record_checkpoint();
+        }
+        state
+    }
+
+    // Step `frames` frames of the script (or until the run ends), collecting every event
+    fn play(state: &mut GameState, frames: u32) -> Vec<Vec<GameEvent>> {
+        let mut events = Vec::new();
+        while state.mode == GameMode::Playing && state.run_frames < frames {
+            events.push(state.step(&[scripted_input(state.run_frames)]));
+        }
+        events
+    }
+
+    fn snapshot(state: &GameState) -> Vec<u8> {
+        borsh::to_vec(state).unwrap()
+    }
+
+    #[test]
+    fn same_seed_and_inputs_play_out_the_same() {
+        let mut first = start(RunKind::Endless, 42);
+        let mut second = start(RunKind::Endless, 42);
+        assert_eq!(play(&mut first, 1200), play(&mut second, 1200));
+        assert_eq!(snapshot(&first), snapshot(&second));
+
+        let mut other_seed = start(RunKind::Endless, 43);
+        play(&mut other_seed, 1200);
+        assert_ne!(snapshot(&first), snapshot(&other_seed));
+    }
+
+    #[test]
+    fn score_and_distance_advance() {
+        let mut state = start(RunKind::Endless, 1);
+        state.step(&[InputFrame::default()]);
+        assert_eq!(state.player().score, 0);
+        assert!(state.distance > 0.0);
+
+        for _ in 0..9 {
+            state.step(&[InputFrame::default()]);
+        }
+        // Survival points every 10 frames
+        assert_eq!(state.player().score, 1);
+    }
+
+    #[test]
+    fn jump_leaves_the_ground() {
+        let mut state = start(RunKind::Endless, 1);
+        let events = state.step(&[scripted_input(0)]);
+        assert!(events.contains(&GameEvent::Jumped));
+        assert!(!state.player().is_on_ground);
+        assert!(state.player().y < GROUND_Y);
+    }
+
+    #[test]
+    fn hitting_an_obstacle_ends_the_run() {
+        let mut state = start(RunKind::Endless, 1);
+        state.obstacles.push(Obstacle::new(PLAYER_X - 4.0, ObstacleType::Crystal, 0.0));
+        let events = state.step(&[InputFrame::default()]);
+        assert!(events.contains(&GameEvent::GameOver));
+        assert_eq!(state.mode, GameMode::GameOver);
+        assert!(!state.player().alive);
+    }
+
+    #[test]
+    fn restart_starts_a_clean_identical_run() {
+        let mut state = start(RunKind::Endless, 7);
+        let first = play(&mut state, 600);
+        let first_result = state.run_result();
+        state.obstacles.push(Obstacle::new(PLAYER_X - 4.0, ObstacleType::Crystal, 0.0));
+        state.step(&[InputFrame::default()]);
+        assert_eq!(state.mode, GameMode::GameOver);
+
+        state.start_run(state.replay.setup());
+        assert_eq!(state.mode, GameMode::Playing);
+        assert_eq!(state.run_frames, 0);
+        assert_eq!(state.distance, 0.0);
+        assert_eq!(state.run_result(), RunResult { score: 0, max_combo: 0, stars_collected: 0 });
+        assert!(state.player().alive);
+        assert!(state.obstacles.is_empty() && state.stars.is_empty() && state.powerups.is_empty());
+
+        assert_eq!(play(&mut state, 600), first);
+        assert_eq!(state.run_result(), first_result);
+    }
+
+    #[test]
+    fn daily_runs_leave_upgrades_at_home() {
+        let mut state = GameState::new();
+        state.save.upgrades.push(shop::OwnedUpgrade {
+            id: shop::UPGRADES[0].id.to_string(),
+            level: 1,
+        });
+        assert!(state.own_setup(RunKind::Endless, 1).modifiers != Modifiers::default());
+        assert!(state.own_setup(RunKind::Daily(1), 1).modifiers == Modifiers::default());
+    }
+
+    #[test]
+    fn daily_runs_always_start_with_one_heart() {
+        let mut state = GameState::new();
+        state.save.hearts = health::HeartRules {
+            endless: 3,
+            campaign: 3,
+            versus: 3,
+            coop: 3,
+        };
+        assert_eq!(state.own_setup(RunKind::Endless, 1).hearts, 3);
+        assert_eq!(state.own_setup(RunKind::Daily(1), 1).hearts, 1);
+    }
+}
@@ -0,0 +1,250 @@
+use crate::achievements::Toast;
+use crate::draw::DrawList;
+use crate::replay::fnv1a;
+use crate::rng::Rng;
+use crate::save::SaveData;
+use crate::{GameEvent, GameState, COLOR_AURORA_GREEN, COLOR_STAR, COLOR_TEXT, COLOR_UI_BG};
+
+// Missions on offer at once
+const ACTIVE_MISSIONS: usize = 3;
+
+// Frames each mission stays up when they take turns on one line
+const MISSION_CYCLE_FRAMES: u32 = 180;
+
+#[turbo::serialize]
+#[derive(Copy, PartialEq)]
+pub(crate) enum MissionGoal {
+    // Stars in a row without letting one go by
+    StarStreak,
+    // Obstacles passed above half the screen's height
+    HighPasses,
+    Combo,
+    // Points from the altitude bonus
+    AltitudePoints,
+    // Every gift in the mask (one bit per PowerUpType) picked up
+    Gifts(u8),
+}
+
+// One objective, to be done within a single run
+#[turbo::serialize]
+#[derive(PartialEq)]
+pub(crate) struct Mission {
+    pub(crate) goal: MissionGoal,
+    pub(crate) target: u32,
+    // Candy paid out on completion
+    pub(crate) reward: u32,
+}
+
+// What a mission can be rolled from: a goal and its (target, reward) tiers
+struct Template {
+    goal: MissionGoal,
+    tiers: &'static [(u32, u32)],
+}
+
+const TEMPLATES: &[Template] = &[
+    Template {
+        goal: MissionGoal::StarStreak,
+        tiers: &[(5, 20), (8, 30), (12, 50)],
+    },
+    Template {
+        goal: MissionGoal::HighPasses,
+        tiers: &[(5, 20), (10, 35), (15, 50)],
+    },
+    Template {
+        goal: MissionGoal::Combo,
+        tiers: &[(10, 15), (20, 30), (30, 50)],
+    },
+    Template {
+        goal: MissionGoal::AltitudePoints,
+        tiers: &[(100, 20), (250, 35), (500, 50)],
+    },
+    // The two gifts are picked when the mission is rolled, so the target is always 2
+    Template {
+        goal: MissionGoal::Gifts(0),
+        tiers: &[(2, 30)],
+    },
+];
+
+// Indexed by PowerUpType bit
+const GIFT_NAMES: [&str; 4] = ["Shield", "Slow-Mo", "Magnet", "2x"];
+
+impl Mission {
+    // Mission number `serial`; the same serial always rolls the same mission
+    fn roll(serial: u32) -> Self {
+        let mut rng = Rng::new(fnv1a(format!("mission {serial}").as_bytes()) as u32);
+        let template = &TEMPLATES[rng.below(TEMPLATES.len() as u32) as usize];
+        let (target, reward) = template.tiers[rng.below(template.tiers.len() as u32) as usize];
+        let goal = match template.goal {
+            MissionGoal::Gifts(_) => {
+                let first = rng.below(4);
+                let second = (first + 1 + rng.below(3)) % 4;
+                MissionGoal::Gifts(1 << first | 1 << second)
+            }
+            goal => goal,
+        };
+        Self { goal, target, reward }
+    }
+
+    pub(crate) fn description(&self) -> String {
+        let target = self.target;
+        match self.goal {
+            MissionGoal::StarStreak => format!("Collect {target} stars without missing one"),
+            MissionGoal::HighPasses => format!("Pass {target} obstacles above half height"),
+            MissionGoal::Combo => format!("Reach a {target}x combo"),
+            MissionGoal::AltitudePoints => format!("Earn {target} altitude points in a run"),
+            MissionGoal::Gifts(mask) => {
+                let names: Vec<&str> = (0..4).filter(|bit| mask & 1 << bit != 0).map(|bit| GIFT_NAMES[bit]).collect();
+                format!("Use {} in one run", names.join(" and "))
+            }
+        }
+    }
+
+    // Progress as shown against the target (gifts count the kinds picked up, not the raw mask)
+    fn shown(&self, progress: u32) -> u32 {
+        match self.goal {
+            MissionGoal::Gifts(mask) => (progress & mask as u32).count_ones(),
+            _ => progress.min(self.target),
+        }
+    }
+
+    fn complete(&self, progress: u32) -> bool {
+        match self.goal {
+            MissionGoal::Gifts(mask) => progress & mask as u32 == mask as u32,
+            _ => progress >= self.target,
+        }
+    }
+}
+
+// How far the current run has got with one mission
+#[turbo::serialize]
+#[derive(Default)]
+pub(crate) struct MissionRun {
+    progress: u32,
+    complete: bool,
+}
+
+impl SaveData {
+    // Replace finished missions (and fill empty slots) with fresh ones, avoiding two of the same kind
+    pub(crate) fn rotate_missions(&mut self, finished: &[bool]) {
+        let mut kept: Vec<Mission> = Vec::new();
+        for (i, mission) in self.missions.drain(..).enumerate() {
+            if !finished.get(i).copied().unwrap_or(false) {
+                kept.push(mission);
+            }
+        }
+        while kept.len() < ACTIVE_MISSIONS {
+            let mission = Mission::roll(self.mission_serial);
+            self.mission_serial += 1;
+            let same_kind = kept
+                .iter()
+                .any(|other| std::mem::discriminant(&other.goal) == std::mem::discriminant(&mission.goal));
+            if !same_kind {
+                kept.push(mission);
+            }
+        }
+        self.missions = kept;
+    }
+}
+
+impl GameState {
+    // Fresh progress for the run that's starting
+    pub(crate) fn reset_missions(&mut self) {
+        self.mission_runs = self.save.missions.iter().map(|_| MissionRun::default()).collect();
+    }
+
+    // Count this frame's events (player one's only) towards each mission, paying out the moment one is done
+    pub(crate) fn track_missions(&mut self, events: &[GameEvent]) {
+        for (mission, run) in self.save.missions.iter().zip(&mut self.mission_runs) {
+            if run.complete {
+                continue;
+            }
+            for event in events.iter().filter(|event| event.is_local()) {
+                match (mission.goal, event) {
+                    (MissionGoal::StarStreak, GameEvent::StarCollected(_)) => run.progress += 1,
+                    (MissionGoal::StarStreak, GameEvent::StarMissed) => run.progress = 0,
+                    (MissionGoal::HighPasses, GameEvent::ObstaclePassed { altitude, .. }) if *altitude >= 0.5 => {
+                        run.progress += 1
+                    }
+                    (MissionGoal::Combo, GameEvent::ObstaclePassed { combo, .. }) => {
+                        run.progress = run.progress.max(*combo)
+                    }
+                    (MissionGoal::AltitudePoints, GameEvent::AltitudeBonus(_, points)) => run.progress += points,
+                    (MissionGoal::Gifts(_), GameEvent::PowerUpCollected(_, kind)) => run.progress |= kind.bit() as u32,
+                    _ => {}
+                }
+            }
+            if mission.complete(run.progress) {
+                run.complete = true;
+                self.save.candy += mission.reward;
+                self.toasts.push(Toast::new("MISSION COMPLETE", format!("+{} CANDY", mission.reward)));
+            }
+        }
+    }
+
+    // Swap out the missions this run completed
+    pub(crate) fn finish_missions(&mut self) {
+        let finished: Vec<bool> = self.mission_runs.iter().map(|run| run.complete).collect();
+        self.save.rotate_missions(&finished);
+        self.reset_missions();
+    }
+
+    // The mission whose turn it is on the one-line ticker
+    fn shown_mission(&self) -> Option<(usize, &Mission)> {
+        let missions = &self.save.missions;
+        if missions.is_empty() {
+            return None;
+        }
+        let index = (self.frame / MISSION_CYCLE_FRAMES) as usize % missions.len();
+        Some((index, &missions[index]))
+    }
+
+    // In-run ticker: one mission at a time with how far this run has got
+    pub(crate) fn render_mission_hud(&self, draw: &mut DrawList) {
+        let Some((index, mission)) = self.shown_mission() else {
+            return;
+        };
+        let Some(run) = self.mission_runs.get(index) else {
+            return;
+        };
+        let (text, color) = if run.complete {
+            (format!("DONE! {}", mission.description()), COLOR_AURORA_GREEN)
+        } else {
+            (format!("{} {}/{}", mission.description(), mission.shown(run.progress), mission.target), COLOR_TEXT)
+        };
+        draw.rect(4, 133, 248, 9, COLOR_UI_BG);
+        draw.text(text, 8, 135, "small", color);
+    }
+
+    // Title screen: candy balance and the missions on offer with their rewards
+    pub(crate) fn render_missions(&self, draw: &mut DrawList) {
+        draw.text(format!("CANDY: {}", self.save.candy), 168, 28, "small", COLOR_STAR);
+        if let Some((index, mission)) = self.shown_mission() {
+            draw.text(format!("MISSION {}: {}", index + 1, mission.description()), 8, 124, "small", COLOR_TEXT);
+            draw.text(format!("+{}", mission.reward), 232, 124, "small", COLOR_STAR);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::start;
+    use crate::RunKind;
+
+    #[test]
+    fn only_player_one_works_on_missions() {
+        let mut state = start(RunKind::Versus, 4);
+        state.save.missions = vec![Mission {
+            goal: MissionGoal::StarStreak,
+            target: 5,
+            reward: 20,
+        }];
+        state.reset_missions();
+        let candy = state.save.candy;
+        state.track_missions(&vec![GameEvent::StarCollected(1); 5]);
+        assert_eq!(state.mission_runs[0].progress, 0);
+        state.track_missions(&vec![GameEvent::StarCollected(0); 5]);
+        assert!(state.mission_runs[0].complete);
+        assert_eq!(state.save.candy, candy + 20);
+    }
+}
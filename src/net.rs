@@ -0,0 +1,756 @@
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::rc::Rc;
+
+use turbo::os::client::channel::{Channel, ChannelConnection};
+use turbo::*;
+
+use crate::cosmetics::{self, Wardrobe};
+use crate::draw::DrawList;
+use crate::ghost::{Ghost, GHOST_OPACITY};
+use crate::replay::{Replay, RunSetup};
+use crate::{GameMode, GameState, RunKind, COLOR_AURORA_GREEN, COLOR_AURORA_RED, COLOR_STAR, COLOR_TEXT, COLOR_UI_BG};
+
+// Runtime env var naming the deployed relay program (a channel handler that broadcasts every packet to
+// everyone subscribed). The relay isn't part of this crate and has to be deployed separately (see README);
+// without one, races run against a local stand-in
+const RELAY_PROGRAM_ENV: &str = "AURORA_RACE_PROGRAM";
+const RELAY_CHANNEL: &str = "race";
+const RELAY_ROOM: &str = "aurora";
+
+// Frames between position updates
+const UPDATE_INTERVAL: u32 = 6;
+// Frames between hellos, which double as a heartbeat and let late joiners find everyone
+const HELLO_INTERVAL: u32 = 60;
+// Frames of silence before a racer counts as disconnected
+const TIMEOUT_FRAMES: u32 = 180;
+// How long "X JOINED" / "X LEFT" stays on screen
+const NOTICE_FRAMES: u32 = 120;
+
+// What racers tell each other
+#[turbo::serialize]
+pub(crate) enum NetMessage {
+    // Who I am, what I'm wearing and which run I'm on (racing = already past the lobby)
+    Hello { name: String, wardrobe: Wardrobe, setup: RunSetup, racing: bool },
+    // Where I am in my run
+    Update(RacerUpdate),
+    // Leaving on purpose (a lost connection just goes quiet and times out)
+    Bye,
+}
+
+#[turbo::serialize]
+pub(crate) struct Packet {
+    pub(crate) from: u32,
+    pub(crate) message: NetMessage,
+}
+
+#[turbo::serialize]
+#[derive(Copy, PartialEq)]
+pub(crate) struct RacerUpdate {
+    pub(crate) distance: f32,
+    pub(crate) y: f32,
+    pub(crate) score: u32,
+    pub(crate) alive: bool,
+}
+
+// How packets get between racers
+pub(crate) trait Transport {
+    fn send(&mut self, packet: &Packet) -> Result<(), String>;
+    // Next packet that has arrived, if any
+    fn recv(&mut self) -> Option<Packet>;
+}
+
+// Turbo channel on the relay program
+struct TurboChannel {
+    program: String,
+    // Subscribed on first use and kept; dropped after a failed send so the next one subscribes again
+    connection: Option<ChannelConnection<Packet, Packet>>,
+}
+
+impl TurboChannel {
+    fn new(program: String) -> Self {
+        Self {
+            program,
+            connection: None,
+        }
+    }
+
+    fn connection(&mut self) -> Option<&ChannelConnection<Packet, Packet>> {
+        if self.connection.is_none() {
+            self.connection = Channel::subscribe(&self.program, RELAY_CHANNEL, RELAY_ROOM);
+        }
+        self.connection.as_ref()
+    }
+}
+
+impl Transport for TurboChannel {
+    fn send(&mut self, packet: &Packet) -> Result<(), String> {
+        let connection = self.connection().ok_or_else(|| "relay not connected".to_string())?;
+        let sent = connection.send(packet).map_err(|err| err.to_string());
+        if sent.is_err() {
+            self.connection = None;
+        }
+        sent
+    }
+
+    fn recv(&mut self) -> Option<Packet> {
+        self.connection()?.recv().ok()
+    }
+}
+
+// In-process stand-in for the relay: every endpoint on a hub hears every other open endpoint
+#[derive(Clone, Default)]
+pub(crate) struct LoopbackHub {
+    inboxes: Rc<RefCell<Vec<Option<VecDeque<Packet>>>>>,
+}
+
+impl LoopbackHub {
+    pub(crate) fn connect(&self) -> Loopback {
+        let mut inboxes = self.inboxes.borrow_mut();
+        inboxes.push(Some(VecDeque::new()));
+        Loopback {
+            hub: self.clone(),
+            index: inboxes.len() - 1,
+        }
+    }
+}
+
+pub(crate) struct Loopback {
+    hub: LoopbackHub,
+    index: usize,
+}
+
+// Dropping an endpoint takes it off the hub without a goodbye, like a lost connection
+impl Drop for Loopback {
+    fn drop(&mut self) {
+        self.hub.inboxes.borrow_mut()[self.index] = None;
+    }
+}
+
+impl Transport for Loopback {
+    fn send(&mut self, packet: &Packet) -> Result<(), String> {
+        let mut inboxes = self.hub.inboxes.borrow_mut();
+        if inboxes[self.index].is_none() {
+            return Err("loopback endpoint closed".to_string());
+        }
+        for (index, inbox) in inboxes.iter_mut().enumerate() {
+            if let (true, Some(inbox)) = (index != self.index, inbox) {
+                inbox.push_back(packet.clone());
+            }
+        }
+        Ok(())
+    }
+
+    fn recv(&mut self) -> Option<Packet> {
+        self.hub.inboxes.borrow_mut()[self.index].as_mut()?.pop_front()
+    }
+}
+
+// A recorded run playing the part of a remote racer over the loopback, for trying races out alone
+// It starts whenever the player does, and again when they restart
+pub(crate) struct StandIn {
+    id: u32,
+    name: String,
+    wardrobe: Wardrobe,
+    replay: Replay,
+    sim: GameState,
+    endpoint: Loopback,
+    ticks: u32,
+    started: bool,
+    rival_distance: f32,
+}
+
+impl StandIn {
+    pub(crate) fn new(id: u32, ghost: &Ghost, endpoint: Loopback) -> Self {
+        Self {
+            id,
+            name: ghost.name.clone(),
+            wardrobe: ghost.wardrobe.clone(),
+            replay: ghost.replay.clone(),
+            sim: GameState::new(),
+            endpoint,
+            ticks: 0,
+            started: false,
+            rival_distance: 0.0,
+        }
+    }
+
+    fn tick(&mut self) {
+        while let Some(packet) = self.endpoint.recv() {
+            let restart = match packet.message {
+                NetMessage::Hello { racing, .. } => racing && !self.started,
+                NetMessage::Update(update) => {
+                    let restarted = update.distance < self.rival_distance;
+                    self.rival_distance = update.distance;
+                    !self.started || restarted
+                }
+                NetMessage::Bye => false,
+            };
+            if restart {
+                self.sim.start_run(self.replay.setup());
+                self.started = true;
+            }
+        }
+
+        if self.sim.mode == GameMode::Playing {
+            match self.replay.get(self.sim.run_frames) {
+                Some(inputs) => {
+                    self.sim.step(&inputs);
+                }
+                None => self.sim.mode = GameMode::GameOver,
+            }
+        }
+
+        self.ticks += 1;
+        let update = self.started.then(|| self.sim.racer_update());
+        let packet = status_packet(self.id, self.ticks, &self.name, &self.wardrobe, self.replay.setup(), update);
+        if let Some(packet) = packet {
+            let _ = self.endpoint.send(&packet);
+        }
+    }
+}
+
+// Hello every HELLO_INTERVAL ticks, position updates in between once racing
+fn status_packet(
+    id: u32,
+    ticks: u32,
+    name: &str,
+    wardrobe: &Wardrobe,
+    setup: RunSetup,
+    update: Option<RacerUpdate>,
+) -> Option<Packet> {
+    let message = if ticks.is_multiple_of(HELLO_INTERVAL) {
+        NetMessage::Hello {
+            name: name.to_string(),
+            wardrobe: wardrobe.clone(),
+            setup,
+            racing: update.is_some(),
+        }
+    } else if ticks.is_multiple_of(UPDATE_INTERVAL) {
+        NetMessage::Update(update?)
+    } else {
+        return None;
+    };
+    Some(Packet { from: id, message })
+}
+
+// The connection for the current race; lives outside GameState since it can't be saved or cloned
+pub(crate) struct Net {
+    transport: Box<dyn Transport>,
+    stand_in: Option<StandIn>,
+}
+
+impl Net {
+    pub(crate) fn new(transport: Box<dyn Transport>, stand_in: Option<StandIn>) -> Self {
+        Self { transport, stand_in }
+    }
+}
+
+thread_local! {
+    static NET: RefCell<Option<Net>> = const { RefCell::new(None) };
+}
+
+// Someone else in the race, as last heard
+#[turbo::serialize]
+pub(crate) struct Racer {
+    pub(crate) id: u32,
+    pub(crate) name: String,
+    // The classic look until their hello comes through
+    wardrobe: Wardrobe,
+    setup: Option<RunSetup>,
+    racing: bool,
+    pub(crate) update: Option<RacerUpdate>,
+    heard_at: u32,
+}
+
+// An online race: our side of it plus everyone we've heard from
+#[turbo::serialize]
+pub(crate) struct Race {
+    id: u32,
+    name: String,
+    // Player one's wardrobe, for the others to draw us in
+    wardrobe: Wardrobe,
+    // Run everyone races; agreed in the lobby
+    pub(crate) setup: RunSetup,
+    racing: bool,
+    ticks: u32,
+    pub(crate) racers: Vec<Racer>,
+    // Where we were the last time we were playing, reported while on the results screen
+    last: Option<RacerUpdate>,
+    // Relay program, empty when racing the local stand-in
+    relay: String,
+    offline: bool,
+    notice: Option<(String, u32)>,
+}
+
+impl Race {
+    // Racers on the same run as us (others can't be drawn in step)
+    fn rivals(&self) -> impl Iterator<Item = &Racer> {
+        self.racers.iter().filter(|racer| racer.setup == Some(self.setup))
+    }
+}
+
+impl GameState {
+    // Join the race room: over the relay if one is configured, otherwise against a stand-in replaying a recorded run
+    pub(crate) fn open_lobby(&mut self) {
+        audio::stop("music-background");
+        let id = random::u32().max(1);
+        let setup = RunSetup {
+            seed: random::u32(),
+            tuning: self.tuning_config,
+            kind: RunKind::Endless,
+            modifiers: self.save.modifiers(),
+            hearts: self.save.hearts.for_kind(RunKind::Endless),
+            preset: self.save.preset.for_kind(RunKind::Endless),
+        };
+        let relay = env::get(RELAY_PROGRAM_ENV);
+        let net = if relay.is_empty() {
+            let hub = LoopbackHub::default();
+            let local = hub.connect();
+            // The stand-in's id is below any real one so the lobby settles on its run
+            let stand_in = self.stand_in_ghost().map(|ghost| StandIn::new(0, &ghost, hub.connect()));
+            Net::new(Box::new(local), stand_in)
+        } else {
+            Net::new(Box::new(TurboChannel::new(relay.clone())), None)
+        };
+        self.join_race(id, setup, relay, net);
+        self.mode = GameMode::Lobby;
+    }
+
+    // Best single-player recording to stand in for a remote racer
+    fn stand_in_ghost(&self) -> Option<Ghost> {
+        self.ghosts
+            .iter()
+            .filter(|ghost| ghost.replay.setup().kind.players() == 1)
+            .max_by_key(|ghost| ghost.score())
+            .cloned()
+    }
+
+    pub(crate) fn join_race(&mut self, id: u32, setup: RunSetup, relay: String, net: Net) {
+        NET.with(|cell| *cell.borrow_mut() = Some(net));
+        self.race = Some(Race {
+            id,
+            name: format!("RACER {:04X}", id & 0xffff),
+            wardrobe: self.save.wardrobe.clone(),
+            setup,
+            racing: false,
+            ticks: 0,
+            racers: Vec::new(),
+            last: None,
+            relay,
+            offline: false,
+            notice: None,
+        });
+    }
+
+    pub(crate) fn leave_race(&mut self) {
+        if let Some(race) = &self.race {
+            let bye = Packet {
+                from: race.id,
+                message: NetMessage::Bye,
+            };
+            NET.with(|cell| {
+                if let Some(net) = cell.borrow_mut().as_mut() {
+                    let _ = net.transport.send(&bye);
+                }
+                *cell.borrow_mut() = None;
+            });
+        }
+        self.race = None;
+    }
+
+    pub(crate) fn start_race(&mut self) {
+        let Some(race) = &mut self.race else {
+            return;
+        };
+        race.racing = true;
+        let setup = race.setup;
+        self.play_run(setup);
+    }
+
+    pub(crate) fn update_lobby(&mut self, gp: &gamepad::Gamepad) {
+        self.update_backdrop();
+
+        if gp.b.just_pressed() {
+            self.leave_race();
+            self.mode = GameMode::Title;
+        } else if gp.start.just_pressed() || gp.a.just_pressed() {
+            self.start_race();
+        }
+    }
+
+    // Where this run is, as told to the other racers
+    fn racer_update(&self) -> RacerUpdate {
+        RacerUpdate {
+            distance: self.distance,
+            y: self.player().y,
+            score: self.player().score,
+            alive: self.mode == GameMode::Playing,
+        }
+    }
+
+    // Exchange packets for this frame; runs in every mode while in a race
+    pub(crate) fn update_race(&mut self) {
+        if self.mode == GameMode::Playing {
+            let update = self.racer_update();
+            if let Some(race) = &mut self.race {
+                race.last = Some(update);
+            }
+        }
+        let Some(race) = &mut self.race else {
+            return;
+        };
+        NET.with(|cell| {
+            let mut cell = cell.borrow_mut();
+            // A hot reload loses the connection; the relay can just be rejoined (a stand-in is gone for good)
+            let net = cell.get_or_insert_with(|| {
+                let transport: Box<dyn Transport> = if race.relay.is_empty() {
+                    Box::new(LoopbackHub::default().connect())
+                } else {
+                    Box::new(TurboChannel::new(race.relay.clone()))
+                };
+                Net::new(transport, None)
+            });
+            if let Some(stand_in) = &mut net.stand_in {
+                stand_in.tick();
+            }
+            race.exchange(net.transport.as_mut());
+        });
+    }
+}
+
+impl Race {
+    // One frame of the race: tell the others where we are, then take in everything that has arrived
+    fn exchange(&mut self, transport: &mut dyn Transport) {
+        self.ticks += 1;
+        if let Some((_, frames)) = &mut self.notice {
+            *frames = frames.saturating_sub(1);
+        }
+
+        let update = if self.racing { self.last } else { None };
+        if let Some(packet) = status_packet(self.id, self.ticks, &self.name, &self.wardrobe, self.setup, update) {
+            match transport.send(&packet) {
+                Ok(()) => self.offline = false,
+                Err(err) if !self.offline => {
+                    log!("[race] {err}");
+                    self.offline = true;
+                }
+                Err(_) => {}
+            }
+        }
+        while let Some(packet) = transport.recv() {
+            self.receive(packet);
+        }
+        self.drop_silent_racers();
+        self.agree_on_setup();
+    }
+
+    // (name, score, still running) for everyone on this run including us, best first
+    fn standings(&self, score: u32) -> Vec<(String, u32, bool)> {
+        let mut standings: Vec<(String, u32, bool)> = self
+            .rivals()
+            .filter_map(|racer| racer.update.map(|update| (racer.name.clone(), update.score, update.alive)))
+            .collect();
+        standings.push(("YOU".to_string(), score, false));
+        standings.sort_by_key(|(_, score, _)| std::cmp::Reverse(*score));
+        standings
+    }
+
+    fn receive(&mut self, packet: Packet) {
+        // The relay echoes our own packets back
+        if packet.from == self.id {
+            return;
+        }
+        if let NetMessage::Bye = packet.message {
+            if let Some(index) = self.racers.iter().position(|racer| racer.id == packet.from) {
+                let racer = self.racers.remove(index);
+                self.notice = Some((format!("{} LEFT", racer.name), NOTICE_FRAMES));
+            }
+            return;
+        }
+
+        let index = match self.racers.iter().position(|racer| racer.id == packet.from) {
+            Some(index) => index,
+            None => {
+                // Heard an update before the hello: track them until their name comes through
+                self.racers.push(Racer {
+                    id: packet.from,
+                    name: "RACER".to_string(),
+                    wardrobe: Wardrobe::default(),
+                    setup: None,
+                    racing: false,
+                    update: None,
+                    heard_at: self.ticks,
+                });
+                self.racers.len() - 1
+            }
+        };
+        let racer = &mut self.racers[index];
+        racer.heard_at = self.ticks;
+        match packet.message {
+            NetMessage::Hello { name, wardrobe, setup, racing } => {
+                if racer.setup.is_none() {
+                    self.notice = Some((format!("{} JOINED", name.to_uppercase()), NOTICE_FRAMES));
+                }
+                racer.name = name.to_uppercase();
+                racer.wardrobe = wardrobe;
+                racer.setup = Some(setup);
+                racer.racing = racing;
+            }
+            NetMessage::Update(update) => {
+                racer.update = Some(update);
+                racer.racing = true;
+            }
+            NetMessage::Bye => {}
+        }
+    }
+
+    fn drop_silent_racers(&mut self) {
+        let ticks = self.ticks;
+        let (gone, kept): (Vec<Racer>, Vec<Racer>) =
+            self.racers.drain(..).partition(|racer| ticks - racer.heard_at > TIMEOUT_FRAMES);
+        self.racers = kept;
+        if let Some(racer) = gone.last() {
+            self.notice = Some((format!("{} DISCONNECTED", racer.name), NOTICE_FRAMES));
+        }
+    }
+
+    // In the lobby, take the run of anyone already racing (late joiners slot into the race under way),
+    // otherwise the run proposed by the lowest id, so everyone waiting ends up on the same seed
+    fn agree_on_setup(&mut self) {
+        if self.racing {
+            return;
+        }
+        let racing = self.racers.iter().find(|racer| racer.racing).and_then(|racer| racer.setup);
+        let lowest = self
+            .racers
+            .iter()
+            .filter(|racer| racer.id < self.id)
+            .min_by_key(|racer| racer.id)
+            .and_then(|racer| racer.setup);
+        if let Some(setup) = racing.or(lowest) {
+            self.setup = setup;
+        }
+    }
+}
+
+impl GameState {
+    pub(crate) fn render_lobby(&self, draw: &mut DrawList) {
+        let Some(race) = &self.race else {
+            return;
+        };
+        draw.text("ONLINE RACE", 66, 12, "large", COLOR_AURORA_GREEN);
+        let link = if race.relay.is_empty() {
+            "LOCAL STAND-IN (no relay configured)".to_string()
+        } else if race.offline {
+            "RELAY OFFLINE".to_string()
+        } else {
+            format!("RELAY {}", race.relay.to_uppercase())
+        };
+        let link_color = if race.offline { COLOR_AURORA_RED } else { 0xaaaaaaff };
+        draw.text(link, 28, 28, "small", link_color);
+        draw.text(format!("SEED: {:08X}", race.setup.seed), 28, 38, "small", COLOR_TEXT);
+
+        draw.rect(24, 50, 208, 60, COLOR_UI_BG);
+        draw.text(format!("{} (YOU)", race.name), 32, 54, "small", COLOR_STAR);
+        if race.racers.is_empty() {
+            draw.text("Waiting for racers...", 32, 66, "small", 0xaaaaaaff);
+        }
+        for (row, racer) in race.racers.iter().take(5).enumerate() {
+            let y = 66 + row as i32 * 9;
+            draw.text(racer.name.clone(), 32, y, "small", COLOR_TEXT);
+            let (status, color) = match (racer.setup == Some(race.setup), racer.racing) {
+                (false, _) => ("OTHER SEED", 0xaaaaaaff),
+                (true, true) => ("RACING", COLOR_AURORA_GREEN),
+                (true, false) => ("READY", COLOR_TEXT),
+            };
+            draw.text(status, 168, y, "small", color);
+        }
+
+        let under_way = race.rivals().any(|racer| racer.racing);
+        let prompt = if under_way { "[A] Join Race  [B] Back" } else { "[A] Start  [B] Back" };
+        if (self.frame / 30).is_multiple_of(2) {
+            draw.text(prompt, 64, 120, "small", COLOR_TEXT);
+        }
+        self.render_race_notice(draw);
+    }
+
+    // Other racers as ghosts, placed by how far ahead or behind they are
+    // Anyone off screen gets a marker at the edge instead
+    pub(crate) fn render_racers(&self, draw: &mut DrawList) {
+        let Some(race) = &self.race else {
+            return;
+        };
+        let player_x = self.player().x;
+        let mut ahead = 0;
+        let mut behind = 0;
+        for racer in race.rivals() {
+            let Some(update) = racer.update else {
+                continue;
+            };
+            let x = player_x + update.distance - self.distance;
+            let gap = ((update.distance - self.distance) / 10.0) as i32;
+            let opacity = if update.alive { GHOST_OPACITY } else { GHOST_OPACITY * 0.5 };
+            if x < -8.0 {
+                draw.text(format!("< {} {gap}m", racer.name), 4, 120 - behind * 8, "small", 0xaaaaaaff);
+                behind += 1;
+            } else if x > 264.0 {
+                draw.text(format!("{} +{gap}m >", racer.name), 150, 120 - ahead * 8, "small", 0xaaaaaaff);
+                ahead += 1;
+            } else {
+                cosmetics::render_outfit(draw, &racer.wardrobe.outfit(), x as i32, update.y as i32, opacity);
+                draw.text(racer.name.clone(), x as i32 - 12, update.y as i32 - 28, "small", 0xccccccff);
+            }
+        }
+
+        let (place, field) = self.race_place();
+        draw.text(format!("POS {place}/{field}"), 132, 8, "small", COLOR_STAR);
+        self.render_race_notice(draw);
+    }
+
+    // Our place by score among everyone on this run, and how many that is
+    fn race_place(&self) -> (usize, usize) {
+        let Some(race) = &self.race else {
+            return (1, 1);
+        };
+        let score = self.player().score;
+        let scores: Vec<u32> = race.rivals().filter_map(|racer| racer.update.map(|update| update.score)).collect();
+        let place = 1 + scores.iter().filter(|&&other| other > score).count();
+        (place, scores.len() + 1)
+    }
+
+    // Everyone's score, best first, over the results screen
+    pub(crate) fn render_standings(&self, draw: &mut DrawList) {
+        let Some(race) = &self.race else {
+            return;
+        };
+        let standings = race.standings(self.player().score);
+        for (row, (name, score, running)) in standings.iter().take(3).enumerate() {
+            let running = if *running { " (RUNNING)" } else { "" };
+            let color = if name == "YOU" { COLOR_STAR } else { COLOR_TEXT };
+            draw.text(format!("{}. {name} {score}{running}", row + 1), 4, 4 + row as i32 * 8, "small", color);
+        }
+        draw.text("[B] Leave Race", 4, 126, "small", 0xaaaaaaff);
+        self.render_race_notice(draw);
+    }
+
+    fn render_race_notice(&self, draw: &mut DrawList) {
+        if let Some((text, frames)) = self.race.as_ref().and_then(|race| race.notice.as_ref()) {
+            if *frames > 0 {
+                draw.text(text.clone(), 160, 136, "small", COLOR_AURORA_GREEN);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::start;
+
+    // A racer in the lobby proposing a run on `seed`
+    fn racer(id: u32, seed: u32) -> Race {
+        let mut setup = start(RunKind::Endless, 0).replay.setup();
+        setup.seed = seed;
+        Race {
+            id,
+            name: format!("RACER {id}"),
+            wardrobe: Wardrobe::default(),
+            setup,
+            racing: false,
+            ticks: 0,
+            racers: Vec::new(),
+            last: None,
+            relay: String::new(),
+            offline: false,
+            notice: None,
+        }
+    }
+
+    fn tick(racers: &mut [(&mut Race, &mut Loopback)], frames: u32) {
+        for _ in 0..frames {
+            for (race, endpoint) in racers.iter_mut() {
+                race.exchange(*endpoint);
+            }
+        }
+    }
+
+    fn update(score: u32) -> RacerUpdate {
+        RacerUpdate {
+            distance: score as f32,
+            y: 0.0,
+            score,
+            alive: true,
+        }
+    }
+
+    #[test]
+    fn lobby_settles_on_the_lowest_ids_run() {
+        let hub = LoopbackHub::default();
+        let (mut one, mut two) = (hub.connect(), hub.connect());
+        let (mut low, mut high) = (racer(3, 111), racer(8, 222));
+        // A hello reaches racers that already went this frame one frame later
+        tick(&mut [(&mut low, &mut one), (&mut high, &mut two)], HELLO_INTERVAL + 1);
+
+        assert_eq!(high.setup.seed, 111);
+        assert_eq!(low.setup.seed, 111);
+        assert_eq!(low.racers.len(), 1);
+        assert_eq!(high.racers[0].name, "RACER 3");
+
+        // A late joiner takes the run already under way, even with the lowest id
+        low.racing = true;
+        low.last = Some(update(0));
+        let mut three = hub.connect();
+        let mut late = racer(1, 333);
+        tick(&mut [(&mut low, &mut one), (&mut high, &mut two), (&mut late, &mut three)], HELLO_INTERVAL);
+        assert_eq!(late.setup.seed, 111);
+    }
+
+    #[test]
+    fn racers_are_drawn_in_their_own_wardrobe() {
+        let hub = LoopbackHub::default();
+        let (mut one, mut two) = (hub.connect(), hub.connect());
+        let (mut plain, mut dressed) = (racer(3, 111), racer(8, 111));
+        dressed.wardrobe.hat = "elf".to_string();
+        tick(&mut [(&mut plain, &mut one), (&mut dressed, &mut two)], HELLO_INTERVAL + 1);
+
+        assert_eq!(plain.racers[0].wardrobe, dressed.wardrobe);
+        assert_eq!(dressed.racers[0].wardrobe, Wardrobe::default());
+    }
+
+    #[test]
+    fn standings_rank_everyone_on_the_run() {
+        let hub = LoopbackHub::default();
+        let (mut one, mut two, mut three) = (hub.connect(), hub.connect(), hub.connect());
+        let (mut a, mut b, mut c) = (racer(1, 5), racer(2, 5), racer(3, 6));
+        for race in [&mut a, &mut b, &mut c] {
+            race.racing = true;
+        }
+        a.last = Some(update(300));
+        b.last = Some(update(100));
+        c.last = Some(update(900));
+        tick(&mut [(&mut a, &mut one), (&mut b, &mut two), (&mut c, &mut three)], HELLO_INTERVAL);
+
+        // C is on another seed, so it isn't in the race
+        let standings: Vec<(String, u32)> =
+            b.standings(200).into_iter().map(|(name, score, _)| (name, score)).collect();
+        assert_eq!(standings, [("RACER 1".to_string(), 300), ("YOU".to_string(), 200)]);
+    }
+
+    #[test]
+    fn silent_racers_are_dropped() {
+        let hub = LoopbackHub::default();
+        let (mut one, mut two) = (hub.connect(), hub.connect());
+        let (mut stays, mut goes) = (racer(1, 5), racer(2, 5));
+        tick(&mut [(&mut stays, &mut one), (&mut goes, &mut two)], HELLO_INTERVAL + 1);
+        assert_eq!(stays.racers.len(), 1);
+
+        // Lost connection: no goodbye, just silence
+        drop(two);
+        tick(&mut [(&mut stays, &mut one)], TIMEOUT_FRAMES);
+        assert_eq!(stays.racers.len(), 1);
+        tick(&mut [(&mut stays, &mut one)], 1);
+        assert!(stays.racers.is_empty());
+        assert_eq!(stays.notice, Some(("RACER 2 DISCONNECTED".to_string(), NOTICE_FRAMES)));
+    }
+}
@@ -0,0 +1,103 @@
+use crate::replay::RunResult;
+use crate::{RunKind, GROUND_Y, PLAYER_X};
+
+// Where player two runs in versus, a little ahead of player one
+pub(crate) const PLAYER_TWO_X: f32 = 72.0;
+
+// Where player two runs in co-op, just ahead of player one
+pub(crate) const COOP_PARTNER_X: f32 = 52.0;
+
+// One reindeer: its jump, its score and the power-ups it's carrying
+#[turbo::serialize]
+pub(crate) struct Player {
+    pub(crate) x: f32,
+    // Where it runs; a knockback pushes `x` behind this for a moment
+    pub(crate) home_x: f32,
+    pub(crate) y: f32,
+    pub(crate) velocity_y: f32,
+    pub(crate) is_jumping: bool,
+    pub(crate) jump_hold_frames: u32,
+    pub(crate) is_on_ground: bool,
+    pub(crate) rotation: f32,
+    pub(crate) alive: bool,
+    // Frames until a downed co-op reindeer rejoins, 0 when it isn't coming back
+    pub(crate) revive_timer: u32,
+    // Hits it can still take (1 = the next one ends its run), and frames until it can be hurt again
+    pub(crate) hearts: u32,
+    pub(crate) max_hearts: u32,
+    pub(crate) invulnerable_timer: u32,
+
+    // Scoring
+    pub(crate) score: u32,
+    pub(crate) combo: u32,
+    pub(crate) max_combo: u32,
+    pub(crate) stars_collected: u32,
+
+    // Power-up effects
+    pub(crate) has_shield: bool,
+    pub(crate) shield_timer: u32,
+    pub(crate) slow_mo_timer: u32,
+    pub(crate) magnet_timer: u32,
+    pub(crate) double_points_timer: u32,
+
+    // Streak system
+    pub(crate) perfect_landings: u32,
+    pub(crate) near_miss_count: u32,
+}
+
+impl Player {
+    pub(crate) fn new(x: f32, hearts: u32) -> Self {
+        Self {
+            x,
+            home_x: x,
+            y: GROUND_Y,
+            velocity_y: 0.0,
+            is_jumping: false,
+            jump_hold_frames: 0,
+            is_on_ground: true,
+            rotation: 0.0,
+            alive: true,
+            revive_timer: 0,
+            hearts,
+            max_hearts: hearts,
+            invulnerable_timer: 0,
+            score: 0,
+            combo: 0,
+            max_combo: 0,
+            stars_collected: 0,
+            has_shield: false,
+            shield_timer: 0,
+            slow_mo_timer: 0,
+            magnet_timer: 0,
+            double_points_timer: 0,
+            perfect_landings: 0,
+            near_miss_count: 0,
+        }
+    }
+
+    // Fresh players for a run of `kind`, each with `hearts`
+    pub(crate) fn lineup(kind: RunKind, hearts: u32) -> Vec<Self> {
+        let partner_x = match kind {
+            RunKind::Coop => COOP_PARTNER_X,
+            _ => PLAYER_TWO_X,
+        };
+        [PLAYER_X, partner_x].into_iter().take(kind.players()).map(|x| Self::new(x, hearts)).collect()
+    }
+
+    // Score multiplier from the 2x gift
+    pub(crate) fn multiplier(&self) -> u32 {
+        if self.double_points_timer > 0 {
+            2
+        } else {
+            1
+        }
+    }
+
+    pub(crate) fn result(&self) -> RunResult {
+        RunResult {
+            score: self.score,
+            max_combo: self.max_combo,
+            stars_collected: self.stars_collected,
+        }
+    }
+}
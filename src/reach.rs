@@ -0,0 +1,143 @@
+use crate::player::Player;
+use crate::tuning::Tuning;
+use crate::{Obstacle, GROUND_Y, PLAYER_X};
+
+// Every path the reindeer can take through the air
+// arcs[h - 1][i] is (y, velocity) at the end of the i-th airborne frame of a jump held for h frames;
+// the frame after an arc's last entry is the landing
+pub(crate) struct JumpEnvelope {
+    arcs: Vec<Vec<(f32, f32)>>,
+}
+
+// Where the reindeer is within the envelope
+#[derive(Clone, Copy, PartialEq)]
+enum Pose {
+    Ground,
+    Air { hold: usize, index: usize },
+}
+
+impl JumpEnvelope {
+    pub(crate) fn new(tuning: &Tuning) -> Self {
+        Self {
+            arcs: (1..=tuning.max_jump_hold).map(|hold| jump_arc(tuning, hold)).collect(),
+        }
+    }
+
+    fn height(&self, pose: Pose) -> f32 {
+        match pose {
+            Pose::Ground => GROUND_Y,
+            Pose::Air { hold, index } => self.arcs[hold][index].0,
+        }
+    }
+
+    // Poses reachable one frame after `pose`
+    fn successors(&self, pose: Pose, next: &mut Vec<Pose>) {
+        match pose {
+            Pose::Ground => {
+                next.push(Pose::Ground);
+                next.extend((0..self.arcs.len()).map(|hold| Pose::Air { hold, index: 0 }));
+            }
+            Pose::Air { hold, index } if index + 1 < self.arcs[hold].len() => {
+                next.push(Pose::Air { hold, index: index + 1 });
+            }
+            Pose::Air { .. } => next.push(Pose::Ground),
+        }
+    }
+
+    // Poses matching the player's current height and velocity
+    // Mid-jump the hold length isn't decided yet, so every arc sharing that prefix matches
+    fn poses_of(&self, player: &Player) -> Vec<Pose> {
+        let mut poses = Vec::new();
+        if !player.is_on_ground {
+            for (hold, arc) in self.arcs.iter().enumerate() {
+                for (index, &(y, velocity)) in arc.iter().enumerate() {
+                    if (y - player.y).abs() < 0.01 && (velocity - player.velocity_y).abs() < 0.01 {
+                        poses.push(Pose::Air { hold, index });
+                    }
+                }
+            }
+        }
+        if poses.is_empty() {
+            poses.push(Pose::Ground);
+        }
+        poses
+    }
+
+    // Can `player` get past every obstacle in `obstacles`, all scrolling at `speed`, from where they are now?
+    pub(crate) fn survivable(&self, player: &Player, obstacles: &[Obstacle], speed: f32) -> bool {
+        self.survivable_from(self.poses_of(player), player.x, obstacles, speed)
+    }
+
+    // Same, for a player standing at the usual spot
+    pub(crate) fn clearable(&self, obstacles: &[Obstacle], speed: f32) -> bool {
+        self.survivable_from(vec![Pose::Ground], PLAYER_X, obstacles, speed)
+    }
+
+    // Runs until the last obstacle is fully behind the player's hitbox
+    fn survivable_from(&self, mut alive: Vec<Pose>, player_x: f32, obstacles: &[Obstacle], speed: f32) -> bool {
+        let Some(last_edge) = obstacles.iter().map(|o| o.x + o.width).reduce(f32::max) else {
+            return true;
+        };
+        let speed = speed.max(0.1);
+        let frames = ((last_edge - (player_x - 8.0)) / speed).ceil().max(0.0) as u32;
+
+        // Frames during which each obstacle is level with the player's hitbox
+        let windows: Vec<(f32, f32)> = obstacles
+            .iter()
+            .map(|o| ((o.x - (player_x + 8.0)) / speed, (o.x + o.width - (player_x - 8.0)) / speed))
+            .collect();
+        let longest_arc = self.arcs.iter().map(Vec::len).max().unwrap_or(0) as f32;
+
+        let mut next = Vec::new();
+        for frame in 1..=frames {
+            // Nothing to dodge for a whole jump's length: anything airborne lands in time,
+            // so standing on the ground covers every option
+            let horizon = (frame as f32, frame as f32 + longest_arc);
+            if alive.contains(&Pose::Ground) && !windows.iter().any(|w| w.0 <= horizon.1 && w.1 >= horizon.0) {
+                alive.clear();
+                alive.push(Pose::Ground);
+                continue;
+            }
+
+            let offset = speed * frame as f32;
+            next.clear();
+            for &pose in &alive {
+                self.successors(pose, &mut next);
+            }
+            next.sort_by_key(|pose| match pose {
+                Pose::Ground => (0, 0),
+                Pose::Air { hold, index } => (hold + 1, *index),
+            });
+            next.dedup();
+            next.retain(|&pose| {
+                let y = self.height(pose);
+                !obstacles.iter().any(|o| o.hits_at(o.x - offset, player_x, y))
+            });
+            if next.is_empty() {
+                return false;
+            }
+            std::mem::swap(&mut alive, &mut next);
+        }
+        true
+    }
+}
+
+// Same physics as `GameState::step` for a jump pressed on frame 0 and held for `hold` frames
+fn jump_arc(tuning: &Tuning, hold: u32) -> Vec<(f32, f32)> {
+    let mut arc = Vec::new();
+    let mut y = GROUND_Y;
+    let mut velocity = tuning.jump_power;
+    let mut held = 0;
+    loop {
+        if held < hold {
+            velocity += (tuning.jump_power - tuning.min_jump_power) / tuning.max_jump_hold as f32;
+            held += 1;
+        }
+        velocity = (velocity + tuning.gravity).min(15.0);
+        y += velocity;
+        if y >= GROUND_Y {
+            return arc;
+        }
+        arc.push((y, velocity));
+    }
+}
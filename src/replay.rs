@@ -0,0 +1,538 @@
+use turbo::*;
+
+use crate::difficulty::Preset;
+use crate::levels;
+use crate::shop::Modifiers;
+use crate::tuning::Tuning;
+use crate::{GameMode, GameState, InputFrame, RunKind, GROUND_Y, PARTICLE_SPAWN_RATE, PLAYER_X, SPAWN_ATTEMPTS};
+
+// Replay file format
+// Binary: REPLAY_MAGIC followed by the Borsh-encoded ReplayFile
+// Text: the ReplayFile as JSON
+// Bump REPLAY_FORMAT_VERSION whenever ReplayFile's layout changes
+const REPLAY_MAGIC: &[u8; 4] = b"AURR";
+const REPLAY_FORMAT_VERSION: u16 = 8;
+
+// Bump SIM_VERSION whenever a change to the simulation (`step`, spawning, scoring, the RNG...)
+// would make a recorded run play out differently; replays from other versions are then rejected
+pub(crate) const SIM_VERSION: u32 = 2;
+
+// Frames between recorded stat checkpoints
+const CHECKPOINT_INTERVAL: u32 = 60;
+
+// Longest run a replay file may hold (four hours at 60 fps), so a forged file can't make the verifier
+// expand billions of frames
+const MAX_REPLAY_FRAMES: u32 = 4 * 60 * 60 * 60;
+
+// Stats a run is scored on
+#[turbo::serialize]
+#[derive(Copy, PartialEq)]
+pub(crate) struct RunResult {
+    pub(crate) score: u32,
+    pub(crate) max_combo: u32,
+    pub(crate) stars_collected: u32,
+}
+
+// Run stats as of the end of `frame`, recorded so a verifier can pinpoint desyncs
+#[turbo::serialize]
+#[derive(PartialEq)]
+pub(crate) struct Checkpoint {
+    pub(crate) frame: u32,
+    pub(crate) result: RunResult,
+}
+
+// Everything besides player input that decides how a run plays out
+#[turbo::serialize]
+#[derive(Copy, PartialEq)]
+pub(crate) struct RunSetup {
+    pub(crate) seed: u32,
+    pub(crate) tuning: Tuning,
+    pub(crate) kind: RunKind,
+    // Shop upgrades the run was played with
+    #[serde(default)]
+    pub(crate) modifiers: Modifiers,
+    // Hearts each player started with (1 = any hit ends the run)
+    #[serde(default = "classic_hearts")]
+    pub(crate) hearts: u32,
+    // Difficulty preset (Normal when missing)
+    #[serde(default)]
+    pub(crate) preset: Preset,
+}
+
+fn classic_hearts() -> u32 {
+    1
+}
+
+// Input log for a single run
+// Packed 2 bits per player per frame: bit 0 = up held, bit 1 = up just pressed, player n shifted up by 2n
+#[turbo::serialize]
+#[derive(PartialEq)]
+pub(crate) struct Replay {
+    setup: RunSetup,
+    // `sim_hash` of the simulation and chunk library the run is played on
+    sim_hash: u64,
+    frames: u32,
+    data: Vec<u8>,
+    checkpoints: Vec<Checkpoint>,
+}
+
+impl Replay {
+    pub(crate) fn new(setup: RunSetup, sim_hash: u64) -> Self {
+        Self {
+            setup,
+            sim_hash,
+            frames: 0,
+            data: Vec::new(),
+            checkpoints: Vec::new(),
+        }
+    }
+
+    pub(crate) fn setup(&self) -> RunSetup {
+        self.setup
+    }
+
+    // Number of recorded frames
+    pub(crate) fn frames(&self) -> u32 {
+        self.frames
+    }
+
+    // Bits one frame takes up
+    fn frame_width(&self) -> u32 {
+        self.setup.kind.players() as u32 * 2
+    }
+
+    // Record one frame of input, one entry per player
+    pub(crate) fn push(&mut self, inputs: &[InputFrame]) {
+        let bits = inputs
+            .iter()
+            .enumerate()
+            .fold(0, |bits, (player, input)| bits | input_to_bits(*input) << (player * 2));
+        self.push_bits(bits);
+    }
+
+    fn push_bits(&mut self, bits: u8) {
+        let width = self.frame_width();
+        let shift = (self.frames * width) % 8;
+        if shift == 0 {
+            self.data.push(0);
+        }
+        if let Some(byte) = self.data.last_mut() {
+            *byte |= bits << shift;
+        }
+        self.frames += 1;
+    }
+
+    // Inputs recorded for `frame`, one per player, or None past the end of the run
+    pub(crate) fn get(&self, frame: u32) -> Option<Vec<InputFrame>> {
+        let bits = self.bits(frame)?;
+        let players = self.setup.kind.players();
+        Some((0..players).map(|player| input_from_bits(bits >> (player * 2))).collect())
+    }
+
+    fn bits(&self, frame: u32) -> Option<u8> {
+        if frame >= self.frames {
+            return None;
+        }
+        let width = self.frame_width();
+        let offset = frame * width;
+        let mask = ((1u16 << width) - 1) as u8;
+        Some(self.data[(offset / 8) as usize] >> (offset % 8) & mask)
+    }
+
+    pub(crate) fn checkpoints(&self) -> &[Checkpoint] {
+        &self.checkpoints
+    }
+}
+
+fn input_to_bits(input: InputFrame) -> u8 {
+    input.up_pressed as u8 | (input.up_just_pressed as u8) << 1
+}
+
+fn input_from_bits(bits: u8) -> InputFrame {
+    InputFrame {
+        up_pressed: bits & 0b01 != 0,
+        up_just_pressed: bits & 0b10 != 0,
+    }
+}
+
+// On-disk form of a replay
+#[turbo::serialize]
+#[derive(PartialEq)]
+pub(crate) struct ReplayFile {
+    format_version: u16,
+    game_version: String,
+    setup: RunSetup,
+    // Named tuning_hash before format 8, which is how older replays left in a save still parse
+    #[serde(alias = "tuning_hash")]
+    sim_hash: u64,
+    frames: u32,
+    inputs: Vec<InputRun>,
+    checkpoints: Vec<Checkpoint>,
+}
+
+// Run-length encoded stretch of identical input frames
+#[turbo::serialize]
+#[derive(PartialEq)]
+struct InputRun {
+    input: u8,
+    frames: u32,
+}
+
+#[derive(Debug, PartialEq)]
+pub(crate) enum ReplayError {
+    Malformed(String),
+    UnsupportedFormat { found: u16, expected: u16 },
+    SimMismatch { game_version: String },
+}
+
+impl std::fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Malformed(reason) => write!(f, "malformed replay: {reason}"),
+            Self::UnsupportedFormat { found, expected } => write!(
+                f,
+                "unsupported replay format version {found} (this build reads version {expected})"
+            ),
+            Self::SimMismatch { game_version } => write!(
+                f,
+                "replay was recorded on a different simulation or chunk library (game version {game_version}) and would desync"
+            ),
+        }
+    }
+}
+
+// Fingerprint of everything besides the run setup that decides how a replay plays out: SIM_VERSION,
+// the fixed constants and the built-in chunk library (tuning.json values travel inside the replay instead)
+// Replays recorded against anything else are rejected instead of desyncing
+pub(crate) fn sim_hash() -> u64 {
+    library_sim_hash(levels::LEVELS_SOURCE)
+}
+
+// Same, for a run played on the chunk library parsed from `levels_source`
+// (after an editor export, which no other player or verifier has)
+pub(crate) fn library_sim_hash(levels_source: &str) -> u64 {
+    sim_hash_at(SIM_VERSION, levels_source)
+}
+
+// The fingerprint a build on simulation version `sim_version` gave runs on `levels_source`
+pub(crate) fn sim_hash_at(sim_version: u32, levels_source: &str) -> u64 {
+    let mut bytes = sim_version.to_le_bytes().to_vec();
+    bytes.extend(unversioned_sim_bytes(levels_source));
+    fnv1a(&bytes)
+}
+
+// What replay formats before 8 stored as the fingerprint, from before SIM_VERSION existed
+pub(crate) fn unversioned_sim_hash(levels_source: &str) -> u64 {
+    fnv1a(&unversioned_sim_bytes(levels_source))
+}
+
+fn unversioned_sim_bytes(levels_source: &str) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    for value in [PLAYER_X, GROUND_Y] {
+        bytes.extend(value.to_le_bytes());
+    }
+    for value in [PARTICLE_SPAWN_RATE, SPAWN_ATTEMPTS] {
+        bytes.extend(value.to_le_bytes());
+    }
+    bytes.extend(levels_source.as_bytes());
+    bytes
+}
+
+// FNV-1a: stable across builds and platforms, unlike std's hasher
+pub(crate) fn fnv1a(bytes: &[u8]) -> u64 {
+    let mut hash = 0xcbf29ce484222325u64;
+    for byte in bytes {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+impl ReplayFile {
+    pub(crate) fn from_replay(replay: &Replay) -> Self {
+        let mut inputs: Vec<InputRun> = Vec::new();
+        for frame in 0..replay.frames() {
+            let input = replay.bits(frame).unwrap_or(0);
+            match inputs.last_mut() {
+                Some(run) if run.input == input => run.frames += 1,
+                _ => inputs.push(InputRun { input, frames: 1 }),
+            }
+        }
+        Self {
+            format_version: REPLAY_FORMAT_VERSION,
+            game_version: env!("CARGO_PKG_VERSION").to_string(),
+            setup: replay.setup(),
+            sim_hash: replay.sim_hash,
+            frames: replay.frames(),
+            inputs,
+            checkpoints: replay.checkpoints.clone(),
+        }
+    }
+
+    pub(crate) fn into_replay(self) -> Result<Replay, ReplayError> {
+        if self.format_version != REPLAY_FORMAT_VERSION {
+            return Err(ReplayError::UnsupportedFormat {
+                found: self.format_version,
+                expected: REPLAY_FORMAT_VERSION,
+            });
+        }
+        if self.sim_hash != sim_hash() {
+            return Err(ReplayError::SimMismatch {
+                game_version: self.game_version,
+            });
+        }
+        let mut replay = Replay::new(self.setup, self.sim_hash);
+        let width = replay.frame_width();
+        if let Some(run) = self.inputs.iter().find(|run| run.input >> width != 0) {
+            return Err(ReplayError::Malformed(format!(
+                "input {:#b} has bits set past the {width} a frame takes",
+                run.input
+            )));
+        }
+        // Check the lengths before expanding anything
+        let streamed: u64 = self.inputs.iter().map(|run| run.frames as u64).sum();
+        if streamed != self.frames as u64 {
+            return Err(ReplayError::Malformed(format!(
+                "header says {} frames but input stream has {streamed}",
+                self.frames
+            )));
+        }
+        if self.frames > MAX_REPLAY_FRAMES {
+            return Err(ReplayError::Malformed(format!(
+                "{} frames is longer than any run ({MAX_REPLAY_FRAMES} at most)",
+                self.frames
+            )));
+        }
+        for run in &self.inputs {
+            for _ in 0..run.frames {
+                replay.push_bits(run.input);
+            }
+        }
+        replay.checkpoints = self.checkpoints;
+        Ok(replay)
+    }
+}
+
+// Binary and text decoding are for tools and tests; the game itself only hands out text
+#[cfg_attr(not(test), allow(dead_code))]
+pub(crate) fn encode(replay: &Replay) -> Vec<u8> {
+    let mut bytes = REPLAY_MAGIC.to_vec();
+    // Serializing into a Vec can't fail
+    bytes.extend(borsh::to_vec(&ReplayFile::from_replay(replay)).unwrap_or_default());
+    bytes
+}
+
+#[cfg_attr(not(test), allow(dead_code))]
+pub(crate) fn decode(bytes: &[u8]) -> Result<Replay, ReplayError> {
+    let body = bytes
+        .strip_prefix(REPLAY_MAGIC)
+        .ok_or_else(|| ReplayError::Malformed("not an Aurora replay".to_string()))?;
+    // Check the version before parsing the rest so old files get a clear error
+    let version = match body {
+        [lo, hi, ..] => u16::from_le_bytes([*lo, *hi]),
+        _ => return Err(ReplayError::Malformed("truncated header".to_string())),
+    };
+    if version != REPLAY_FORMAT_VERSION {
+        return Err(ReplayError::UnsupportedFormat {
+            found: version,
+            expected: REPLAY_FORMAT_VERSION,
+        });
+    }
+    let file: ReplayFile = borsh::from_slice(body).map_err(|err| ReplayError::Malformed(err.to_string()))?;
+    file.into_replay()
+}
+
+pub(crate) fn encode_text(replay: &Replay) -> String {
+    serde_json::to_string(&ReplayFile::from_replay(replay)).unwrap_or_default()
+}
+
+#[cfg_attr(not(test), allow(dead_code))]
+pub(crate) fn decode_text(text: &str) -> Result<Replay, ReplayError> {
+    let value: serde_json::Value =
+        serde_json::from_str(text).map_err(|err| ReplayError::Malformed(err.to_string()))?;
+    let version = value
+        .get("format_version")
+        .and_then(|v| v.as_u64())
+        .ok_or_else(|| ReplayError::Malformed("missing format_version".to_string()))?;
+    if version != REPLAY_FORMAT_VERSION as u64 {
+        return Err(ReplayError::UnsupportedFormat {
+            found: version.min(u16::MAX as u64) as u16,
+            expected: REPLAY_FORMAT_VERSION,
+        });
+    }
+    let file: ReplayFile = serde_json::from_value(value).map_err(|err| ReplayError::Malformed(err.to_string()))?;
+    file.into_replay()
+}
+
+impl GameState {
+    pub(crate) fn run_result(&self) -> RunResult {
+        self.player().result()
+    }
+
+    // Snapshot run stats into the recording every CHECKPOINT_INTERVAL frames and when the run ends
+    pub(crate) fn record_checkpoint(&mut self) {
+        if self.run_frames.is_multiple_of(CHECKPOINT_INTERVAL) || self.mode != GameMode::Playing {
+            self.replay.checkpoints.push(Checkpoint {
+                frame: self.run_frames,
+                result: self.run_result(),
+            });
+        }
+    }
+
+    // Re-run the last recorded run from its seed
+    pub(crate) fn start_replay(&mut self) {
+        let replay = self.replay.clone();
+        self.start_run(replay.setup());
+        self.replay = replay;
+        self.mode = GameMode::Replay;
+
+        audio::stop("music-background");
+        audio::play("music-background");
+    }
+
+    pub(crate) fn update_replay(&mut self, gp: &gamepad::Gamepad) {
+        // Skip straight to the end of the run
+        if gp.start.just_pressed() || gp.a.just_pressed() {
+            self.finish_replay();
+            return;
+        }
+
+        match self.replay.get(self.run_frames) {
+            Some(inputs) => {
+                self.update_playing(&inputs);
+            }
+            None => self.finish_replay(),
+        }
+    }
+
+    // Silently simulate the remaining recorded frames and return to the results screen
+    fn finish_replay(&mut self) {
+        while self.mode == GameMode::Replay {
+            match self.replay.get(self.run_frames) {
+                Some(inputs) => {
+                    self.step(&inputs);
+                }
+                None => self.mode = GameMode::GameOver,
+            }
+        }
+        audio::stop("music-background");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::record;
+
+    fn recorded() -> Replay {
+        let state = record(RunKind::Endless, 5, 3000);
+        assert!(state.replay.frames() > 0);
+        state.replay
+    }
+
+    #[test]
+    fn binary_round_trip() {
+        let replay = recorded();
+        let bytes = encode(&replay);
+        assert!(bytes.starts_with(REPLAY_MAGIC));
+        assert_eq!(decode(&bytes), Ok(replay));
+    }
+
+    #[test]
+    fn text_round_trip() {
+        let replay = recorded();
+        assert_eq!(decode_text(&encode_text(&replay)), Ok(replay));
+    }
+
+    #[test]
+    fn two_player_round_trip() {
+        let replay = record(RunKind::Versus, 5, 600).replay;
+        assert_eq!(replay.get(0).map(|inputs| inputs.len()), Some(2));
+        assert_eq!(decode(&encode(&replay)), Ok(replay.clone()));
+        assert_eq!(decode_text(&encode_text(&replay)), Ok(replay));
+    }
+
+    #[test]
+    fn wrong_magic_is_rejected() {
+        let mut bytes = encode(&recorded());
+        bytes[..4].copy_from_slice(b"NOPE");
+        assert!(matches!(decode(&bytes), Err(ReplayError::Malformed(_))));
+        assert!(matches!(decode(b"AUR"), Err(ReplayError::Malformed(_))));
+        assert!(matches!(decode(b"AURR\x07"), Err(ReplayError::Malformed(_))));
+    }
+
+    #[test]
+    fn older_format_version_is_rejected() {
+        let old = REPLAY_FORMAT_VERSION - 1;
+        let expected = Err(ReplayError::UnsupportedFormat {
+            found: old,
+            expected: REPLAY_FORMAT_VERSION,
+        });
+
+        let mut bytes = encode(&recorded());
+        bytes[4..6].copy_from_slice(&old.to_le_bytes());
+        assert_eq!(decode(&bytes), expected);
+
+        let mut value: serde_json::Value = serde_json::from_str(&encode_text(&recorded())).unwrap();
+        value["format_version"] = old.into();
+        assert_eq!(decode_text(&value.to_string()), expected);
+    }
+
+    #[test]
+    fn sim_hash_mismatch_is_rejected() {
+        let mut file = ReplayFile::from_replay(&recorded());
+        file.sim_hash ^= 1;
+        let expected = Err(ReplayError::SimMismatch {
+            game_version: env!("CARGO_PKG_VERSION").to_string(),
+        });
+        assert_eq!(decode_text(&serde_json::to_string(&file).unwrap()), expected);
+
+        let mut bytes = REPLAY_MAGIC.to_vec();
+        bytes.extend(borsh::to_vec(&file).unwrap());
+        assert_eq!(decode(&bytes), expected);
+    }
+
+    #[test]
+    fn other_sim_versions_are_rejected() {
+        let mut file = ReplayFile::from_replay(&recorded());
+        file.sim_hash = sim_hash_at(SIM_VERSION + 1, levels::LEVELS_SOURCE);
+        assert!(matches!(file.into_replay(), Err(ReplayError::SimMismatch { .. })));
+    }
+
+    #[test]
+    fn inputs_past_the_frame_width_are_rejected() {
+        let mut file = ReplayFile::from_replay(&recorded());
+        file.inputs[0].input |= 0b100;
+        assert!(matches!(file.into_replay(), Err(ReplayError::Malformed(_))));
+
+        // Player two's bits are fine on a two-player run
+        let mut file = ReplayFile::from_replay(&record(RunKind::Versus, 5, 600).replay);
+        file.inputs[0].input |= 0b1000;
+        assert!(file.into_replay().is_ok());
+    }
+
+    #[test]
+    fn frame_count_must_match_input_stream() {
+        let mut file = ReplayFile::from_replay(&recorded());
+        file.frames += 1;
+        assert!(matches!(file.into_replay(), Err(ReplayError::Malformed(_))));
+    }
+
+    #[test]
+    fn oversized_runs_are_rejected_before_expanding() {
+        let mut file = ReplayFile::from_replay(&recorded());
+        file.inputs = vec![InputRun { input: 0, frames: u32::MAX }, InputRun { input: 1, frames: u32::MAX }];
+        file.frames = u32::MAX;
+        assert!(matches!(file.clone().into_replay(), Err(ReplayError::Malformed(_))));
+
+        // Even when the header agrees
+        file.inputs.pop();
+        assert!(matches!(file.clone().into_replay(), Err(ReplayError::Malformed(_))));
+
+        // A few bytes on disk
+        let mut bytes = REPLAY_MAGIC.to_vec();
+        bytes.extend(borsh::to_vec(&file).unwrap());
+        assert!(bytes.len() < 1024);
+        assert!(matches!(decode(&bytes), Err(ReplayError::Malformed(_))));
+    }
+}
@@ -0,0 +1,41 @@
+// Seedable RNG for everything a run rolls (spawns, particles, power-ups)
+// Same seed + same inputs = same run, bit-for-bit
+#[turbo::serialize]
+#[derive(PartialEq)]
+pub(crate) struct Rng {
+    seed: u32,
+    state: u32,
+}
+
+impl Rng {
+    pub(crate) fn new(seed: u32) -> Self {
+        Self { seed, state: seed }
+    }
+
+    // Seed this generator was created with (shown on screen, stored in replays)
+    pub(crate) fn seed(&self) -> u32 {
+        self.seed
+    }
+
+    pub(crate) fn next_u32(&mut self) -> u32 {
+        // LCG step, then scramble the output so low bits are usable with `%`
+        self.state = self.state.wrapping_mul(1664525).wrapping_add(1013904223);
+        let mut x = self.state;
+        x ^= x >> 16;
+        x = x.wrapping_mul(0x7feb352d);
+        x ^= x >> 15;
+        x = x.wrapping_mul(0x846ca68b);
+        x ^= x >> 16;
+        x
+    }
+
+    // Random value in 0..n
+    pub(crate) fn below(&mut self, n: u32) -> u32 {
+        self.next_u32() % n.max(1)
+    }
+
+    // True roughly once every `n` rolls
+    pub(crate) fn one_in(&mut self, n: u32) -> bool {
+        self.below(n) == 0
+    }
+}
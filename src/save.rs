@@ -0,0 +1,497 @@
+use turbo::*;
+
+use crate::cosmetics::Wardrobe;
+use crate::difficulty::Preset;
+use crate::health::HeartRules;
+use crate::missions::Mission;
+use crate::levels::{self, SavedLevels};
+use crate::replay::{self, Replay, ReplayFile, RunResult};
+use crate::shop::{Modifiers, OwnedUpgrade};
+use crate::GameState;
+
+// Upgrade steps for older save files
+// MIGRATIONS[n] turns a version n+1 save into a version n+2 save,
+// so adding a step here is all it takes to bump SAVE_VERSION
+type Migration = fn(&mut serde_json::Map<String, serde_json::Value>);
+const MIGRATIONS: &[Migration] = &[add_level_bests, add_daily_best, add_best_replays, add_achievements, add_missions, add_upgrades, add_wardrobe, add_heart_rules, add_presets, add_sim_version, add_saved_levels];
+const SAVE_VERSION: u64 = MIGRATIONS.len() as u64 + 1;
+
+// Everything that survives between sessions
+#[turbo::serialize]
+#[derive(PartialEq, Default)]
+pub(crate) struct SaveData {
+    // Endless records, one per difficulty preset played
+    pub(crate) high_scores: Vec<HighScore>,
+    pub(crate) best_combo: u32,
+    pub(crate) total_distance: f32,
+    pub(crate) total_stars: u32,
+    pub(crate) runs_played: u32,
+    // Campaign records, by level name
+    pub(crate) level_bests: Vec<LevelBest>,
+    // Best daily run score, for the day it was set on
+    pub(crate) daily: DailyBest,
+    // Ids of unlocked achievements, in the order they were earned
+    pub(crate) achievements: Vec<String>,
+    // Currency from stars and completed missions
+    pub(crate) candy: u32,
+    // Missions on offer, and how many have ever been rolled (seeds the next one)
+    pub(crate) missions: Vec<Mission>,
+    pub(crate) mission_serial: u32,
+    // Shop upgrades bought, by id
+    pub(crate) upgrades: Vec<OwnedUpgrade>,
+    // Cosmetics picked on the wardrobe screen
+    pub(crate) wardrobe: Wardrobe,
+    // Hearts per run, by mode
+    pub(crate) hearts: HeartRules,
+    // Difficulty picked for the next endless, versus or co-op run
+    pub(crate) preset: Preset,
+    // The last editor export, played in place of the built-in levels.json
+    pub(crate) levels: Option<SavedLevels>,
+}
+
+// Best endless score on one preset, with the run to race as a ghost
+#[turbo::serialize]
+#[derive(PartialEq)]
+pub(crate) struct HighScore {
+    pub(crate) preset: Preset,
+    pub(crate) score: u32,
+    pub(crate) replay: Option<ReplayFile>,
+}
+
+// Best rating and score on one campaign level
+#[turbo::serialize]
+#[derive(PartialEq)]
+pub(crate) struct LevelBest {
+    pub(crate) name: String,
+    pub(crate) stars: u32,
+    pub(crate) score: u32,
+}
+
+// Best score on one day's daily run
+#[turbo::serialize]
+#[derive(PartialEq, Default)]
+pub(crate) struct DailyBest {
+    pub(crate) day: u32,
+    pub(crate) score: u32,
+    pub(crate) replay: Option<ReplayFile>,
+}
+
+// Version 1 -> 2: campaign records
+fn add_level_bests(fields: &mut serde_json::Map<String, serde_json::Value>) {
+    fields.insert("level_bests".to_string(), serde_json::Value::Array(Vec::new()));
+}
+
+// Version 2 -> 3: daily run record
+fn add_daily_best(fields: &mut serde_json::Map<String, serde_json::Value>) {
+    fields.insert("daily".to_string(), serde_json::json!({ "day": 0, "score": 0 }));
+}
+
+// Version 3 -> 4: replays of the best runs, for ghost racing
+fn add_best_replays(fields: &mut serde_json::Map<String, serde_json::Value>) {
+    fields.insert("best_replay".to_string(), serde_json::Value::Null);
+    if let Some(daily) = fields.get_mut("daily").and_then(|daily| daily.as_object_mut()) {
+        daily.insert("replay".to_string(), serde_json::Value::Null);
+    }
+}
+
+// Version 4 -> 5: achievements
+fn add_achievements(fields: &mut serde_json::Map<String, serde_json::Value>) {
+    fields.insert("achievements".to_string(), serde_json::Value::Array(Vec::new()));
+}
+
+// Version 5 -> 6: missions and candy
+fn add_missions(fields: &mut serde_json::Map<String, serde_json::Value>) {
+    fields.insert("candy".to_string(), 0.into());
+    fields.insert("missions".to_string(), serde_json::Value::Array(Vec::new()));
+    fields.insert("mission_serial".to_string(), 0.into());
+}
+
+// Version 6 -> 7: shop upgrades
+// Stored replays gained run modifiers (none back then), and the magnet's reach moved into their tuning
+fn add_upgrades(fields: &mut serde_json::Map<String, serde_json::Value>) {
+    fields.insert("upgrades".to_string(), serde_json::Value::Array(Vec::new()));
+    upgrade_replays(fields, 5, |setup| {
+        let modifiers = serde_json::to_value(Modifiers::default()).unwrap_or_default();
+        setup.insert("modifiers".to_string(), modifiers);
+        if let Some(tuning) = setup.get_mut("tuning").and_then(|tuning| tuning.as_object_mut()) {
+            // Fixed at 120px before it was tunable
+            tuning.entry("magnet_radius").or_insert(120.0.into());
+        }
+    });
+}
+
+// Version 7 -> 8: cosmetics
+fn add_wardrobe(fields: &mut serde_json::Map<String, serde_json::Value>) {
+    let wardrobe = serde_json::to_value(Wardrobe::default()).unwrap_or_default();
+    fields.insert("wardrobe".to_string(), wardrobe);
+}
+
+// Version 8 -> 9: hearts per mode
+// Stored replays gained a heart count; older runs were all the classic single heart
+fn add_heart_rules(fields: &mut serde_json::Map<String, serde_json::Value>) {
+    let hearts = serde_json::to_value(HeartRules::default()).unwrap_or_default();
+    fields.insert("hearts".to_string(), hearts);
+    upgrade_replays(fields, 6, |setup| {
+        setup.insert("hearts".to_string(), 1.into());
+    });
+}
+
+// Version 9 -> 10: difficulty presets
+// The old high score becomes Normal's; stored replays gained a preset, and older runs were all on Normal
+fn add_presets(fields: &mut serde_json::Map<String, serde_json::Value>) {
+    upgrade_replays(fields, 7, |setup| {
+        setup.insert("preset".to_string(), serde_json::json!(Preset::Normal));
+    });
+    let score = fields.remove("high_score").unwrap_or_default();
+    let replay = fields.remove("best_replay").unwrap_or_default();
+    let normal = serde_json::json!({ "preset": Preset::Normal, "score": score, "replay": replay });
+    fields.insert("high_scores".to_string(), serde_json::Value::Array(vec![normal]));
+    fields.insert("preset".to_string(), serde_json::json!(Preset::Normal));
+}
+
+// Version 10 -> 11: replay fingerprints gained SIM_VERSION
+// A stored replay whose old fingerprint matches the built-in chunk library was recorded on simulation version 1
+fn add_sim_version(fields: &mut serde_json::Map<String, serde_json::Value>) {
+    let unversioned = replay::unversioned_sim_hash(levels::LEVELS_SOURCE);
+    let versioned = replay::sim_hash_at(1, levels::LEVELS_SOURCE);
+    for_each_replay(fields, |replay| {
+        let format = replay.get("format_version").and_then(|v| v.as_u64());
+        if format != Some(7) || replay.get("tuning_hash").and_then(|v| v.as_u64()) != Some(unversioned) {
+            return;
+        }
+        replay.remove("tuning_hash");
+        replay.insert("sim_hash".to_string(), versioned.into());
+        replay.insert("format_version".to_string(), 8.into());
+    });
+}
+
+// Version 11 -> 12: levels.json kept in the save
+fn add_saved_levels(fields: &mut serde_json::Map<String, serde_json::Value>) {
+    fields.insert("levels".to_string(), serde_json::Value::Null);
+}
+
+// Convert the stored replays from replay format `version - 1` to `version`
+// Only for format bumps that add a field to the run setup: `upgrade` fills it in with
+// the value every older run was played with, so the replay plays back exactly as recorded
+// Replays on any other format are left alone for `into_replay` to reject
+type SetupUpgrade = fn(&mut serde_json::Map<String, serde_json::Value>);
+fn upgrade_replays(fields: &mut serde_json::Map<String, serde_json::Value>, version: u16, upgrade: SetupUpgrade) {
+    for_each_replay(fields, |replay| {
+        if replay.get("format_version").and_then(|v| v.as_u64()) != Some(version as u64 - 1) {
+            return;
+        }
+        let Some(setup) = replay.get_mut("setup").and_then(|setup| setup.as_object_mut()) else {
+            return;
+        };
+        upgrade(setup);
+        replay.insert("format_version".to_string(), version.into());
+    });
+}
+
+// Every replay the save holds
+fn for_each_replay(
+    fields: &mut serde_json::Map<String, serde_json::Value>,
+    mut visit: impl FnMut(&mut serde_json::Map<String, serde_json::Value>),
+) {
+    let mut convert = |replay: Option<&mut serde_json::Value>| {
+        if let Some(replay) = replay.and_then(|replay| replay.as_object_mut()) {
+            visit(replay);
+        }
+    };
+    convert(fields.get_mut("best_replay"));
+    convert(fields.get_mut("daily").and_then(|daily| daily.get_mut("replay")));
+    if let Some(high_scores) = fields.get_mut("high_scores").and_then(|scores| scores.as_array_mut()) {
+        for high_score in high_scores {
+            convert(high_score.get_mut("replay"));
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub(crate) enum SaveError {
+    Corrupt(String),
+    TooNew { found: u64, supported: u64 },
+    Write(String),
+}
+
+impl std::fmt::Display for SaveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Corrupt(reason) => write!(f, "save data is corrupt: {reason}"),
+            Self::TooNew { found, supported } => write!(
+                f,
+                "save data is version {found} but this build only understands up to version {supported}"
+            ),
+            Self::Write(reason) => write!(f, "could not write save data: {reason}"),
+        }
+    }
+}
+
+// Where save bytes live
+pub(crate) trait SaveBackend {
+    fn read(&mut self) -> Option<Vec<u8>>;
+    fn write(&mut self, bytes: &[u8]) -> Result<(), SaveError>;
+}
+
+// Turbo's per-game local storage
+pub(crate) struct LocalStorage;
+
+impl SaveBackend for LocalStorage {
+    fn read(&mut self) -> Option<Vec<u8>> {
+        local::load().ok().filter(|bytes| !bytes.is_empty())
+    }
+
+    fn write(&mut self, bytes: &[u8]) -> Result<(), SaveError> {
+        local::save(bytes)
+            .map(|_| ())
+            .map_err(|code| SaveError::Write(format!("local storage error code {code}")))
+    }
+}
+
+impl SaveData {
+    // Missing save = fresh defaults
+    pub(crate) fn load(backend: &mut impl SaveBackend) -> Result<Self, SaveError> {
+        match backend.read() {
+            Some(bytes) => Self::from_bytes(&bytes),
+            None => Ok(Self::default()),
+        }
+    }
+
+    pub(crate) fn store(&self, backend: &mut impl SaveBackend) -> Result<(), SaveError> {
+        backend.write(&self.to_bytes())
+    }
+
+    // Stored as JSON with a top-level "version" so old saves can be migrated field by field
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut value = serde_json::to_value(self).unwrap_or_default();
+        if let Some(fields) = value.as_object_mut() {
+            fields.insert("version".to_string(), SAVE_VERSION.into());
+        }
+        value.to_string().into_bytes()
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, SaveError> {
+        let mut value: serde_json::Value =
+            serde_json::from_slice(bytes).map_err(|err| SaveError::Corrupt(err.to_string()))?;
+        let fields = value
+            .as_object_mut()
+            .ok_or_else(|| SaveError::Corrupt("expected a JSON object".to_string()))?;
+        let version = fields
+            .remove("version")
+            .and_then(|v| v.as_u64())
+            .ok_or_else(|| SaveError::Corrupt("missing version".to_string()))?;
+        if version == 0 {
+            return Err(SaveError::Corrupt("invalid version 0".to_string()));
+        }
+        if version > SAVE_VERSION {
+            return Err(SaveError::TooNew {
+                found: version,
+                supported: SAVE_VERSION,
+            });
+        }
+        for migration in &MIGRATIONS[(version - 1) as usize..] {
+            migration(fields);
+        }
+        serde_json::from_value(value).map_err(|err| SaveError::Corrupt(err.to_string()))
+    }
+
+    // Fold a finished run into the lifetime stats
+    pub(crate) fn record_run(&mut self, result: &RunResult, distance: f32) {
+        self.best_combo = self.best_combo.max(result.max_combo);
+        self.total_distance += distance;
+        self.total_stars += result.stars_collected;
+        // A candy for every star, on top of mission rewards
+        self.candy += result.stars_collected;
+        self.runs_played += 1;
+    }
+
+    // Best endless score on `preset` (0 if it hasn't been played yet)
+    pub(crate) fn high_score(&self, preset: Preset) -> u32 {
+        self.high_scores.iter().find(|best| best.preset == preset).map_or(0, |best| best.score)
+    }
+
+    // Only endless runs count towards the high scores
+    pub(crate) fn record_high_score(&mut self, preset: Preset, score: u32, replay: &Replay) {
+        if score <= self.high_score(preset) {
+            return;
+        }
+        let best = HighScore {
+            preset,
+            score,
+            replay: Some(ReplayFile::from_replay(replay)),
+        };
+        match self.high_scores.iter_mut().find(|best| best.preset == preset) {
+            Some(old) => *old = best,
+            None => self.high_scores.push(best),
+        }
+    }
+
+    // Best score on `day`'s daily run (0 if it hasn't been played yet)
+    pub(crate) fn daily_best(&self, day: u32) -> u32 {
+        if self.daily.day == day {
+            self.daily.score
+        } else {
+            0
+        }
+    }
+
+    // A new day's first run replaces the old record; racing an older day's ghost leaves it alone
+    pub(crate) fn record_daily(&mut self, day: u32, score: u32, replay: &Replay) {
+        if day > self.daily.day || (day == self.daily.day && score > self.daily.score) {
+            self.daily = DailyBest {
+                day,
+                score,
+                replay: Some(ReplayFile::from_replay(replay)),
+            };
+        }
+    }
+
+    pub(crate) fn level_best(&self, name: &str) -> Option<&LevelBest> {
+        self.level_bests.iter().find(|best| best.name == name)
+    }
+
+    // Keep the best rating and the best score for a cleared level (not necessarily from the same run)
+    pub(crate) fn record_level(&mut self, name: &str, stars: u32, score: u32) {
+        match self.level_bests.iter_mut().find(|best| best.name == name) {
+            Some(best) => {
+                best.stars = best.stars.max(stars);
+                best.score = best.score.max(score);
+            }
+            None => self.level_bests.push(LevelBest {
+                name: name.to_string(),
+                stars,
+                score,
+            }),
+        }
+    }
+
+    pub(crate) fn has_achievement(&self, id: &str) -> bool {
+        self.achievements.iter().any(|unlocked| unlocked == id)
+    }
+
+    // True if `id` wasn't unlocked before
+    pub(crate) fn unlock_achievement(&mut self, id: &str) -> bool {
+        if self.has_achievement(id) {
+            return false;
+        }
+        self.achievements.push(id.to_string());
+        true
+    }
+}
+
+impl GameState {
+    pub(crate) fn load_save(&mut self) {
+        self.save = SaveData::load(&mut LocalStorage).unwrap_or_else(|err| {
+            log!("[save] {err}, starting fresh");
+            SaveData::default()
+        });
+        // First run (or just migrated): roll the opening missions
+        if self.save.missions.is_empty() {
+            self.save.rotate_missions(&[]);
+        }
+        self.reset_missions();
+        self.save_loaded = true;
+        self.reload_levels();
+        self.refresh_ghosts();
+    }
+
+    pub(crate) fn write_save(&self) {
+        if let Err(err) = self.save.store(&mut LocalStorage) {
+            log!("[save] {err}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::replay::{decode_text, encode_text, ReplayError};
+    use crate::verify;
+
+    // A run recorded by the build that introduced replay format 4, the first one saves stored
+    const FORMAT_4_REPLAY: &str = include_str!("../testdata/replay-format-4.json");
+
+    struct Memory(Vec<u8>);
+
+    impl SaveBackend for Memory {
+        fn read(&mut self) -> Option<Vec<u8>> {
+            Some(self.0.clone())
+        }
+
+        fn write(&mut self, bytes: &[u8]) -> Result<(), SaveError> {
+            self.0 = bytes.to_vec();
+            Ok(())
+        }
+    }
+
+    // A version 4 save (the first with replays) holding `replay` as both of its records
+    fn version_4_save(replay: serde_json::Value) -> Memory {
+        let save = serde_json::json!({
+            "version": 4,
+            "high_score": 2470,
+            "best_combo": 9,
+            "total_distance": 0.0,
+            "total_stars": 3,
+            "runs_played": 1,
+            "best_replay": replay,
+            "level_bests": [],
+            "daily": { "day": 20000, "score": 2470, "replay": replay },
+        });
+        Memory(save.to_string().into_bytes())
+    }
+
+    #[test]
+    fn old_format_is_rejected_until_migrated() {
+        assert_eq!(
+            decode_text(FORMAT_4_REPLAY),
+            Err(ReplayError::UnsupportedFormat { found: 4, expected: 8 })
+        );
+    }
+
+    // Re-stamp a replay from simulation version 1 as this build's, for a run no later simulation change touches
+    // (the fixture is a solo endless run, and the changes since were to co-op)
+    fn on_this_simulation(file: &ReplayFile) -> Replay {
+        let mut fields = serde_json::to_value(file).unwrap();
+        assert_eq!(fields["sim_hash"], serde_json::json!(replay::sim_hash_at(1, levels::LEVELS_SOURCE)));
+        fields["sim_hash"] = serde_json::json!(replay::sim_hash());
+        serde_json::from_value::<ReplayFile>(fields).unwrap().into_replay().unwrap()
+    }
+
+    #[test]
+    fn migrated_replays_play_back_as_recorded() {
+        let replay = serde_json::from_str(FORMAT_4_REPLAY).unwrap();
+        let save = SaveData::load(&mut version_4_save(replay)).unwrap();
+        assert_eq!(save.high_score(Preset::Normal), 2470);
+
+        // Stamped with the simulation version it was recorded on, which this build no longer plays
+        let best = save.high_scores[0].replay.clone().unwrap();
+        assert!(matches!(best.clone().into_replay(), Err(ReplayError::SimMismatch { .. })));
+        let best = on_this_simulation(&best);
+        assert_eq!(best, on_this_simulation(save.daily.replay.as_ref().unwrap()));
+        let setup = best.setup();
+        assert_eq!(setup.seed, 2024);
+        assert_eq!(setup.modifiers, Modifiers::default());
+        assert_eq!(setup.hearts, 1);
+        assert_eq!(setup.preset, Preset::Normal);
+        assert_eq!(setup.tuning.magnet_radius, 120.0);
+
+        // Same inputs, same outcome: the checkpoints the old build recorded still line up
+        let claimed = best.checkpoints().last().unwrap().result;
+        assert!(verify::verify(&best, claimed).is_valid());
+
+        // And the converted replay is exactly what this build writes for that run
+        assert_eq!(decode_text(&encode_text(&best)), Ok(best));
+    }
+
+    #[test]
+    fn replays_on_other_formats_are_left_alone() {
+        let mut replay: serde_json::Value = serde_json::from_str(FORMAT_4_REPLAY).unwrap();
+        replay["format_version"] = 3.into();
+        let save = SaveData::load(&mut version_4_save(replay)).unwrap();
+        let best = save.high_scores[0].replay.clone().unwrap();
+        assert_eq!(
+            best.into_replay(),
+            Err(ReplayError::UnsupportedFormat { found: 3, expected: 8 })
+        );
+    }
+}
@@ -0,0 +1,267 @@
+use turbo::*;
+
+use crate::draw::DrawList;
+use crate::save::SaveData;
+use crate::tuning::Tuning;
+use crate::{GameMode, GameState, COLOR_AURORA_GREEN, COLOR_AURORA_RED, COLOR_STAR, COLOR_TEXT, COLOR_UI_BG};
+
+pub(crate) struct Upgrade {
+    // Stored in the save, so never rename one
+    pub(crate) id: &'static str,
+    pub(crate) name: &'static str,
+    pub(crate) description: &'static str,
+    // Candy for each level in turn; the upgrade maxes out after the last one
+    costs: &'static [u32],
+    effect: Effect,
+}
+
+// What one level of an upgrade adds
+enum Effect {
+    ShieldFrames(u32),
+    MagnetRadius(f32),
+    StarValue(u32),
+    StartingShield,
+}
+
+pub(crate) const UPGRADES: &[Upgrade] = &[
+    Upgrade {
+        id: "shield_time",
+        name: "Thick Ice",
+        description: "Shields last 2s longer",
+        costs: &[60, 120, 240],
+        effect: Effect::ShieldFrames(120),
+    },
+    Upgrade {
+        id: "magnet_radius",
+        name: "Lodestone",
+        description: "Magnet reaches 30px further",
+        costs: &[50, 100, 200],
+        effect: Effect::MagnetRadius(30.0),
+    },
+    Upgrade {
+        id: "star_value",
+        name: "Polished Stars",
+        description: "Stars are worth 10 more",
+        costs: &[80, 160, 320],
+        effect: Effect::StarValue(10),
+    },
+    Upgrade {
+        id: "starting_shield",
+        name: "Head Start",
+        description: "Start every run with a shield",
+        costs: &[250],
+        effect: Effect::StartingShield,
+    },
+];
+
+// Permanent upgrades as they apply to a run, on top of its tuning
+// Part of the RunSetup, so replays and ghosts play back with the upgrades they were recorded with
+#[turbo::serialize]
+#[derive(Copy, PartialEq, Default)]
+pub(crate) struct Modifiers {
+    pub(crate) shield_frames: u32,
+    pub(crate) magnet_radius: f32,
+    pub(crate) star_value: u32,
+    pub(crate) starting_shield: bool,
+}
+
+impl Modifiers {
+    // The tuning a run actually plays with
+    pub(crate) fn apply(&self, tuning: Tuning) -> Tuning {
+        Tuning {
+            shield_frames: tuning.shield_frames + self.shield_frames,
+            magnet_radius: tuning.magnet_radius + self.magnet_radius,
+            star_value: tuning.star_value + self.star_value,
+            ..tuning
+        }
+    }
+}
+
+// Levels bought of one upgrade
+#[turbo::serialize]
+#[derive(PartialEq)]
+pub(crate) struct OwnedUpgrade {
+    pub(crate) id: String,
+    pub(crate) level: u32,
+}
+
+impl SaveData {
+    pub(crate) fn upgrade_level(&self, id: &str) -> u32 {
+        self.upgrades.iter().find(|owned| owned.id == id).map_or(0, |owned| owned.level)
+    }
+
+    // Everything bought so far, folded into run modifiers
+    pub(crate) fn modifiers(&self) -> Modifiers {
+        let mut modifiers = Modifiers::default();
+        for upgrade in UPGRADES {
+            let level = self.upgrade_level(upgrade.id);
+            match upgrade.effect {
+                Effect::ShieldFrames(frames) => modifiers.shield_frames += frames * level,
+                Effect::MagnetRadius(radius) => modifiers.magnet_radius += radius * level as f32,
+                Effect::StarValue(value) => modifiers.star_value += value * level,
+                Effect::StartingShield => modifiers.starting_shield |= level > 0,
+            }
+        }
+        modifiers
+    }
+
+    // Price of the next level, or None once it's maxed out
+    fn next_cost(&self, upgrade: &Upgrade) -> Option<u32> {
+        upgrade.costs.get(self.upgrade_level(upgrade.id) as usize).copied()
+    }
+
+    // Spend candy on the next level; false if it's maxed out or unaffordable
+    fn buy(&mut self, upgrade: &Upgrade) -> bool {
+        let Some(cost) = self.next_cost(upgrade).filter(|&cost| cost <= self.candy) else {
+            return false;
+        };
+        self.candy -= cost;
+        match self.upgrades.iter_mut().find(|owned| owned.id == upgrade.id) {
+            Some(owned) => owned.level += 1,
+            None => self.upgrades.push(OwnedUpgrade {
+                id: upgrade.id.to_string(),
+                level: 1,
+            }),
+        }
+        true
+    }
+}
+
+impl GameState {
+    pub(crate) fn open_shop(&mut self) {
+        self.shop_cursor = 0;
+        self.mode = GameMode::Shop;
+    }
+
+    pub(crate) fn update_shop(&mut self, gp: &gamepad::Gamepad) {
+        self.update_backdrop();
+
+        if gp.up.just_pressed() {
+            self.shop_cursor = self.shop_cursor.saturating_sub(1);
+        }
+        if gp.down.just_pressed() && self.shop_cursor + 1 < UPGRADES.len() {
+            self.shop_cursor += 1;
+        }
+        if gp.a.just_pressed() {
+            if self.save.buy(&UPGRADES[self.shop_cursor]) {
+                audio::play("collect-powerup");
+                self.write_save();
+            } else {
+                audio::play("shield-break");
+            }
+        }
+        if gp.left.just_pressed() {
+            self.open_achievements();
+        }
+        if gp.right.just_pressed() {
+            self.open_wardrobe();
+        }
+        if gp.b.just_pressed() || gp.start.just_pressed() {
+            self.mode = GameMode::Title;
+        }
+    }
+
+    pub(crate) fn render_shop(&self, draw: &mut DrawList) {
+        draw.text("SHOP", 106, 10, "large", COLOR_AURORA_GREEN);
+        draw.text(format!("CANDY: {}", self.save.candy), 100, 26, "small", COLOR_STAR);
+
+        for (i, upgrade) in UPGRADES.iter().enumerate() {
+            let y = 38 + i as i32 * 20;
+            if i == self.shop_cursor {
+                draw.rect(16, y - 2, 224, 18, COLOR_UI_BG);
+                draw.text(">", 20, y + 3, "small", COLOR_STAR);
+            }
+            let level = self.save.upgrade_level(upgrade.id);
+            let pips: String = (0..upgrade.costs.len()).map(|pip| if pip < level as usize { '#' } else { '-' }).collect();
+            draw.text(format!("{} {pips}", upgrade.name.to_uppercase()), 28, y, "small", COLOR_TEXT);
+            draw.text(upgrade.description, 28, y + 8, "small", 0xaaaaaaff);
+            let (price, color) = match self.save.next_cost(upgrade) {
+                Some(cost) if cost <= self.save.candy => (format!("{cost}"), COLOR_STAR),
+                Some(cost) => (format!("{cost}"), COLOR_AURORA_RED),
+                None => ("MAX".to_string(), COLOR_AURORA_GREEN),
+            };
+            draw.text(price, 212, y + 3, "small", color);
+        }
+
+        draw.text("[LEFT] Achievements  [RIGHT] Wardrobe", 34, 122, "small", 0xaaaaaaff);
+        draw.text("[A] Buy  [B] Back", 84, 132, "small", 0xaaaaaaff);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RunKind;
+
+    fn upgrade(id: &str) -> &'static Upgrade {
+        UPGRADES.iter().find(|upgrade| upgrade.id == id).unwrap()
+    }
+
+    #[test]
+    fn each_level_costs_more_until_the_last() {
+        let thick_ice = upgrade("shield_time");
+        let mut save = SaveData {
+            candy: 1000,
+            ..SaveData::default()
+        };
+        for (level, spent) in [(1, 60), (2, 180), (3, 420)] {
+            assert!(save.buy(thick_ice));
+            assert_eq!(save.upgrade_level("shield_time"), level);
+            assert_eq!(save.candy, 1000 - spent);
+        }
+
+        // Maxed out: nothing more to buy, and no candy taken
+        assert_eq!(save.next_cost(thick_ice), None);
+        assert!(!save.buy(thick_ice));
+        assert_eq!(save.upgrade_level("shield_time"), 3);
+        assert_eq!(save.candy, 580);
+    }
+
+    #[test]
+    fn short_of_candy_buys_nothing() {
+        let mut save = SaveData {
+            candy: 59,
+            ..SaveData::default()
+        };
+        assert!(!save.buy(upgrade("shield_time")));
+        assert_eq!(save.candy, 59);
+        assert!(save.upgrades.is_empty());
+
+        save.candy = 60;
+        assert!(save.buy(upgrade("shield_time")));
+        assert_eq!(save.candy, 0);
+    }
+
+    #[test]
+    fn upgrades_change_the_run() {
+        let mut state = GameState::new();
+        state.save.candy = 10_000;
+        for id in ["shield_time", "magnet_radius", "star_value", "starting_shield"] {
+            assert!(state.save.buy(upgrade(id)));
+        }
+        assert!(state.save.buy(upgrade("star_value")));
+
+        let modifiers = state.save.modifiers();
+        assert_eq!(
+            modifiers,
+            Modifiers {
+                shield_frames: 120,
+                magnet_radius: 30.0,
+                star_value: 20,
+                starting_shield: true,
+            }
+        );
+
+        let base = state.tuning_config;
+        let tuning = modifiers.apply(base);
+        assert_eq!(tuning.shield_frames, base.shield_frames + 120);
+        assert_eq!(tuning.magnet_radius, base.magnet_radius + 30.0);
+        assert_eq!(tuning.star_value, base.star_value + 20);
+        assert_eq!(tuning.gravity, base.gravity);
+
+        state.play_run(state.own_setup(RunKind::Endless, 3));
+        assert_eq!(state.tuning, tuning);
+        assert!(state.player().has_shield);
+        assert_eq!(state.player().shield_timer, tuning.shield_frames);
+    }
+}
@@ -0,0 +1,185 @@
+use turbo::*;
+
+use crate::assets::{self, check_range, AssetError};
+use crate::GameState;
+
+// Gameplay balance, kept next to turbo.toml and compiled in (see assets.rs)
+pub(crate) const TUNING_PATH: &str = "tuning.json";
+pub(crate) const TUNING_SOURCE: &str = include_str!("../tuning.json");
+
+// Every number a balance pass is likely to touch
+// A run keeps the tuning it started with, and its replay records it
+#[turbo::serialize]
+#[derive(Copy, PartialEq)]
+#[serde(try_from = "RawTuning")]
+pub(crate) struct Tuning {
+    // Player physics (px per frame)
+    pub(crate) gravity: f32,
+    pub(crate) jump_power: f32,
+    pub(crate) min_jump_power: f32,
+    // Frames a held jump keeps adding lift
+    pub(crate) max_jump_hold: u32,
+    pub(crate) scroll_speed_base: f32,
+    // Frames of open ground between chunks
+    pub(crate) obstacle_spawn_interval: u32,
+    // Power-up durations (frames)
+    pub(crate) shield_frames: u32,
+    pub(crate) slow_mo_frames: u32,
+    pub(crate) magnet_frames: u32,
+    pub(crate) double_points_frames: u32,
+    // How close a star has to be for the magnet to pull it in (px)
+    pub(crate) magnet_radius: f32,
+    // Points
+    pub(crate) star_value: u32,
+    pub(crate) miss_penalty: u32,
+}
+
+impl Default for Tuning {
+    fn default() -> Self {
+        Self {
+            gravity: 0.8,
+            jump_power: -9.5,
+            min_jump_power: -5.0,
+            max_jump_hold: 12,
+            scroll_speed_base: 3.0,
+            obstacle_spawn_interval: 90,
+            shield_frames: 600,   // 10 seconds at 60fps
+            slow_mo_frames: 600,  // 10 seconds at 60fps
+            magnet_frames: 420,   // 7 seconds
+            double_points_frames: 300,
+            magnet_radius: 120.0,
+            star_value: 50,
+            miss_penalty: 25,
+        }
+    }
+}
+
+// Parse and validate tuning.json
+pub(crate) fn parse(source: &str) -> Result<Tuning, AssetError> {
+    assets::parse(TUNING_PATH, source)
+}
+
+// The built-in tuning.json, or the defaults if it doesn't validate
+pub(crate) fn built_in() -> Tuning {
+    parse(TUNING_SOURCE).unwrap_or_default()
+}
+
+pub(crate) fn source_hash() -> u64 {
+    assets::fingerprint(TUNING_SOURCE)
+}
+
+// File-side shape; anything left out keeps its default
+#[turbo::serialize]
+#[serde(default, deny_unknown_fields)]
+struct RawTuning {
+    gravity: f32,
+    jump_power: f32,
+    min_jump_power: f32,
+    max_jump_hold: u32,
+    scroll_speed_base: f32,
+    obstacle_spawn_interval: u32,
+    shield_frames: u32,
+    slow_mo_frames: u32,
+    magnet_frames: u32,
+    double_points_frames: u32,
+    magnet_radius: f32,
+    star_value: u32,
+    miss_penalty: u32,
+}
+
+impl Default for RawTuning {
+    fn default() -> Self {
+        let tuning = Tuning::default();
+        Self {
+            gravity: tuning.gravity,
+            jump_power: tuning.jump_power,
+            min_jump_power: tuning.min_jump_power,
+            max_jump_hold: tuning.max_jump_hold,
+            scroll_speed_base: tuning.scroll_speed_base,
+            obstacle_spawn_interval: tuning.obstacle_spawn_interval,
+            shield_frames: tuning.shield_frames,
+            slow_mo_frames: tuning.slow_mo_frames,
+            magnet_frames: tuning.magnet_frames,
+            double_points_frames: tuning.double_points_frames,
+            magnet_radius: tuning.magnet_radius,
+            star_value: tuning.star_value,
+            miss_penalty: tuning.miss_penalty,
+        }
+    }
+}
+
+impl TryFrom<RawTuning> for Tuning {
+    type Error = String;
+
+    fn try_from(raw: RawTuning) -> Result<Self, String> {
+        // Velocities are negative going up; each held frame adds (jump_power - min_jump_power) / max_jump_hold,
+        // so `min_jump_power` has to be the weaker of the two
+        let jump_power = check_range("jump_power", raw.jump_power, -30.0, -0.5)?;
+        Ok(Self {
+            gravity: check_range("gravity", raw.gravity, 0.05, 5.0)?,
+            jump_power,
+            min_jump_power: check_range("min_jump_power", raw.min_jump_power, jump_power, 0.0)?,
+            max_jump_hold: check_range("max_jump_hold", raw.max_jump_hold, 1, 60)?,
+            scroll_speed_base: check_range("scroll_speed_base", raw.scroll_speed_base, 0.5, 12.0)?,
+            obstacle_spawn_interval: check_range("obstacle_spawn_interval", raw.obstacle_spawn_interval, 0, 600)?,
+            shield_frames: check_range("shield_frames", raw.shield_frames, 1, 3600)?,
+            slow_mo_frames: check_range("slow_mo_frames", raw.slow_mo_frames, 1, 3600)?,
+            magnet_frames: check_range("magnet_frames", raw.magnet_frames, 1, 3600)?,
+            double_points_frames: check_range("double_points_frames", raw.double_points_frames, 1, 3600)?,
+            magnet_radius: check_range("magnet_radius", raw.magnet_radius, 0.0, 256.0)?,
+            star_value: check_range("star_value", raw.star_value, 0, 1000)?,
+            miss_penalty: check_range("miss_penalty", raw.miss_penalty, 0, 1000)?,
+        })
+    }
+}
+
+impl GameState {
+    // (Re)load the built-in tuning.json, keeping the current tuning if it doesn't validate
+    // Takes effect from the next run so a run (and its replay) sticks to one tuning
+    pub(crate) fn reload_tuning(&mut self) {
+        self.tuning_source_hash = source_hash();
+        match parse(TUNING_SOURCE) {
+            Ok(tuning) => self.tuning_config = tuning,
+            Err(err) => log!("[tuning] {err}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shipped_tuning_parses() {
+        assert!(parse(TUNING_SOURCE).is_ok());
+    }
+
+    #[test]
+    fn left_out_keys_keep_their_defaults() {
+        assert_eq!(parse("{}"), Ok(Tuning::default()));
+        let tuning = parse(r#"{ "star_value": 80 }"#).unwrap();
+        assert_eq!(tuning.star_value, 80);
+        assert_eq!(tuning.gravity, Tuning::default().gravity);
+    }
+
+    #[test]
+    fn out_of_range_fields_are_named() {
+        // Checked once the whole object is in, so there's no line to point at; the field says where
+        let err = parse("{\n  \"gravity\": 9.0\n}").unwrap_err();
+        assert_eq!(err.to_string(), "tuning.json: `gravity` must be between 0.05 and 5, got 9");
+
+        let err = parse(r#"{ "max_jump_hold": 0 }"#).unwrap_err();
+        assert_eq!(err.message, "`max_jump_hold` must be between 1 and 60, got 0");
+
+        // The weakest jump can't be stronger than the full one
+        let err = parse(r#"{ "jump_power": -9.5, "min_jump_power": -12.0 }"#).unwrap_err();
+        assert_eq!(err.message, "`min_jump_power` must be between -9.5 and 0, got -12");
+    }
+
+    #[test]
+    fn unknown_fields_are_rejected() {
+        let err = parse("{\n  \"gravty\": 0.8\n}").unwrap_err();
+        assert_eq!((err.path, err.line), (TUNING_PATH, 2));
+        assert!(err.message.starts_with("unknown field `gravty`"), "{}", err.message);
+    }
+}
@@ -0,0 +1,199 @@
+use crate::replay::{Replay, RunResult};
+use crate::{GameMode, GameState};
+
+// Outcome of re-simulating a replay against a claimed result
+#[derive(Debug, PartialEq)]
+pub(crate) struct Verification {
+    pub(crate) actual: RunResult,
+    pub(crate) frames: u32,
+    pub(crate) divergence: Option<Divergence>,
+}
+
+impl Verification {
+    pub(crate) fn is_valid(&self) -> bool {
+        self.divergence.is_none()
+    }
+}
+
+// First point where the claim and the simulation disagree
+#[derive(Debug, PartialEq)]
+pub(crate) struct Divergence {
+    pub(crate) frame: u32,
+    pub(crate) kind: DivergenceKind,
+}
+
+#[derive(Debug, PartialEq)]
+pub(crate) enum DivergenceKind {
+    // Recorded checkpoint or final claim doesn't match the simulation
+    Stats { claimed: RunResult, actual: RunResult },
+    // Simulated run ended before the recording did
+    RunLength { recorded: u32, simulated: u32 },
+    // Recording stopped while the simulated run was still going
+    Unfinished,
+}
+
+// Re-run `replay` headlessly and check it produces `claimed`
+// Checkpoints stored in the replay narrow a mismatch down to the frame it first appears
+pub(crate) fn verify(replay: &Replay, claimed: RunResult) -> Verification {
+    let mut sim = GameState::new();
+    sim.start_run(replay.setup());
+
+    let mut checkpoints = replay.checkpoints().iter().peekable();
+    let mut divergence = None;
+
+    while sim.mode == GameMode::Playing {
+        let Some(inputs) = replay.get(sim.run_frames) else {
+            break;
+        };
+        sim.step(&inputs);
+
+        while let Some(checkpoint) = checkpoints.next_if(|c| c.frame <= sim.run_frames) {
+            let actual = sim.run_result();
+            if divergence.is_none() && checkpoint.frame == sim.run_frames && checkpoint.result != actual {
+                divergence = Some(Divergence {
+                    frame: checkpoint.frame,
+                    kind: DivergenceKind::Stats {
+                        claimed: checkpoint.result,
+                        actual,
+                    },
+                });
+            }
+        }
+    }
+
+    let actual = sim.run_result();
+    if divergence.is_none() && sim.mode == GameMode::Playing {
+        divergence = Some(Divergence {
+            frame: sim.run_frames,
+            kind: DivergenceKind::Unfinished,
+        });
+    }
+    if divergence.is_none() && sim.run_frames != replay.frames() {
+        divergence = Some(Divergence {
+            frame: sim.run_frames,
+            kind: DivergenceKind::RunLength {
+                recorded: replay.frames(),
+                simulated: sim.run_frames,
+            },
+        });
+    }
+    if divergence.is_none() && actual != claimed {
+        divergence = Some(Divergence {
+            frame: sim.run_frames,
+            kind: DivergenceKind::Stats { claimed, actual },
+        });
+    }
+
+    Verification {
+        actual,
+        frames: sim.run_frames,
+        divergence,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use turbo::*;
+
+    use super::*;
+    use crate::replay::{decode_text, encode_text};
+    use crate::tests::record;
+    use crate::RunKind;
+
+    // A finished run and its recording
+    fn finished_run() -> (Replay, RunResult) {
+        let state = record(RunKind::Endless, 9, 20_000);
+        assert_eq!(state.mode, GameMode::GameOver);
+        (state.replay.clone(), state.run_result())
+    }
+
+    // Edit a replay through its text form
+    fn edited(replay: &Replay, edit: impl FnOnce(&mut serde_json::Value)) -> Replay {
+        let mut value: serde_json::Value = serde_json::from_str(&encode_text(replay)).unwrap();
+        edit(&mut value);
+        decode_text(&value.to_string()).unwrap()
+    }
+
+    #[test]
+    fn honest_run_verifies() {
+        let (replay, result) = finished_run();
+        let verification = verify(&replay, result);
+        assert!(verification.is_valid());
+        assert_eq!(verification.actual, result);
+        assert_eq!(verification.frames, replay.frames());
+    }
+
+    #[test]
+    fn forged_score_is_caught_at_the_end() {
+        let (replay, result) = finished_run();
+        let claimed = RunResult {
+            score: result.score + 100,
+            ..result
+        };
+        let verification = verify(&replay, claimed);
+        assert_eq!(
+            verification.divergence,
+            Some(Divergence {
+                frame: replay.frames(),
+                kind: DivergenceKind::Stats { claimed, actual: result },
+            })
+        );
+    }
+
+    #[test]
+    fn tampered_checkpoint_is_pinpointed() {
+        let (replay, result) = finished_run();
+        let tampered = &replay.checkpoints()[2];
+        let forged = edited(&replay, |value| {
+            let score = &mut value["checkpoints"][2]["result"]["score"];
+            *score = (score.as_u64().unwrap() + 500).into();
+        });
+        let verification = verify(&forged, result);
+        assert_eq!(
+            verification.divergence,
+            Some(Divergence {
+                frame: tampered.frame,
+                kind: DivergenceKind::Stats {
+                    claimed: RunResult {
+                        score: tampered.result.score + 500,
+                        ..tampered.result
+                    },
+                    actual: tampered.result,
+                },
+            })
+        );
+    }
+
+    #[test]
+    fn padded_recording_is_caught() {
+        let (replay, result) = finished_run();
+        let padded = edited(&replay, |value| {
+            value["frames"] = (replay.frames() + 30).into();
+            let inputs = value["inputs"].as_array_mut().unwrap();
+            inputs.push(serde_json::json!({ "input": 0, "frames": 30 }));
+        });
+        assert_eq!(
+            verify(&padded, result).divergence,
+            Some(Divergence {
+                frame: replay.frames(),
+                kind: DivergenceKind::RunLength {
+                    recorded: replay.frames() + 30,
+                    simulated: replay.frames(),
+                },
+            })
+        );
+    }
+
+    #[test]
+    fn recording_that_stops_mid_run_is_unfinished() {
+        let state = record(RunKind::Endless, 9, 120);
+        assert_eq!(state.mode, GameMode::Playing);
+        assert_eq!(
+            verify(&state.replay, state.run_result()).divergence,
+            Some(Divergence {
+                frame: 120,
+                kind: DivergenceKind::Unfinished,
+            })
+        );
+    }
+}
@@ -0,0 +1,77 @@
+use crate::draw::DrawList;
+use crate::{GameState, COLOR_AURORA_GREEN, COLOR_STAR, COLOR_TEXT, COLOR_UI_BG};
+
+// P1 tag and HUD colour, then P2
+pub(crate) const PLAYER_COLORS: [u32; 2] = [0x66ccffff, 0xff8866ff];
+
+impl GameState {
+    // Index of the player who won a versus run, None on a draw
+    // The last reindeer standing wins; if both went down on the same frame the higher score takes it
+    pub(crate) fn versus_winner(&self) -> Option<usize> {
+        let alive: Vec<usize> = (0..self.players.len()).filter(|&i| self.players[i].alive).collect();
+        if let [winner] = alive[..] {
+            return Some(winner);
+        }
+        let [one, two] = &self.players[..] else {
+            return None;
+        };
+        match one.score.cmp(&two.score) {
+            std::cmp::Ordering::Greater => Some(0),
+            std::cmp::Ordering::Less => Some(1),
+            std::cmp::Ordering::Equal => None,
+        }
+    }
+
+    // Player two's score, combo and power-ups, top right
+    pub(crate) fn render_rival_hud(&self, draw: &mut DrawList) {
+        let Some(rival) = self.players.get(1) else {
+            return;
+        };
+        draw.rect(132, 4, 120, 34, COLOR_UI_BG);
+        draw.text(format!("P2: {}", rival.score), 136, 8, "small", PLAYER_COLORS[1]);
+        self.render_hearts(draw, rival, 204, 8);
+        if rival.combo > 0 {
+            draw.text(format!("COMBO x{}", rival.combo), 136, 18, "small", crate::combo_color(rival.combo));
+        }
+        let mut powerups = Vec::new();
+        if rival.shield_timer > 0 {
+            powerups.push(format!("SH {}", rival.shield_timer / 60 + 1));
+        }
+        if rival.slow_mo_timer > 0 {
+            powerups.push(format!("SL {}", rival.slow_mo_timer / 60 + 1));
+        }
+        if rival.double_points_timer > 0 {
+            powerups.push(format!("2x {}", rival.double_points_timer / 60));
+        }
+        if rival.magnet_timer > 0 {
+            powerups.push(format!("MAG {}", rival.magnet_timer / 60));
+        }
+        draw.text(powerups.join(" "), 136, 28, "small", COLOR_AURORA_GREEN);
+    }
+
+    pub(crate) fn render_winner(&self, draw: &mut DrawList) {
+        self.render_frozen_run(draw);
+
+        draw.rect(0, 0, 256, 144, 0x00000099);
+        draw.rect(28, 26, 200, 96, COLOR_UI_BG);
+        match self.versus_winner() {
+            Some(winner) => {
+                draw.text(format!("PLAYER {} WINS!", winner + 1), 58, 32, "large", PLAYER_COLORS[winner])
+            }
+            None => draw.text("DRAW!", 104, 32, "large", COLOR_TEXT),
+        }
+
+        for (i, player) in self.players.iter().enumerate() {
+            let x = 40 + i as i32 * 96;
+            draw.text(format!("P{}", i + 1), x, 52, "medium", PLAYER_COLORS[i]);
+            draw.text(format!("SCORE: {}", player.score), x, 66, "small", COLOR_STAR);
+            draw.text(format!("COMBO: {}x", player.max_combo), x, 76, "small", COLOR_AURORA_GREEN);
+            draw.text(format!("STARS: {}", player.stars_collected), x, 86, "small", COLOR_STAR);
+        }
+
+        if (self.frame / 30).is_multiple_of(2) {
+            draw.text("[SPACE] Rematch", 78, 103, "small", COLOR_TEXT);
+        }
+        draw.text("[DOWN] Watch Replay", 68, 112, "small", 0xaaaaaaff);
+    }
+}